@@ -0,0 +1,2327 @@
+//! Terminal frontend.
+//!
+//! A full-screen version of the egui app for terminals without a display
+//! server, so a game works over SSH. It is laid out like the window: a
+//! status bar along the top, the side panel's navigation down the left, the
+//! current screen in the middle and the message log underneath. Every
+//! action goes through the engine, so games play out exactly as they do in
+//! the window, and saves, content packs, replays and high scores are shared
+//! with it.
+
+use dopewars_gui::game::replay;
+use dopewars_gui::game::save::{self, AUTOSAVE_FILE, MANUAL_SAVE_FILE};
+use dopewars_gui::game::scores::{HighScore, HighScores, HIGH_SCORES_FILE};
+use dopewars_gui::game::{
+    describe_problems, Action, ContentPack, Difficulty, GameEngine, GameError, GameRules,
+    GameState, PoliceState, Service, StashUpgrade, BARRED_AFTER_DAYS, BROKER_CUT_PERCENT,
+    ENFORCERS_AFTER_DAYS, MINIMUM_PAYMENT_PERCENT, TERM_DAYS,
+};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{
+    Bar, BarChart, BarGroup, Block, List, ListItem, ListState, Paragraph, Wrap,
+};
+use ratatui::{DefaultTerminal, Frame};
+use std::io;
+use std::path::PathBuf;
+
+const SIDE_PANEL_WIDTH: u16 = 24;
+/// Height of the message log, borders included.
+const LOG_HEIGHT: u16 = 7;
+/// Height of the price chart, title included.
+const CHART_HEIGHT: u16 = 12;
+/// Wide enough for a five figure price.
+const CHART_BAR_WIDTH: u16 = 7;
+/// Lines moved by Page Up and Page Down.
+const SCROLL_STEP: u16 = 5;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Screen {
+    Main,
+    Buy,
+    Sell,
+    Travel,
+    Weapons,
+    StashHouses,
+    StashUpgrades,
+    Fronts,
+    LoanShark,
+    Bank,
+    Hospital,
+    PriceChart,
+    News,
+    NetWorth,
+    NewGame,
+    Rules,
+    HighScores,
+}
+
+/// What picking a row or pressing a hotkey does.
+#[derive(Clone)]
+enum Command {
+    Open(Screen),
+    Act(Action),
+    Ask(Ask),
+    Undo,
+    Save(&'static str),
+    Load(&'static str),
+    ToggleAutosave,
+    PlayAgain,
+    /// Play the finished game's seed again, in the same city by the same rules.
+    ReplaySeed,
+    StartNewGame,
+    SetDifficulty(Difficulty),
+    ToggleFireSale,
+    UseBuiltinPack,
+    Quit,
+}
+
+/// Something the player types in at the prompt.
+#[derive(Clone)]
+enum Ask {
+    Buy(String),
+    Sell(String),
+    Borrow,
+    Repay,
+    BankDeposit,
+    BankWithdraw,
+    StashDeposit(String),
+    StashWithdraw(String),
+    Launder,
+    Bribe,
+    Name,
+    Seed,
+    ContentPack,
+    /// A field of the rules editor, by its row.
+    Rule(usize),
+}
+
+impl Ask {
+    /// Whether the answer is an amount that goes straight to the engine.
+    fn is_amount(&self) -> bool {
+        !matches!(
+            self,
+            Ask::Name | Ask::Seed | Ask::ContentPack | Ask::Rule(_)
+        )
+    }
+}
+
+struct Prompt {
+    ask: Ask,
+    input: String,
+}
+
+struct Row {
+    label: String,
+    /// `None` greys the row out.
+    command: Option<Command>,
+}
+
+/// A side panel entry and the key that picks it.
+struct NavEntry {
+    key: char,
+    label: String,
+    command: Option<Command>,
+}
+
+/// What the middle of the screen shows.
+struct View {
+    title: String,
+    text: Vec<Line<'static>>,
+    rows: Vec<Row>,
+    /// The rows choose what the screen shows rather than doing anything.
+    browse: bool,
+    /// Labelled prices for the chart under the rows.
+    chart: Option<Vec<(String, i32)>>,
+}
+
+impl View {
+    fn new(title: impl Into<String>) -> Self {
+        View {
+            title: title.into(),
+            text: Vec::new(),
+            rows: Vec::new(),
+            browse: false,
+            chart: None,
+        }
+    }
+
+    fn say(&mut self, line: impl Into<Line<'static>>) {
+        self.text.push(line.into());
+    }
+
+    fn warn(&mut self, text: impl Into<String>) {
+        self.text
+            .push(Line::styled(text.into(), Style::new().fg(Color::Yellow)));
+    }
+
+    fn blank(&mut self) {
+        self.text.push(Line::default());
+    }
+
+    fn row(&mut self, label: impl Into<String>, command: Option<Command>) {
+        self.rows.push(Row {
+            label: label.into(),
+            command,
+        });
+    }
+}
+
+struct TuiApp {
+    engine: GameEngine,
+    /// Pack that the next new game is played with.
+    content: ContentPack,
+    /// Rules that the next new game is played by.
+    rules: GameRules,
+    /// Name the next score is recorded under.
+    player_name: String,
+    high_scores: HighScores,
+    /// Whether this game's score has gone into the table yet.
+    score_recorded: bool,
+    /// Where this game's score landed in the table, if it made it.
+    new_high_score: Option<usize>,
+    high_score_error: Option<String>,
+    /// Where this game's replay was recorded, or why it couldn't be.
+    replay_status: Option<String>,
+    autosave_enabled: bool,
+    last_autosave_day: i32,
+    screen: Screen,
+    /// The highlighted row of the current screen.
+    selection: ListState,
+    /// Title of the view the selection belongs to.
+    shown_title: String,
+    /// How far the screen's text is scrolled down.
+    scroll: u16,
+    /// How many lines of the screen's text didn't fit last time it was drawn.
+    text_overflow: u16,
+    prompt: Option<Prompt>,
+    /// Why the last thing the player tried didn't work. Cleared by the next key.
+    notice: Option<String>,
+    /// Borough shown on the price chart, as an index into the pack's locations.
+    chart_location: usize,
+    /// Which rules the high score table shows, `None` for all of them.
+    score_filter: Option<Difficulty>,
+    quit: bool,
+}
+
+impl TuiApp {
+    fn new(content: ContentPack, notice: Option<String>) -> Self {
+        let mut app = TuiApp {
+            engine: GameEngine::with_content(rand::random(), content.clone()),
+            content,
+            rules: GameRules::default(),
+            player_name: "Player".to_string(),
+            high_scores: HighScores::default(),
+            score_recorded: false,
+            new_high_score: None,
+            high_score_error: None,
+            replay_status: None,
+            autosave_enabled: true,
+            last_autosave_day: 1,
+            screen: Screen::Main,
+            selection: ListState::default().with_selected(Some(0)),
+            shown_title: String::new(),
+            scroll: 0,
+            text_overflow: 0,
+            prompt: None,
+            notice: None,
+            chart_location: 0,
+            score_filter: None,
+            quit: false,
+        };
+        if let Some(notice) = notice {
+            app.engine.message_log.push(notice);
+        }
+        match save::save_path(HIGH_SCORES_FILE).and_then(|path| HighScores::load(&path)) {
+            Ok(scores) => app.high_scores = scores,
+            Err(e) => app
+                .engine
+                .message_log
+                .push(format!("Could not read the high scores: {}", e)),
+        }
+        app
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        while !self.quit {
+            // Check for game over conditions
+            self.engine.update_state();
+            self.autosave();
+            if !self.engine.is_running() && !self.score_recorded {
+                self.record_score();
+            }
+
+            let view = self.view();
+            if view.title != self.shown_title {
+                self.shown_title = view.title.clone();
+                self.selection.select(Some(0));
+                self.scroll = 0;
+            }
+            if let Some(selected) = self.selection.selected() {
+                let last = view.rows.len().saturating_sub(1);
+                self.selection.select(Some(selected.min(last)));
+            }
+
+            terminal.draw(|frame| self.draw(frame, &view))?;
+
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    self.handle_key(key, &view);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Autosave once at the start of every new day.
+    fn autosave(&mut self) {
+        if self.autosave_enabled
+            && self.engine.is_running()
+            && self.engine.player.day != self.last_autosave_day
+        {
+            self.last_autosave_day = self.engine.player.day;
+            if let Err(e) = self.write_save(AUTOSAVE_FILE) {
+                self.engine
+                    .message_log
+                    .push(format!("Autosave failed: {}", e));
+            }
+        }
+    }
+
+    /// Substances in the current game's content pack, in pack order.
+    fn substance_names(&self) -> Vec<String> {
+        self.engine
+            .content()
+            .substance_names()
+            .map(String::from)
+            .collect()
+    }
+
+    fn location_names(&self) -> Vec<String> {
+        self.engine
+            .content()
+            .location_names()
+            .map(String::from)
+            .collect()
+    }
+
+    fn carried(&self, substance: &str) -> i32 {
+        self.engine
+            .player
+            .inventory
+            .get(substance)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    fn stashed(&self, substance: &str) -> i32 {
+        self.engine
+            .player
+            .stash_houses
+            .get(&self.engine.player.current_location)
+            .and_then(|stash| stash.inventory.get(substance).copied())
+            .unwrap_or(0)
+    }
+
+    /// Whether the player can go about their business: the game is on and
+    /// the police aren't holding them.
+    fn is_free(&self) -> bool {
+        self.engine.is_running()
+            && self.engine.custody().is_none()
+            && self.engine.police_encounter().is_none()
+    }
+
+    // --- Games and files ---
+
+    /// Starts a fresh game with the chosen content and rules.
+    fn restart(&mut self, seed: Option<u64>) {
+        let replay_note = self.record_unfinished_game();
+        let seed = seed.unwrap_or_else(rand::random);
+        self.engine = GameEngine::with_rules(seed, self.content.clone(), self.rules.clone());
+        self.engine.message_log.extend(replay_note);
+        self.engine.message_log.push("Game restarted!".to_string());
+        self.score_recorded = false;
+        self.new_high_score = None;
+        self.high_score_error = None;
+        self.replay_status = None;
+        self.last_autosave_day = self.engine.player.day;
+        self.screen = Screen::Main;
+    }
+
+    /// Puts the finished game's score into the table and writes it out.
+    fn record_score(&mut self) {
+        self.score_recorded = true;
+        let score = HighScore::from_game(&self.player_name, &self.engine);
+        self.new_high_score = self.high_scores.add(score);
+        if self.new_high_score.is_some() {
+            let written =
+                save::save_path(HIGH_SCORES_FILE).and_then(|path| self.high_scores.save(&path));
+            if let Err(e) = written {
+                self.high_score_error = Some(format!("Could not save the high scores: {}", e));
+            }
+        }
+
+        self.replay_status = replay::record(&self.engine).map(|saved| match saved {
+            Ok(path) => format!("Replay saved to {}", path.display()),
+            Err(e) => format!("Could not save the replay: {}", e),
+        });
+    }
+
+    /// Records the replay of a game the player is leaving unfinished, by
+    /// quitting or by starting or loading another. Returns what happened,
+    /// for the log. Finished games were recorded when they ended.
+    fn record_unfinished_game(&self) -> Option<String> {
+        if !self.engine.is_running() {
+            return None;
+        }
+        replay::record(&self.engine).map(|saved| match saved {
+            Ok(path) => format!("The last game's replay was saved to {}", path.display()),
+            Err(e) => format!("Could not save the last game's replay: {}", e),
+        })
+    }
+
+    fn write_save(&self, file_name: &str) -> Result<(), save::SaveError> {
+        let path = save::save_path(file_name)?;
+        // The window's screen isn't saved, so both frontends can load it
+        save::save_game(&path, &self.engine, &())
+    }
+
+    fn save_to(&mut self, file_name: &str) {
+        match self.write_save(file_name) {
+            Ok(()) => self.engine.message_log.push("Game saved.".to_string()),
+            Err(e) => self
+                .engine
+                .message_log
+                .push(format!("Could not save the game: {}", e)),
+        }
+    }
+
+    fn load_from(&mut self, file_name: &str) {
+        let loaded = save::save_path(file_name).and_then(|path| save::load_game::<()>(&path));
+        match loaded {
+            Ok((engine, ())) => {
+                let replay_note = self.record_unfinished_game();
+                self.engine = engine;
+                // Keep playing in the saved game's city and rules after a restart
+                self.content = self.engine.content().clone();
+                self.rules = self.engine.rules().clone();
+                // A finished game was scored when it ended
+                self.score_recorded = !self.engine.is_running();
+                self.new_high_score = None;
+                self.high_score_error = None;
+                self.last_autosave_day = self.engine.player.day;
+                self.engine.message_log.extend(replay_note);
+                self.engine.message_log.push("Game loaded.".to_string());
+                self.screen = Screen::Main;
+            }
+            Err(e) => self
+                .engine
+                .message_log
+                .push(format!("Could not load the game: {}", e)),
+        }
+    }
+
+    // --- Input ---
+
+    fn handle_key(&mut self, key: KeyEvent, view: &View) {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        if ctrl && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return;
+        }
+        if self.prompt.is_some() {
+            self.prompt_key(key);
+            return;
+        }
+
+        self.notice = None;
+        match key.code {
+            KeyCode::Up => self.selection.select_previous(),
+            KeyCode::Down => self.selection.select_next(),
+            KeyCode::Home => self.selection.select_first(),
+            KeyCode::End => self.selection.select_last(),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(SCROLL_STEP),
+            KeyCode::PageDown => {
+                self.scroll = (self.scroll + SCROLL_STEP).min(self.text_overflow);
+            }
+            KeyCode::Left => self.cycle(-1),
+            KeyCode::Right => self.cycle(1),
+            KeyCode::Esc => self.back(),
+            KeyCode::Enter => {
+                if let Some(index) = self.selection.selected() {
+                    self.pick(view, index);
+                }
+            }
+            KeyCode::Char('z') if ctrl => self.undo(),
+            KeyCode::Char(c @ '1'..='9') => {
+                let index = c as usize - '1' as usize;
+                if index < view.rows.len() {
+                    self.selection.select(Some(index));
+                    self.pick(view, index);
+                }
+            }
+            KeyCode::Char(c) => {
+                let (navigation, game) = self.nav();
+                let command = navigation
+                    .into_iter()
+                    .chain(game)
+                    .find(|entry| entry.key == c)
+                    .and_then(|entry| entry.command);
+                if let Some(command) = command {
+                    self.run_command(command);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn pick(&mut self, view: &View, index: usize) {
+        if let Some(command) = view.rows.get(index).and_then(|row| row.command.clone()) {
+            self.run_command(command);
+        }
+    }
+
+    fn prompt_key(&mut self, key: KeyEvent) {
+        let Some(prompt) = &mut self.prompt else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => {
+                self.prompt = None;
+                self.notice = None;
+            }
+            KeyCode::Enter => self.submit(),
+            KeyCode::Backspace => {
+                prompt.input.pop();
+            }
+            KeyCode::Tab => {
+                let ask = prompt.ask.clone();
+                if let Some(max) = self.max(&ask) {
+                    if let Some(prompt) = &mut self.prompt {
+                        prompt.input = max.to_string();
+                    }
+                }
+            }
+            KeyCode::Char(c) if !prompt.ask.is_amount() || c.is_ascii_digit() => {
+                prompt.input.push(c);
+            }
+            _ => {}
+        }
+    }
+
+    /// Left and Right step through the boroughs on the price chart and the
+    /// rules on the high score table.
+    fn cycle(&mut self, step: isize) {
+        match self.screen {
+            Screen::PriceChart => {
+                let count = self.engine.content().locations.len().max(1) as isize;
+                self.chart_location =
+                    (self.chart_location as isize + step).rem_euclid(count) as usize;
+            }
+            Screen::HighScores => {
+                let filters = score_filters();
+                let current = filters
+                    .iter()
+                    .position(|filter| *filter == self.score_filter)
+                    .unwrap_or(0) as isize;
+                let next = (current + step).rem_euclid(filters.len() as isize) as usize;
+                self.score_filter = filters[next];
+            }
+            _ => {}
+        }
+    }
+
+    fn back(&mut self) {
+        self.screen = match self.screen {
+            Screen::StashUpgrades => Screen::StashHouses,
+            Screen::Rules => Screen::NewGame,
+            _ => Screen::Main,
+        };
+    }
+
+    fn open(&mut self, screen: Screen) {
+        if screen == Screen::PriceChart {
+            self.chart_location = self
+                .engine
+                .content()
+                .location_names()
+                .position(|location| location == self.engine.player.current_location)
+                .unwrap_or(0);
+        }
+        self.screen = screen;
+    }
+
+    fn undo(&mut self) {
+        if let Err(e) = self.engine.undo() {
+            self.notice = Some(e.to_string());
+        }
+    }
+
+    fn run_command(&mut self, command: Command) {
+        match command {
+            Command::Open(screen) => self.open(screen),
+            Command::Act(action) => match self.engine.apply(&action) {
+                Ok(()) if matches!(action, Action::Travel(_)) => self.screen = Screen::Main,
+                Ok(()) => {}
+                Err(e) => self.notice = Some(e.to_string()),
+            },
+            Command::Ask(ask) => {
+                self.prompt = Some(Prompt {
+                    ask,
+                    input: String::new(),
+                })
+            }
+            Command::Undo => self.undo(),
+            Command::Save(file_name) => self.save_to(file_name),
+            Command::Load(file_name) => self.load_from(file_name),
+            Command::ToggleAutosave => self.autosave_enabled = !self.autosave_enabled,
+            Command::PlayAgain => self.restart(None),
+            Command::ReplaySeed => {
+                // Same seed, same city, same rules
+                self.content = self.engine.content().clone();
+                self.rules = self.engine.rules().clone();
+                self.restart(Some(self.engine.seed()));
+            }
+            Command::StartNewGame => match self.rules.validate() {
+                Ok(()) => {
+                    self.prompt = Some(Prompt {
+                        ask: Ask::Seed,
+                        input: String::new(),
+                    })
+                }
+                Err(problems) => {
+                    let problems: Vec<String> = problems
+                        .iter()
+                        .map(|problem| format!("Rules: {}.", problem))
+                        .collect();
+                    self.notice = Some(problems.join(" "));
+                }
+            },
+            Command::SetDifficulty(difficulty) => self.rules = GameRules::preset(difficulty),
+            Command::ToggleFireSale => {
+                let mut edited = self.rules.clone();
+                edited.fire_sale = !edited.fire_sale;
+                self.set_rules(edited);
+            }
+            Command::UseBuiltinPack => {
+                self.content = ContentPack::default();
+                self.notice = Some("Using the built-in pack.".to_string());
+            }
+            Command::Quit => self.quit = true,
+        }
+    }
+
+    /// Takes on edited rules. Any change to a preset's numbers makes it a
+    /// custom game.
+    fn set_rules(&mut self, mut edited: GameRules) {
+        if edited != self.rules {
+            edited.difficulty = Difficulty::Custom;
+            self.rules = edited;
+        }
+    }
+
+    fn question(&self, ask: &Ask) -> String {
+        match ask {
+            Ask::Buy(substance) => format!("Buy how much {}?", substance),
+            Ask::Sell(substance) => format!("Sell how much {}?", substance),
+            Ask::Borrow => "Borrow how much?".to_string(),
+            Ask::Repay => "Repay how much?".to_string(),
+            Ask::BankDeposit => "Deposit how much?".to_string(),
+            Ask::BankWithdraw => "Withdraw how much?".to_string(),
+            Ask::StashDeposit(substance) => format!("Deposit how much {}?", substance),
+            Ask::StashWithdraw(substance) => format!("Withdraw how much {}?", substance),
+            Ask::Launder => "Launder how much?".to_string(),
+            Ask::Bribe => "Your offer?".to_string(),
+            Ask::Name => "Your name:".to_string(),
+            Ask::Seed => "Seed to replay a shared game:".to_string(),
+            Ask::ContentPack => "Pack file (.toml or .json):".to_string(),
+            Ask::Rule(index) => format!("{}:", rule_names()[*index]),
+        }
+    }
+
+    /// What Tab fills in.
+    fn max(&self, ask: &Ask) -> Option<i32> {
+        let player = &self.engine.player;
+        let max = match ask {
+            Ask::Buy(substance) => self.engine.max_buy(substance),
+            Ask::Sell(substance) => self.carried(substance),
+            Ask::Borrow => self.engine.credit_available(),
+            Ask::Repay => player.cash.min(player.debt),
+            Ask::BankDeposit => player.clean_cash(),
+            Ask::BankWithdraw => self
+                .engine
+                .bank_withdrawal_left()
+                .map_or(player.bank_balance, |left| left.min(player.bank_balance)),
+            Ask::StashDeposit(substance) => {
+                let space = player
+                    .stash_houses
+                    .get(&player.current_location)
+                    .map_or(0, |stash| stash.space_available());
+                self.carried(substance).min(space)
+            }
+            Ask::StashWithdraw(substance) => self.stashed(substance).min(player.space_available()),
+            Ask::Launder => player.dirty_cash.min(self.engine.laundering_left()),
+            Ask::Bribe => self.engine.bribe_negotiation()?.asking_price,
+            Ask::Name | Ask::Seed | Ask::ContentPack | Ask::Rule(_) => return None,
+        };
+        Some(max.max(0))
+    }
+
+    /// The action an amount typed in answer to `ask` stands for.
+    fn action(ask: &Ask, amount: i32) -> Option<Action> {
+        Some(match ask {
+            Ask::Buy(substance) => Action::Buy {
+                substance: substance.clone(),
+                amount,
+            },
+            Ask::Sell(substance) => Action::Sell {
+                substance: substance.clone(),
+                amount,
+            },
+            Ask::Borrow => Action::Borrow(amount),
+            Ask::Repay => Action::Repay(amount),
+            Ask::BankDeposit => Action::BankDeposit(amount),
+            Ask::BankWithdraw => Action::BankWithdraw(amount),
+            Ask::StashDeposit(substance) => Action::Deposit {
+                substance: substance.clone(),
+                amount,
+            },
+            Ask::StashWithdraw(substance) => Action::Withdraw {
+                substance: substance.clone(),
+                amount,
+            },
+            Ask::Launder => Action::Launder(amount),
+            Ask::Bribe => Action::OfferBribe(amount),
+            Ask::Name | Ask::Seed | Ask::ContentPack | Ask::Rule(_) => return None,
+        })
+    }
+
+    /// Everything stopping `action`, described the way the window does.
+    fn problems(&self, action: &Action) -> Option<String> {
+        let (prefix, problems) = match action {
+            Action::Buy { substance, amount } => {
+                ("Cannot buy: ", self.engine.check_buy(substance, *amount))
+            }
+            Action::Sell { substance, amount } => {
+                ("Cannot sell: ", self.engine.check_sell(substance, *amount))
+            }
+            Action::Borrow(amount) => ("Cannot borrow: ", self.engine.check_borrow(*amount)),
+            Action::Repay(amount) => ("Cannot repay: ", self.engine.check_repay(*amount)),
+            Action::BankDeposit(amount) => {
+                ("Cannot deposit: ", self.engine.check_bank_deposit(*amount))
+            }
+            Action::BankWithdraw(amount) => (
+                "Cannot withdraw: ",
+                self.engine.check_bank_withdraw(*amount),
+            ),
+            Action::Deposit { substance, amount } => (
+                "Cannot deposit: ",
+                self.engine.check_deposit(substance, *amount),
+            ),
+            Action::Withdraw { substance, amount } => (
+                "Cannot withdraw: ",
+                self.engine.check_withdraw(substance, *amount),
+            ),
+            Action::Launder(amount) => ("Cannot launder: ", self.engine.check_launder(*amount)),
+            _ => return None,
+        };
+        (!problems.is_empty()).then(|| describe_problems(prefix, &problems))
+    }
+
+    /// What the answer typed so far would do, shown under the prompt.
+    fn preview(&self, prompt: &Prompt) -> String {
+        match &prompt.ask {
+            Ask::Seed => return "Enter for a random one.".to_string(),
+            Ask::Rule(9) => return "In dollars, or Enter for no limit.".to_string(),
+            Ask::Rule(index) if is_percent_rule(*index) => return "In percent.".to_string(),
+            _ => {}
+        }
+        let Some(action) = prompt
+            .input
+            .parse()
+            .ok()
+            .and_then(|amount| Self::action(&prompt.ask, amount))
+        else {
+            return String::new();
+        };
+        if let Some(problems) = self.problems(&action) {
+            return problems;
+        }
+        match action {
+            Action::Buy { substance, amount } => format!(
+                "Total cost: ${}. Buying this many will push the price up to ${}.",
+                self.engine.quote_buy(&substance, amount),
+                self.engine.price_after_trade(&substance, amount)
+            ),
+            Action::Sell { substance, amount } => format!(
+                "Total earned: ${}. Selling this many will push the price down to ${}.",
+                self.engine.quote_sell(&substance, amount),
+                self.engine.price_after_trade(&substance, -amount)
+            ),
+            _ => String::new(),
+        }
+    }
+
+    fn submit(&mut self) {
+        let Some(prompt) = self.prompt.take() else {
+            return;
+        };
+        let answer = prompt.input.trim().to_string();
+        let result = match &prompt.ask {
+            Ask::Name => {
+                if !answer.is_empty() {
+                    self.player_name = answer;
+                }
+                Ok(())
+            }
+            Ask::Seed => self.start_with_seed(&answer),
+            Ask::ContentPack => self.load_content_pack(&answer),
+            Ask::Rule(index) => self.set_rule(*index, &answer),
+            ask => match answer
+                .parse()
+                .ok()
+                .and_then(|amount| Self::action(ask, amount))
+            {
+                None => Err("Enter a whole number.".to_string()),
+                Some(action) => match self.problems(&action) {
+                    Some(problems) => Err(problems),
+                    None => self.engine.apply(&action).map_err(|e| e.to_string()),
+                },
+            },
+        };
+        if let Err(problem) = result {
+            self.notice = Some(problem);
+            // Let the player fix the answer rather than type it again
+            self.prompt = Some(prompt);
+        }
+    }
+
+    fn start_with_seed(&mut self, answer: &str) -> Result<(), String> {
+        let seed = if answer.is_empty() {
+            None
+        } else {
+            let seed = answer.parse::<u64>().map_err(|_| {
+                "The seed must be a whole number between 0 and 18446744073709551615.".to_string()
+            })?;
+            Some(seed)
+        };
+        self.restart(seed);
+        Ok(())
+    }
+
+    fn load_content_pack(&mut self, answer: &str) -> Result<(), String> {
+        if answer.is_empty() {
+            return Ok(());
+        }
+        let path = PathBuf::from(answer);
+        let content = ContentPack::load(&path)
+            .map_err(|e| format!("Could not load {}: {}", path.display(), e))?;
+        self.notice = Some(format!(
+            "Loaded \"{}\". Start a new game to play it.",
+            content.name
+        ));
+        self.content = content;
+        Ok(())
+    }
+
+    fn set_rule(&mut self, index: usize, answer: &str) -> Result<(), String> {
+        let mut edited = self.rules.clone();
+        if is_percent_rule(index) {
+            let percent = answer
+                .trim_end_matches('%')
+                .parse::<f32>()
+                .map_err(|_| "Enter a number.".to_string())?;
+            let field = match index {
+                4 => &mut edited.loan_shark_interest,
+                8 => &mut edited.bank_interest,
+                10 => &mut edited.laundering_fee,
+                _ => &mut edited.dirty_cash_penalty,
+            };
+            *field = percent / 100.0;
+        } else if index == 9 {
+            edited.bank_withdrawal_limit = if answer.is_empty() {
+                None
+            } else {
+                match answer.trim_start_matches('$').parse::<i32>() {
+                    Ok(limit) if limit > 0 => Some(limit),
+                    _ => return Err("Enter a positive number.".to_string()),
+                }
+            };
+        } else {
+            let value = answer
+                .trim_start_matches('$')
+                .parse::<i32>()
+                .map_err(|_| "Enter a whole number.".to_string())?;
+            let field = match index {
+                0 => &mut edited.max_days,
+                1 => &mut edited.starting_cash,
+                2 => &mut edited.starting_debt,
+                3 => &mut edited.carrying_capacity,
+                5 => &mut edited.stash_house_capacity,
+                11 => &mut edited.laundering_limit,
+                _ => &mut edited.hospital_cost_per_point,
+            };
+            *field = value;
+        }
+        self.set_rules(edited);
+        Ok(())
+    }
+
+    // --- Side panel ---
+
+    /// The side panel's entries: where to go, then what to do with the game.
+    fn nav(&self) -> (Vec<NavEntry>, Vec<NavEntry>) {
+        let free = self.is_free();
+        let running = self.engine.is_running();
+        let entry = |key, label: &str, command: Option<Command>| NavEntry {
+            key,
+            label: label.to_string(),
+            command,
+        };
+        let screen = |key, label, screen| entry(key, label, free.then_some(Command::Open(screen)));
+        let service = |key, label, service, screen| {
+            let open = free && self.engine.service_here(service);
+            entry(key, label, open.then_some(Command::Open(screen)))
+        };
+        // Owned weapons can be equipped and owned stash houses used anywhere
+        let armed = self.engine.player.has_weapon();
+        let stash_here = self.engine.player.owns_stash_house_in_current_location();
+        let weapons = free && (armed || self.engine.service_here(Service::GunShop));
+        let stash = free && (stash_here || self.engine.service_here(Service::StashBroker));
+        // A finished game can still go on to the next one
+        let between_games = free || !running;
+
+        let navigation = vec![
+            screen('m', "Main Menu", Screen::Main),
+            screen('b', "Buy", Screen::Buy),
+            screen('s', "Sell", Screen::Sell),
+            screen('t', "Travel", Screen::Travel),
+            entry(
+                'w',
+                "Weapons Shop",
+                weapons.then_some(Command::Open(Screen::Weapons)),
+            ),
+            entry(
+                'h',
+                "Stash Houses",
+                stash.then_some(Command::Open(Screen::StashHouses)),
+            ),
+            screen('f', "Fronts", Screen::Fronts),
+            service('l', "Loan Shark", Service::LoanShark, Screen::LoanShark),
+            service('k', "Bank", Service::Bank, Screen::Bank),
+            service('o', "Hospital", Service::Hospital, Screen::Hospital),
+            screen('c', "Price Charts", Screen::PriceChart),
+            screen('n', "News", Screen::News),
+            screen('v', "Net Worth", Screen::NetWorth),
+            entry(
+                'g',
+                "New Game",
+                between_games.then_some(Command::Open(Screen::NewGame)),
+            ),
+            entry(
+                'x',
+                "High Scores",
+                between_games.then_some(Command::Open(Screen::HighScores)),
+            ),
+        ];
+        let game = vec![
+            entry(
+                'u',
+                "Undo",
+                self.engine.last_undoable().map(|_| Command::Undo),
+            ),
+            entry(
+                'S',
+                "Save Game",
+                running.then_some(Command::Save(MANUAL_SAVE_FILE)),
+            ),
+            entry('L', "Load Game", Some(Command::Load(MANUAL_SAVE_FILE))),
+            entry('A', "Load Autosave", Some(Command::Load(AUTOSAVE_FILE))),
+            entry(
+                'a',
+                if self.autosave_enabled {
+                    "Autosave: on"
+                } else {
+                    "Autosave: off"
+                },
+                Some(Command::ToggleAutosave),
+            ),
+            entry('q', "Quit", Some(Command::Quit)),
+        ];
+        (navigation, game)
+    }
+
+    fn inventory_lines(&self) -> Vec<Line<'static>> {
+        let player = &self.engine.player;
+        let mut lines = vec![Line::from(format!(
+            "Inventory ({}/{}):",
+            player.total_items(),
+            player.carrying_capacity
+        ))];
+        let mut empty = true;
+        for substance in self.substance_names() {
+            let amount = self.carried(&substance);
+            if amount > 0 {
+                empty = false;
+                lines.push(Line::from(format!("  {}: {}", substance, amount)));
+            }
+        }
+        if empty {
+            lines.push(Line::from("  nothing"));
+        }
+
+        if player.has_weapon() {
+            lines.push(Line::from("Weapons:"));
+            for weapon in self.engine.content().weapon_names() {
+                let amount = player.weapons.get(weapon).copied().unwrap_or(0);
+                if amount > 0 {
+                    let equipped = player.active_weapon.as_deref() == Some(weapon);
+                    lines.push(Line::from(format!(
+                        "  {}: {}{}",
+                        weapon,
+                        amount,
+                        if equipped { " (EQUIPPED)" } else { "" }
+                    )));
+                }
+            }
+        } else {
+            lines.push(Line::from("Weapons: none"));
+        }
+        lines
+    }
+
+    // --- Screens ---
+
+    fn view(&self) -> View {
+        if self.engine.is_running() {
+            if let Some(view) = self.police_view() {
+                return view;
+            }
+        } else if !matches!(
+            self.screen,
+            Screen::NewGame | Screen::Rules | Screen::HighScores
+        ) {
+            return self.game_over_view();
+        }
+
+        match self.screen {
+            Screen::Main => self.main_view(),
+            Screen::Buy => self.buy_view(),
+            Screen::Sell => self.sell_view(),
+            Screen::Travel => self.travel_view(),
+            Screen::Weapons => self.weapons_view(),
+            Screen::StashHouses => self.stash_houses_view(),
+            Screen::StashUpgrades => self.stash_upgrades_view(),
+            Screen::Fronts => self.fronts_view(),
+            Screen::LoanShark => self.loan_shark_view(),
+            Screen::Bank => self.bank_view(),
+            Screen::Hospital => self.hospital_view(),
+            Screen::PriceChart => self.price_chart_view(),
+            Screen::News => self.news_view(),
+            Screen::NetWorth => self.net_worth_view(),
+            Screen::NewGame => self.new_game_view(),
+            Screen::Rules => self.rules_view(),
+            Screen::HighScores => self.high_scores_view(),
+        }
+    }
+
+    /// No wandering off while the police are holding you.
+    fn police_view(&self) -> Option<View> {
+        if self.engine.custody().is_some() {
+            return Some(self.jail_view());
+        }
+        let encounter = self.engine.police_encounter()?;
+        Some(match encounter.state {
+            PoliceState::Negotiating => self.bribe_negotiation_view(),
+            _ => self.police_stop_view(),
+        })
+    }
+
+    /// Says where to go instead when `service` isn't offered here. Returns
+    /// whether it is.
+    fn service_available(&self, v: &mut View, service: Service) -> bool {
+        if self.engine.service_here(service) {
+            return true;
+        }
+        v.warn(format!(
+            "There's no {} in {}.",
+            service, self.engine.player.current_location
+        ));
+        v.say(self.engine.where_to_find(service));
+        false
+    }
+
+    fn main_view(&self) -> View {
+        let mut v = View::new("DopeWars - Main Menu");
+        v.say("What do you want to do?");
+        let record = &self.engine.player.record;
+        if !record.is_clean() {
+            v.warn(format!(
+                "Criminal record: {} arrests, {} convictions",
+                record.arrests, record.convictions
+            ));
+        }
+        v.blank();
+        v.say(format!(
+            "Market prices in {}:",
+            self.engine.player.current_location
+        ));
+        for substance in self.substance_names() {
+            v.say(format!(
+                "  {:<12} ${:>6}   you have {}",
+                substance,
+                self.engine.local_price(&substance),
+                self.carried(&substance)
+            ));
+        }
+
+        let (navigation, _) = self.nav();
+        let command = |key: char| {
+            navigation
+                .iter()
+                .find(|entry| entry.key == key)
+                .and_then(|entry| entry.command.clone())
+        };
+        for (key, label) in [
+            ('b', "Buy substances"),
+            ('s', "Sell substances"),
+            ('t', "Travel to another location"),
+            ('w', "Buy weapons"),
+            ('h', "Manage stash houses"),
+            ('f', "Launder money"),
+            ('l', "Visit loan shark"),
+            ('k', "Go to the bank"),
+            ('o', "Go to hospital"),
+            ('c', "View price charts"),
+            ('n', "Read the news"),
+            ('v', "Count your net worth"),
+            ('g', "Start a new game"),
+            ('x', "View high scores"),
+        ] {
+            v.row(label, command(key));
+        }
+        v
+    }
+
+    fn buy_view(&self) -> View {
+        let mut v = View::new("Buy Substances");
+        v.say(format!(
+            "Cash: ${}   Space available: {}",
+            self.engine.player.cash,
+            self.engine.player.space_available()
+        ));
+        for substance in self.substance_names() {
+            v.row(
+                format!(
+                    "{} - ${} per unit",
+                    substance,
+                    self.engine.local_price(&substance)
+                ),
+                Some(Command::Ask(Ask::Buy(substance))),
+            );
+        }
+        v
+    }
+
+    fn sell_view(&self) -> View {
+        let mut v = View::new("Sell Substances");
+        v.say(format!(
+            "Cash: ${} (${} dirty)",
+            self.engine.player.cash, self.engine.player.dirty_cash
+        ));
+        v.say("What you make selling is dirty money until it's laundered through a front.");
+
+        for substance in self.substance_names() {
+            let carried = self.carried(&substance);
+            if carried > 0 {
+                v.row(
+                    format!(
+                        "{} - {} units - ${} per unit",
+                        substance,
+                        carried,
+                        self.engine.local_price(&substance)
+                    ),
+                    Some(Command::Ask(Ask::Sell(substance))),
+                );
+            }
+        }
+        if v.rows.is_empty() {
+            v.say("You don't have any substances to sell.");
+        }
+        v
+    }
+
+    fn travel_view(&self) -> View {
+        let mut v = View::new("Travel");
+        v.say("Where do you want to go?");
+        for location in self.location_names() {
+            if location != self.engine.player.current_location {
+                v.row(
+                    location.clone(),
+                    Some(Command::Act(Action::Travel(location))),
+                );
+            }
+        }
+        v
+    }
+
+    fn police_stop_view(&self) -> View {
+        let mut v = View::new("POLICE ENCOUNTER");
+        v.warn("You've been stopped by the police!");
+
+        if self.engine.player.total_items() == 0 {
+            v.say("You aren't carrying any illegal substances.");
+        } else {
+            v.say("The officer wants to search you. You're carrying:");
+            for substance in self.substance_names() {
+                let amount = self.carried(&substance);
+                if amount > 0 {
+                    v.say(format!("- {} units of {}", amount, substance));
+                }
+            }
+        }
+
+        v.row("Fight", Some(Command::Act(Action::PoliceFight)));
+        v.row("Run", Some(Command::Act(Action::PoliceRun)));
+        v.row("Offer bribe", Some(Command::Act(Action::PoliceNegotiate)));
+        v.row(
+            "Let them search you",
+            Some(Command::Act(Action::PoliceSurrender)),
+        );
+        v
+    }
+
+    fn bribe_negotiation_view(&self) -> View {
+        let mut v = View::new("BRIBE NEGOTIATION");
+        let Some(negotiation) = self.engine.bribe_negotiation() else {
+            return v;
+        };
+
+        v.say(format!(
+            "You're carrying inventory worth approximately ${}",
+            self.engine.calculate_inventory_value()
+        ));
+        v.say(format!("The officer wants ${}", negotiation.asking_price));
+
+        if negotiation.greed > 0.6 {
+            v.say("This one looks greedy. Don't expect them to come down much.");
+        } else {
+            v.say("This one seems willing to meet you partway.");
+        }
+
+        match negotiation.patience {
+            1 => v.warn("The officer's patience is wearing thin!"),
+            2 => v.say("The officer is getting impatient."),
+            _ => v.say("The officer seems in no hurry."),
+        }
+
+        if !negotiation.offers.is_empty() {
+            let offers: Vec<String> = negotiation
+                .offers
+                .iter()
+                .map(|offer| format!("${}", offer))
+                .collect();
+            v.say(format!("Your offers so far: {}", offers.join(", ")));
+        }
+        v.say(format!("Cash: ${}", self.engine.player.cash));
+
+        v.row("Make an offer", Some(Command::Ask(Ask::Bribe)));
+        v.row(
+            format!("Pay ${}", negotiation.asking_price),
+            Some(Command::Act(Action::OfferBribe(negotiation.asking_price))),
+        );
+        v.row("Refuse to bribe", Some(Command::Act(Action::RefuseBribe)));
+        v
+    }
+
+    fn jail_view(&self) -> View {
+        let mut v = View::new("IN CUSTODY");
+        let Some(custody) = self.engine.custody() else {
+            return v;
+        };
+        v.say("You've been arrested and booked at the precinct.");
+
+        let record = &self.engine.player.record;
+        v.say(format!(
+            "Criminal record: {} arrests, {} convictions, {} days served",
+            record.arrests, record.convictions, record.days_in_jail
+        ));
+        v.say(format!(
+            "Heat: {} ({})",
+            self.engine.player.heat,
+            self.engine.player.heat_description()
+        ));
+        v.say("The judge will weigh both when your case comes to court.");
+        v.blank();
+        v.say(format!("Bail: ${}", custody.bail));
+        v.say(format!(
+            "Without bail you'll wait {} days in a cell for your hearing.",
+            custody.days_until_trial
+        ));
+        v.say(format!(
+            "Cash: ${}   Debt: ${}",
+            self.engine.player.cash, self.engine.player.debt
+        ));
+
+        v.row(
+            format!("Post bail (${})", custody.bail),
+            Some(Command::Act(Action::PostBail)),
+        );
+        let loan_shark = if self.engine.player.loan.barred {
+            "Call the loan shark (he won't lend you anything until you pay up)".to_string()
+        } else if custody.bail > self.engine.credit_available() {
+            format!(
+                "Call the loan shark (he'll only lend you ${} more)",
+                self.engine.credit_available()
+            )
+        } else {
+            "Call the loan shark (they pay your bail and add it to your debt)".to_string()
+        };
+        v.row(loan_shark, Some(Command::Act(Action::BorrowBail)));
+        v.row(
+            format!("Wait {} days for trial", custody.days_until_trial),
+            Some(Command::Act(Action::AwaitTrial)),
+        );
+        v
+    }
+
+    fn loan_shark_view(&self) -> View {
+        let mut v = View::new("Loan Shark");
+        v.say(format!("Current debt: ${}", self.engine.player.debt));
+        v.say(format!("Current cash: ${}", self.engine.player.cash));
+        v.say(format!(
+            "Daily interest rate: {}%",
+            (self.engine.rules().loan_shark_interest * 100.0).round()
+        ));
+        v.say("Your debt increases every time you travel to a new location.");
+        v.say(format!(
+            "Every {} days he wants {}% of what you owed at the start of them back. Fall {} days \
+             behind and his enforcers come looking; {} days and he stops lending until you're \
+             paid up.",
+            TERM_DAYS, MINIMUM_PAYMENT_PERCENT, ENFORCERS_AFTER_DAYS, BARRED_AFTER_DAYS
+        ));
+        v.blank();
+
+        let loan = &self.engine.player.loan;
+        let overdue = loan.days_overdue(self.engine.player.day);
+        match loan.due_day {
+            Some(due) if overdue > 0 => v.text.push(Line::styled(
+                format!(
+                    "OVERDUE: ${} was due on day {}. You're {} days late.",
+                    loan.payment_due(),
+                    due,
+                    overdue
+                ),
+                Style::new().fg(Color::Red),
+            )),
+            Some(due) if loan.payment_due() > 0 => v.say(format!(
+                "Next payment: ${} by the end of day {}",
+                loan.payment_due(),
+                due
+            )),
+            Some(due) => v.say(format!(
+                "This payment is made. The next one is set after day {}.",
+                due
+            )),
+            None => v.say("You don't owe the loan shark anything."),
+        }
+        if loan.barred {
+            v.warn("He won't lend you another cent until your debt is paid off.");
+        } else {
+            v.say(format!(
+                "Credit limit: ${} (${} available)",
+                self.engine.credit_limit(),
+                self.engine.credit_available()
+            ));
+        }
+        v.say(format!(
+            "Payments made on time: {}   Late: {}",
+            loan.terms_met, loan.terms_missed
+        ));
+
+        v.blank();
+        if self.service_available(&mut v, Service::LoanShark) {
+            v.row("Borrow", Some(Command::Ask(Ask::Borrow)));
+            v.row("Repay", Some(Command::Ask(Ask::Repay)));
+            let due = loan.payment_due();
+            if due > 0 {
+                v.row(
+                    format!("Pay what's due (${})", due),
+                    Some(Command::Act(Action::Repay(due))),
+                );
+            }
+        }
+        v
+    }
+
+    fn bank_view(&self) -> View {
+        let mut v = View::new("Bank");
+        v.say(format!("Savings: ${}", self.engine.player.bank_balance));
+        v.say(format!(
+            "Current cash: ${} (${} clean)",
+            self.engine.player.cash,
+            self.engine.player.clean_cash()
+        ));
+        if !self.service_available(&mut v, Service::Bank) {
+            return v;
+        }
+
+        v.say(format!(
+            "Daily interest on savings: {}%",
+            (self.engine.rules().bank_interest * 1000.0).round() / 10.0
+        ));
+        if let Some(left) = self.engine.bank_withdrawal_left() {
+            v.say(format!("You can withdraw ${} more today.", left));
+        }
+        v.say("Savings are safe from muggers and the police.");
+        v.say("The bank only takes clean money.");
+
+        v.row("Deposit", Some(Command::Ask(Ask::BankDeposit)));
+        v.row("Withdraw", Some(Command::Ask(Ask::BankWithdraw)));
+        v
+    }
+
+    fn hospital_view(&self) -> View {
+        let mut v = View::new("Hospital");
+        v.say(format!("Your health: {}/100", self.engine.player.health));
+        if !self.service_available(&mut v, Service::Hospital) {
+            return v;
+        }
+
+        if self.engine.player.health >= 100 {
+            v.say("You're in perfect health!");
+            return v;
+        }
+
+        let healing_needed = 100 - self.engine.player.health;
+        let total_cost = self.engine.heal_cost();
+        v.say(format!(
+            "It will cost ${} to fully heal ({} health points at ${} each).",
+            total_cost,
+            healing_needed,
+            self.engine.rules().hospital_cost_per_point
+        ));
+        let affordable = self.engine.player.cash >= total_cost;
+        if !affordable {
+            v.warn("You don't have enough cash for treatment.");
+        }
+        v.row(
+            "Get treatment",
+            affordable.then_some(Command::Act(Action::Heal)),
+        );
+        v
+    }
+
+    fn weapons_view(&self) -> View {
+        let mut v = View::new("Weapons Shop");
+        v.say(format!("Cash: ${}", self.engine.player.cash));
+        v.say("A better weapon increases your chance of successfully defending yourself.");
+        v.blank();
+        let shop = self.service_available(&mut v, Service::GunShop);
+
+        for weapon in &self.engine.content().weapons {
+            let mut label = format!(
+                "Buy a {} - ${} (Power: {})",
+                weapon.name, weapon.price, weapon.power
+            );
+            if !weapon.description.is_empty() {
+                label.push_str(&format!(" - {}", weapon.description));
+            }
+            let affordable = self.engine.player.cash >= weapon.price;
+            v.row(
+                label,
+                (shop && affordable).then(|| Command::Act(Action::BuyWeapon(weapon.name.clone()))),
+            );
+        }
+        for weapon in self.engine.content().weapon_names() {
+            let owned = self.engine.player.weapons.get(weapon).copied().unwrap_or(0);
+            if owned > 0 {
+                let equipped = self.engine.player.active_weapon.as_deref() == Some(weapon);
+                v.row(
+                    format!(
+                        "Equip the {} ({} owned){}",
+                        weapon,
+                        owned,
+                        if equipped { " (EQUIPPED)" } else { "" }
+                    ),
+                    (!equipped).then(|| Command::Act(Action::EquipWeapon(weapon.to_string()))),
+                );
+            }
+        }
+        v
+    }
+
+    fn stash_houses_view(&self) -> View {
+        let mut v = View::new("Stash Houses");
+        v.say("Stash houses let you store extra inventory in each location.");
+        v.blank();
+
+        let player = &self.engine.player;
+        if player.stash_houses.is_empty() {
+            v.say("You don't own any stash houses yet.");
+        } else {
+            v.say("Your stash houses:");
+            for location in self.location_names() {
+                if let Some(stash) = player.stash_houses.get(&location) {
+                    let here = location == player.current_location;
+                    v.say(format!(
+                        "  {}: {}/{} capacity, sells for ${}{}",
+                        location,
+                        stash.total_items(),
+                        stash.capacity,
+                        self.engine.stash_house_sale_price(&location).unwrap_or(0),
+                        if here { " (CURRENT LOCATION)" } else { "" }
+                    ));
+                    if !stash.upgrades.is_empty() {
+                        v.say(format!("    {}", stash.describe_upgrades()));
+                    }
+                }
+            }
+        }
+
+        v.blank();
+        v.say("Property market:");
+        for location in self.location_names() {
+            let change = self.engine.property_price_change(&location);
+            v.say(format!(
+                "  {:<16} ${:>7}  {}",
+                location,
+                self.engine.stash_house_price(&location),
+                match change {
+                    0 => "steady".to_string(),
+                    1.. => format!("+{}%", change),
+                    _ => format!("{}%", change),
+                }
+            ));
+        }
+        v.blank();
+
+        let location = player.current_location.clone();
+        let Some(stash) = player.stash_houses.get(&location) else {
+            let price = self.engine.stash_house_price(&location);
+            v.say(format!(
+                "Cost to buy a stash house in {}: ${}",
+                location, price
+            ));
+            if self.service_available(&mut v, Service::StashBroker) {
+                let affordable = player.cash >= price;
+                if !affordable {
+                    v.warn("You can't afford a stash house here.");
+                }
+                v.row(
+                    "Buy a stash house here",
+                    affordable.then_some(Command::Act(Action::BuyStashHouse)),
+                );
+            }
+            return v;
+        };
+
+        for substance in self.substance_names() {
+            let carried = self.carried(&substance);
+            if carried > 0 {
+                v.row(
+                    format!("Deposit {} ({} carried)", substance, carried),
+                    Some(Command::Ask(Ask::StashDeposit(substance))),
+                );
+            }
+        }
+        for substance in self.substance_names() {
+            let stashed = self.stashed(&substance);
+            if stashed > 0 {
+                v.row(
+                    format!("Withdraw {} ({} stashed)", substance, stashed),
+                    Some(Command::Ask(Ask::StashWithdraw(substance))),
+                );
+            }
+        }
+        v.row("Upgrades", Some(Command::Open(Screen::StashUpgrades)));
+
+        if self.service_available(&mut v, Service::StashBroker) {
+            v.say(format!(
+                "The broker will pay ${} for this house after a {}% cut, in dirty cash.",
+                self.engine.stash_house_sale_price(&location).unwrap_or(0),
+                BROKER_CUT_PERCENT
+            ));
+            let left_behind = (stash.total_items() - player.space_available()).max(0);
+            if left_behind > 0 {
+                v.warn(format!(
+                    "You can't carry everything in the stash: {} units would go with the house.",
+                    left_behind
+                ));
+            }
+            v.row("Sell the house", Some(Command::Act(Action::SellStashHouse)));
+        }
+        v
+    }
+
+    fn stash_upgrades_view(&self) -> View {
+        let mut v = View::new("Stash House Upgrades");
+        let location = self.engine.player.current_location.clone();
+        let Some(stash) = self.engine.player.stash_houses.get(&location) else {
+            v.say("You don't own a stash house here.");
+            return v;
+        };
+        v.say(format!(
+            "Security: {}% of raids stopped, raids take {} points less of the stock,",
+            stash.raid_protection(),
+            stash.confiscation_reduction()
+        ));
+        v.say(format!(
+            "{}% less chance of the house being seized.",
+            stash.seizure_reduction()
+        ));
+        if stash.has_upgrade(StashUpgrade::Guards) {
+            v.say(format!(
+                "Your guards here are paid ${} a day.",
+                self.engine.guard_wages(&location)
+            ));
+        }
+
+        for upgrade in StashUpgrade::ALL {
+            let price = self.engine.stash_upgrade_price(&location, upgrade);
+            let status = match price {
+                Some(price) if upgrade == StashUpgrade::Guards => {
+                    format!("${} + ${} a day", price, self.engine.guard_wages(&location))
+                }
+                Some(price) => format!("${}", price),
+                None => GameError::UpgradeAlreadyOwned(upgrade).to_string(),
+            };
+            v.row(
+                format!("{} - {} {}", upgrade, status, upgrade.description()),
+                price.map(|_| Command::Act(Action::UpgradeStashHouse(upgrade))),
+            );
+        }
+        v
+    }
+
+    fn fronts_view(&self) -> View {
+        let mut v = View::new("Fronts");
+        let player = &self.engine.player;
+        v.say(format!(
+            "Cash: ${} (${} dirty)",
+            player.cash, player.dirty_cash
+        ));
+        v.say(
+            "Money from dealing is dirty. The police take it when they arrest you, the bank \
+             won't touch it and it counts for less in your net worth. A front business passes \
+             it off as takings, for a cut.",
+        );
+        v.blank();
+
+        if player.fronts.is_empty() {
+            v.say("You don't own any fronts yet.");
+        } else {
+            v.say("Your fronts:");
+            for location in self.location_names() {
+                if let Some(front) = player.fronts.get(&location) {
+                    v.say(format!(
+                        "  {}: ${} laundered today, ${} in all",
+                        location, front.laundered_today, front.total_laundered
+                    ));
+                }
+            }
+        }
+        v.blank();
+
+        let location = &player.current_location;
+        if player.owns_front_in_current_location() {
+            v.say(format!(
+                "Your front here can launder ${} more today, for a {}% cut.",
+                self.engine.laundering_left(),
+                (self.engine.rules().laundering_fee * 100.0).round()
+            ));
+            v.row("Launder money", Some(Command::Ask(Ask::Launder)));
+        } else {
+            let price = self.engine.front_price(location);
+            v.say(format!("Cost to buy a front in {}: ${}", location, price));
+            let affordable = player.cash >= price;
+            if !affordable {
+                v.warn("You can't afford a front here.");
+            }
+            v.row(
+                "Buy a front here",
+                affordable.then_some(Command::Act(Action::BuyFront)),
+            );
+        }
+        v
+    }
+
+    fn price_chart_view(&self) -> View {
+        let mut v = View::new("Price Charts");
+        v.browse = true;
+        let substances = self.substance_names();
+        let locations = self.location_names();
+        for substance in &substances {
+            v.row(substance.clone(), None);
+        }
+        let index = self.selection.selected().unwrap_or(0);
+        let (Some(substance), Some(location)) =
+            (substances.get(index), locations.get(self.chart_location))
+        else {
+            return v;
+        };
+
+        let here = |location: &String| *location == self.engine.player.current_location;
+        v.say(format!(
+            "Borough: < {}{} >",
+            location,
+            if here(location) {
+                " (you are here)"
+            } else {
+                ""
+            }
+        ));
+        let current_price = self.engine.market.price(location, substance);
+        let history = self.engine.market.history(location, substance);
+        v.say(format!(
+            "Current price of {} in {}: ${}",
+            substance, location, current_price
+        ));
+
+        if history.is_empty() {
+            v.say("No price history available yet. Travel to generate price data.");
+        } else {
+            let mut bars: Vec<(String, i32)> = history
+                .iter()
+                .enumerate()
+                .map(|(i, price)| (format!("-{}", history.len() - i), *price))
+                .collect();
+            bars.push(("today".to_string(), current_price));
+            v.chart = Some(bars);
+        }
+
+        // Trend analysis if we have history
+        if history.len() >= 2 {
+            let first_price = history[0];
+            let last_recorded_price = history[history.len() - 1];
+            let trend_percentage = if first_price > 0 {
+                ((last_recorded_price - first_price) as f32 / first_price as f32) * 100.0
+            } else {
+                0.0
+            };
+
+            v.blank();
+            v.say("Market Analysis:");
+            if trend_percentage > 15.0 {
+                v.say(format!("Strong upward trend: {:.1}%", trend_percentage));
+            } else if trend_percentage > 5.0 {
+                v.say(format!("Upward trend: {:.1}%", trend_percentage));
+            } else if trend_percentage < -15.0 {
+                v.say(format!("Strong downward trend: {:.1}%", trend_percentage));
+            } else if trend_percentage < -5.0 {
+                v.say(format!("Downward trend: {:.1}%", trend_percentage));
+            } else {
+                v.say(format!("Stable price: {:.1}%", trend_percentage));
+            }
+
+            v.say("Trading Recommendation:");
+            if trend_percentage > 10.0 {
+                v.say("Consider selling - prices are high and may drop soon.");
+            } else if trend_percentage < -10.0 {
+                v.say("Good time to buy - prices are low and may rise soon.");
+            } else {
+                v.say("Market is stable - no strong buy/sell signals.");
+            }
+        }
+
+        // Compare today's prices across the city
+        v.blank();
+        v.say(format!("{} across the city today:", substance));
+        let city_prices: Vec<(&String, i32)> = locations
+            .iter()
+            .map(|location| (location, self.engine.market.price(location, substance)))
+            .collect();
+        let cheapest = city_prices
+            .iter()
+            .map(|(_, price)| *price)
+            .min()
+            .unwrap_or(0);
+        let dearest = city_prices
+            .iter()
+            .map(|(_, price)| *price)
+            .max()
+            .unwrap_or(0);
+        for (location, price) in city_prices {
+            let note = if price == cheapest {
+                " (cheapest)"
+            } else if price == dearest {
+                " (priciest)"
+            } else {
+                ""
+            };
+            v.say(format!(
+                "  {:<20} ${}{}{}",
+                location,
+                price,
+                note,
+                if here(location) {
+                    " (you are here)"
+                } else {
+                    ""
+                }
+            ));
+        }
+        v
+    }
+
+    fn news_view(&self) -> View {
+        let mut v = View::new("News");
+        v.say("What's moving prices on the street right now.");
+        v.blank();
+
+        if self.engine.market.events.is_empty() {
+            v.say("Nothing in the news. Prices are following supply and demand.");
+            return v;
+        }
+
+        for event in &self.engine.market.events {
+            let lasts = if event.days_remaining == 1 {
+                "last day".to_string()
+            } else {
+                format!("{} more days", event.days_remaining)
+            };
+            v.say(format!(
+                "- {} ({}, {})",
+                event.headline(),
+                event.effect(),
+                lasts
+            ));
+        }
+        v
+    }
+
+    fn net_worth_view(&self) -> View {
+        let mut v = View::new("Net Worth");
+        v.say("What you'd have if you sold up today.");
+        v.text.extend(self.net_worth_lines());
+        v.say("Stock counts at what selling all of it where it is would fetch today.");
+        v.say("Weapons count at what a dealer pays for them used.");
+        v.say(format!(
+            "Dirty cash counts for {}% of what it says until it's laundered.",
+            ((1.0 - self.engine.rules().dirty_cash_penalty) * 100.0).round()
+        ));
+        if self.engine.rules().fire_sale {
+            v.say("These rules end with a fire sale: everything but cash goes at a discount.");
+        }
+        v
+    }
+
+    fn net_worth_lines(&self) -> Vec<Line<'static>> {
+        let worth = self.engine.net_worth_breakdown();
+        let rows = [
+            ("Clean cash", worth.cash),
+            ("Dirty cash", worth.dirty_cash),
+            ("Dirty penalty", -worth.dirty_cash_penalty),
+            ("Bank", worth.bank),
+            ("Debt", -worth.debt),
+            ("Carried stock", worth.carried_stock),
+            ("Stashed stock", worth.stashed_stock),
+            ("Houses, fronts", worth.property),
+            ("Weapons", worth.weapons),
+            ("Net worth", worth.total()),
+        ];
+        rows.into_iter()
+            .map(|(label, amount)| Line::from(format!("  {:<14} ${:>9}", label, amount)))
+            .collect()
+    }
+
+    fn new_game_view(&self) -> View {
+        let mut v = View::new("New Game");
+        v.say(format!("Current game seed: {}", self.engine.seed()));
+        v.say(format!("Name: {}", self.player_name));
+        v.say(format!("Rules: {}", self.rules.summary()));
+        v.say(format!(
+            "Content pack: {} ({} locations, {} substances, {} weapons)",
+            self.content.name,
+            self.content.locations.len(),
+            self.content.substances.len(),
+            self.content.weapons.len()
+        ));
+        if !self.content.description.is_empty() {
+            v.say(format!("  {}", self.content.description));
+        }
+        v.say("Scores are only comparable between games played under the same rules.");
+
+        v.row("Start new game", Some(Command::StartNewGame));
+        v.row("Change name", Some(Command::Ask(Ask::Name)));
+        for difficulty in Difficulty::PRESETS {
+            let chosen = self.rules.difficulty == difficulty;
+            v.row(
+                format!(
+                    "Difficulty: {}{}",
+                    difficulty,
+                    if chosen { " (chosen)" } else { "" }
+                ),
+                Some(Command::SetDifficulty(difficulty)),
+            );
+        }
+        v.row("Edit rules", Some(Command::Open(Screen::Rules)));
+        v.row("Load content pack", Some(Command::Ask(Ask::ContentPack)));
+        v.row("Use built-in pack", Some(Command::UseBuiltinPack));
+        v
+    }
+
+    fn rules_view(&self) -> View {
+        let mut v = View::new("Rules");
+        v.say(format!("Rules: {}", self.rules.summary()));
+        v.say("Changing any of these makes the game a custom one.");
+
+        let edited = &self.rules;
+        let values = [
+            edited.max_days.to_string(),
+            format!("${}", edited.starting_cash),
+            format!("${}", edited.starting_debt),
+            edited.carrying_capacity.to_string(),
+            format!("{}%", edited.loan_shark_interest * 100.0),
+            edited.stash_house_capacity.to_string(),
+            format!("${}", edited.hospital_cost_per_point),
+            if edited.fire_sale { "yes" } else { "no" }.to_string(),
+            format!("{}%", edited.bank_interest * 100.0),
+            match edited.bank_withdrawal_limit {
+                Some(limit) => format!("${}", limit),
+                None => "no limit".to_string(),
+            },
+            format!("{}%", edited.laundering_fee * 100.0),
+            format!("${}", edited.laundering_limit),
+            format!("{}%", edited.dirty_cash_penalty * 100.0),
+        ];
+        for (index, (name, value)) in rule_names().into_iter().zip(values).enumerate() {
+            let command = if index == 7 {
+                Command::ToggleFireSale
+            } else {
+                Command::Ask(Ask::Rule(index))
+            };
+            v.row(format!("{}: {}", name, value), Some(command));
+        }
+        v
+    }
+
+    fn high_scores_view(&self) -> View {
+        let mut v = View::new("High Scores");
+        v.say("Scores are only comparable between games played under the same rules.");
+        v.say(format!(
+            "Showing: < {} >",
+            self.score_filter
+                .map_or("All".to_string(), |difficulty| difficulty.to_string())
+        ));
+        v.blank();
+        v.text
+            .extend(self.high_score_lines(self.score_filter, usize::MAX));
+        v
+    }
+
+    /// The best `limit` scores played under `filter`, with this game's new
+    /// entry picked out.
+    fn high_score_lines(&self, filter: Option<Difficulty>, limit: usize) -> Vec<Line<'static>> {
+        let shown: Vec<(usize, &HighScore)> = self
+            .high_scores
+            .entries()
+            .iter()
+            .enumerate()
+            .filter(|(_, score)| filter.is_none_or(|d| score.rules.difficulty == d))
+            .take(limit)
+            .collect();
+
+        if shown.is_empty() {
+            return vec![Line::from("No scores yet.")];
+        }
+
+        let mut lines = vec![Line::styled(
+            format!(
+                "  {:>3}  {:<16} {:<10} {:>10} {:>5} {:>20}  Rules",
+                "#", "Name", "Date", "Net Worth", "Days", "Seed"
+            ),
+            Style::new().add_modifier(Modifier::BOLD),
+        )];
+        for (rank, (index, score)) in shown.into_iter().enumerate() {
+            let new = self.new_high_score == Some(index);
+            let line = format!(
+                "{} {:>3}  {:<16} {:<10} {:>10} {:>5} {:>20}  {}",
+                if new { '>' } else { ' ' },
+                rank + 1,
+                score.name,
+                score.date.format("%Y-%m-%d").to_string(),
+                format!("${}", score.net_worth),
+                score.days_survived,
+                score.seed,
+                score.rules.difficulty
+            );
+            lines.push(if new {
+                Line::styled(line, Style::new().fg(Color::Yellow))
+            } else {
+                Line::from(line)
+            });
+        }
+        lines
+    }
+
+    fn game_over_view(&self) -> View {
+        let mut v = View::new("GAME OVER");
+        if let GameState::GameOver(reason) = &self.engine.state {
+            v.warn(reason.to_string());
+        }
+
+        v.row("Play again", Some(Command::PlayAgain));
+        v.row("Replay this seed", Some(Command::ReplaySeed));
+        v.row("New game", Some(Command::Open(Screen::NewGame)));
+        v.row("Load game", Some(Command::Load(MANUAL_SAVE_FILE)));
+        v.row("Quit", Some(Command::Quit));
+
+        v.blank();
+        v.say("Final Stats:");
+        v.say(format!("Days survived: {}", self.engine.days_survived()));
+        v.say(format!("Seed: {}", self.engine.seed()));
+        v.say(format!("Rules: {}", self.engine.rules().summary()));
+        v.blank();
+        v.text.extend(self.net_worth_lines());
+        if let Some(proceeds) = self.engine.fire_sale_proceeds() {
+            v.say(format!(
+                "The fire sale turned everything else into ${} of that cash.",
+                proceeds
+            ));
+        }
+
+        v.blank();
+        v.text.extend(self.inventory_lines());
+
+        if !self.engine.player.stash_houses.is_empty() {
+            v.say("Stash houses:");
+            for location in self.location_names() {
+                let Some(stash) = self.engine.player.stash_houses.get(&location) else {
+                    continue;
+                };
+                if stash.total_items() == 0 {
+                    v.say(format!("  {}: empty", location));
+                    continue;
+                }
+                v.say(format!("  {}: {} units", location, stash.total_items()));
+                for substance in self.substance_names() {
+                    let amount = stash.inventory.get(&substance).copied().unwrap_or(0);
+                    if amount > 0 {
+                        v.say(format!("    {}: {}", substance, amount));
+                    }
+                }
+            }
+        }
+
+        v.blank();
+        let difficulty = self.engine.rules().difficulty;
+        match self.new_high_score {
+            Some(index) => {
+                let rank = self.high_scores.entries()[..=index]
+                    .iter()
+                    .filter(|score| score.rules.difficulty == difficulty)
+                    .count();
+                v.text.push(Line::styled(
+                    format!("New high score! #{} of {} games.", rank, difficulty),
+                    Style::new().fg(Color::Green),
+                ));
+            }
+            None => v.say("Not enough for the high score table this time."),
+        }
+        if let Some(error) = &self.high_score_error {
+            v.warn(error.clone());
+        }
+
+        v.blank();
+        v.say(format!("Top {} scores:", difficulty));
+        v.text.extend(self.high_score_lines(Some(difficulty), 10));
+
+        if let Some(status) = &self.replay_status {
+            v.blank();
+            v.say(status.clone());
+        }
+        v
+    }
+
+    // --- Drawing ---
+
+    fn draw(&mut self, frame: &mut Frame, view: &View) {
+        let [status, middle, footer] = Layout::vertical([
+            Constraint::Length(4),
+            Constraint::Min(0),
+            Constraint::Length(3),
+        ])
+        .areas(frame.area());
+        let [side, right] =
+            Layout::horizontal([Constraint::Length(SIDE_PANEL_WIDTH), Constraint::Min(0)])
+                .areas(middle);
+        let [body, log] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(LOG_HEIGHT)]).areas(right);
+
+        self.draw_status(frame, status);
+        self.draw_side_panel(frame, side);
+        self.draw_body(frame, body, view);
+        self.draw_log(frame, log);
+        self.draw_footer(frame, footer, view);
+    }
+
+    fn draw_status(&self, frame: &mut Frame, area: Rect) {
+        let player = &self.engine.player;
+        let lines = vec![
+            Line::from(format!(
+                "Day {}/{}   Cash ${} (${} dirty)   Bank ${}   Debt ${}",
+                player.day,
+                self.engine.rules().max_days,
+                player.cash,
+                player.dirty_cash,
+                player.bank_balance,
+                player.debt
+            )),
+            Line::from(format!(
+                "{}   Health {}   Heat {} ({}, {}% stop chance)   Space {}/{}",
+                player.current_location,
+                player.health,
+                player.heat,
+                player.heat_description(),
+                self.engine.police_stop_chance(),
+                player.total_items(),
+                player.carrying_capacity
+            )),
+        ];
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(" DopeWars ")),
+            area,
+        );
+    }
+
+    fn draw_side_panel(&self, frame: &mut Frame, area: Rect) {
+        let entry_line = |entry: &NavEntry| {
+            let line = format!("{} {}", entry.key, entry.label);
+            match entry.command {
+                Some(_) => Line::from(line),
+                None => Line::styled(line, Style::new().fg(Color::DarkGray)),
+            }
+        };
+        let heading =
+            |text: &'static str| Line::styled(text, Style::new().add_modifier(Modifier::BOLD));
+
+        let (navigation, game) = self.nav();
+        let mut lines = vec![heading("Navigation")];
+        lines.extend(navigation.iter().map(entry_line));
+        lines.push(Line::default());
+        lines.push(heading("Game"));
+        lines.extend(game.iter().map(entry_line));
+        lines.push(Line::default());
+        lines.extend(self.inventory_lines());
+
+        frame.render_widget(Paragraph::new(lines).block(Block::bordered()), area);
+    }
+
+    fn draw_body(&mut self, frame: &mut Frame, area: Rect, view: &View) {
+        let block = Block::bordered().title(format!(" {} ", view.title));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        // The rows come first, then the chart, then as much text as fits
+        let width = inner.width.max(1) as usize;
+        let text_height: usize = view
+            .text
+            .iter()
+            .map(|line| line.width().max(1).div_ceil(width))
+            .sum();
+        let rows_height = (view.rows.len() as u16).min(inner.height);
+        let chart_height = match view.chart {
+            Some(_) => CHART_HEIGHT.min(inner.height - rows_height),
+            None => 0,
+        };
+        let gap = u16::from(!view.rows.is_empty() && text_height > 0)
+            .min(inner.height - rows_height - chart_height);
+        let room = inner.height - rows_height - chart_height - gap;
+        let text_height = text_height.min(u16::MAX as usize) as u16;
+        self.text_overflow = text_height.saturating_sub(room);
+        self.scroll = self.scroll.min(self.text_overflow);
+
+        let [text_area, _, rows_area, chart_area] = Layout::vertical([
+            Constraint::Length(text_height.min(room)),
+            Constraint::Length(gap),
+            Constraint::Length(rows_height),
+            Constraint::Length(chart_height),
+        ])
+        .areas(inner);
+
+        frame.render_widget(
+            Paragraph::new(view.text.clone())
+                .wrap(Wrap { trim: false })
+                .scroll((self.scroll, 0)),
+            text_area,
+        );
+
+        let items: Vec<ListItem> = view
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                let number = if i < 9 {
+                    (i + 1).to_string()
+                } else {
+                    " ".to_string()
+                };
+                let line = format!("{} {}", number, row.label);
+                if row.command.is_some() || view.browse {
+                    ListItem::new(line)
+                } else {
+                    ListItem::new(line).style(Style::new().fg(Color::DarkGray))
+                }
+            })
+            .collect();
+        let list = List::new(items)
+            .highlight_symbol("> ")
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, rows_area, &mut self.selection);
+
+        if let Some(prices) = &view.chart {
+            // As many of the latest days as there is room for
+            let fits = (chart_area.width as usize + 1) / (CHART_BAR_WIDTH as usize + 1);
+            let latest = &prices[prices.len().saturating_sub(fits)..];
+            let today = latest.len().saturating_sub(1);
+            let bars: Vec<Bar> = latest
+                .iter()
+                .enumerate()
+                .map(|(i, (label, price))| {
+                    let color = if i == today {
+                        Color::Yellow
+                    } else {
+                        Color::Green
+                    };
+                    Bar::default()
+                        .value((*price).max(0) as u64)
+                        .label(Line::from(label.clone()))
+                        .text_value(format!("${}", price))
+                        .style(Style::new().fg(color))
+                        .value_style(Style::new().fg(Color::Black).bg(color))
+                })
+                .collect();
+            let chart = BarChart::default()
+                .block(Block::new().title("Price history"))
+                .data(BarGroup::default().bars(&bars))
+                .bar_width(CHART_BAR_WIDTH)
+                .bar_gap(1);
+            frame.render_widget(chart, chart_area);
+        }
+    }
+
+    fn draw_log(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered().title(" Messages ");
+        let inner = block.inner(area);
+        let width = inner.width.max(1) as usize;
+
+        // The newest messages that fit, oldest at the top
+        let mut room = inner.height as usize;
+        let mut shown = Vec::new();
+        for message in self.engine.message_log.iter().rev() {
+            let height = message.chars().count().max(1).div_ceil(width);
+            if height > room {
+                break;
+            }
+            room -= height;
+            shown.push(Line::from(message.clone()));
+        }
+        shown.reverse();
+
+        frame.render_widget(
+            Paragraph::new(shown)
+                .wrap(Wrap { trim: false })
+                .block(block),
+            area,
+        );
+    }
+
+    fn draw_footer(&self, frame: &mut Frame, area: Rect, view: &View) {
+        let notice = self
+            .notice
+            .as_ref()
+            .map(|notice| Line::styled(notice.clone(), Style::new().fg(Color::Yellow)));
+        let hint_style = Style::new().fg(Color::DarkGray);
+
+        let lines = match &self.prompt {
+            Some(prompt) => {
+                let max = self
+                    .max(&prompt.ask)
+                    .map_or(String::new(), |max| format!("  Tab: max {}", max));
+                vec![
+                    Line::from(format!("{} {}_", self.question(&prompt.ask), prompt.input)),
+                    notice.unwrap_or_else(|| Line::from(self.preview(prompt))),
+                    Line::styled(format!("Enter: ok  Esc: cancel{}", max), hint_style),
+                ]
+            }
+            None => {
+                let mut hints = "Up/Down: choose  Enter or 1-9: pick  Esc: back".to_string();
+                if matches!(view.title.as_str(), "Price Charts" | "High Scores") {
+                    hints.push_str("  Left/Right: switch");
+                }
+                if self.text_overflow > 0 {
+                    hints.push_str("  PgUp/PgDn: scroll");
+                }
+                hints.push_str("  q: quit");
+                vec![
+                    notice.unwrap_or_default(),
+                    Line::default(),
+                    Line::styled(hints, hint_style),
+                ]
+            }
+        };
+        frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), area);
+    }
+}
+
+/// The rules editor's fields, in the window's order.
+fn rule_names() -> [&'static str; 13] {
+    [
+        "Days",
+        "Starting cash",
+        "Starting debt",
+        "Carrying capacity",
+        "Loan shark interest per day",
+        "Stash house capacity",
+        "Hospital cost per health point",
+        "Fire sale at the end",
+        "Bank interest per day",
+        "Bank withdrawals per day",
+        "Laundering fee",
+        "Laundering per front per day",
+        "Dirty cash penalty",
+    ]
+}
+
+/// Whether the rules editor's field at `index` is entered in percent.
+fn is_percent_rule(index: usize) -> bool {
+    [4, 8, 10, 12].contains(&index)
+}
+
+/// What the high score table can be narrowed to, `None` first for all of
+/// them.
+fn score_filters() -> Vec<Option<Difficulty>> {
+    std::iter::once(None)
+        .chain(Difficulty::PRESETS.into_iter().map(Some))
+        .chain(std::iter::once(Some(Difficulty::Custom)))
+        .collect()
+}
+
+/// The content pack given with `--content <file>`, if any.
+fn content_arg() -> Option<PathBuf> {
+    let mut args = std::env::args().skip(1);
+    let mut path = None;
+    while let Some(arg) = args.next() {
+        if arg == "--content" {
+            path = args.next().map(PathBuf::from);
+        }
+    }
+    path
+}
+
+fn main() -> io::Result<()> {
+    let (content, notice) = ContentPack::startup(content_arg());
+    let mut app = TuiApp::new(content, notice);
+
+    let mut terminal = ratatui::try_init()?;
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+
+    if let Some(note) = app.record_unfinished_game() {
+        println!("{}", note);
+    }
+    result
+}
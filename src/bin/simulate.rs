@@ -0,0 +1,356 @@
+//! Monte Carlo balance simulator.
+//!
+//! Plays many seeded games with each reference strategy and reports how they
+//! went: the spread of final net worth, how often players die, get arrested,
+//! lose stock to raids or drug money to the police, miss loan payments or end
+//! up buried in loan shark debt. Every strategy plays the same seeds, so a
+//! change to the rules or the content pack can be judged by running the same
+//! command before and after it.
+//!
+//! ```text
+//! cargo run --release --bin simulate -- --games 5000 --interest 8 --csv games.csv
+//! ```
+
+use dopewars_gui::game::bot::{self, GameReport, RandomPlayer, Strategy, Trader};
+use dopewars_gui::game::{ContentPack, Difficulty, GameEngine, GameRules};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const STRATEGIES: [&str; 3] = ["random", "greedy", "cautious"];
+
+/// A game counts as a debt spiral when it ends owing more than this many
+/// times the starting debt, or than this many times `DEBT_SPIRAL_FLOOR` when
+/// the rules start the player with less (or nothing) owed.
+const DEBT_SPIRAL_FACTOR: i32 = 2;
+const DEBT_SPIRAL_FLOOR: i32 = 2500;
+
+const USAGE: &str = "\
+Usage: simulate [options]
+
+  --games N            games per strategy (default 1000)
+  --seed N             seed of the first game; the rest follow on (default 0)
+  --strategy NAME      random, greedy or cautious; repeat for several (default all)
+  --difficulty NAME    easy, normal or hard (default normal)
+  --days N             override the number of days
+  --interest PERCENT   override the loan shark's daily interest
+  --content FILE       play with a content pack instead of the built-in one
+  --csv FILE           also write one row per game to FILE";
+
+struct Options {
+    games: u64,
+    first_seed: u64,
+    strategies: Vec<String>,
+    rules: GameRules,
+    content: ContentPack,
+    csv: Option<PathBuf>,
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, not \"{}\"", flag, value))
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        games: 1000,
+        first_seed: 0,
+        strategies: Vec::new(),
+        rules: GameRules::default(),
+        content: ContentPack::default(),
+        csv: None,
+    };
+    let mut days = None;
+    let mut interest = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--games" => options.games = parse_number(&arg, args.next())?,
+            "--seed" => options.first_seed = parse_number(&arg, args.next())?,
+            "--strategy" => {
+                let name = args.next().ok_or("--strategy needs a value")?;
+                if !STRATEGIES.contains(&name.as_str()) {
+                    return Err(format!("unknown strategy \"{}\"", name));
+                }
+                options.strategies.push(name);
+            }
+            "--difficulty" => {
+                let name = args.next().ok_or("--difficulty needs a value")?;
+                let difficulty = Difficulty::PRESETS
+                    .into_iter()
+                    .find(|d| d.to_string().eq_ignore_ascii_case(&name))
+                    .ok_or_else(|| format!("unknown difficulty \"{}\"", name))?;
+                options.rules = GameRules::preset(difficulty);
+            }
+            "--days" => days = Some(parse_number(&arg, args.next())?),
+            "--interest" => interest = Some(parse_number::<f32>(&arg, args.next())?),
+            "--content" => {
+                let path = PathBuf::from(args.next().ok_or("--content needs a file")?);
+                options.content = ContentPack::load(&path)
+                    .map_err(|e| format!("could not load {}: {}", path.display(), e))?;
+            }
+            "--csv" => options.csv = Some(PathBuf::from(args.next().ok_or("--csv needs a file")?)),
+            "--help" | "-h" => return Err(String::new()),
+            _ => return Err(format!("unknown option \"{}\"", arg)),
+        }
+    }
+
+    // Overrides apply on top of whichever preset was picked
+    if let Some(days) = days {
+        options.rules.max_days = days;
+        options.rules.difficulty = Difficulty::Custom;
+    }
+    if let Some(percent) = interest {
+        options.rules.loan_shark_interest = percent / 100.0;
+        options.rules.difficulty = Difficulty::Custom;
+    }
+    options
+        .rules
+        .validate()
+        .map_err(|problems| problems.join(", "))?;
+
+    if options.strategies.is_empty() {
+        options.strategies = STRATEGIES.iter().map(|name| name.to_string()).collect();
+    }
+    Ok(options)
+}
+
+fn strategy(name: &str, seed: u64) -> Box<dyn Strategy> {
+    match name {
+        "random" => Box::new(RandomPlayer::new(seed)),
+        "cautious" => Box::new(Trader::cautious()),
+        _ => Box::new(Trader::default()),
+    }
+}
+
+/// What the simulator keeps from one finished game.
+struct GameRow {
+    seed: u64,
+    net_worth: i32,
+    days_survived: i32,
+    died: bool,
+    arrests: i32,
+    convictions: i32,
+    days_in_jail: i32,
+    raids: i32,
+    raid_losses: i32,
+    cash_seized: i32,
+    dirty_cash: i32,
+    final_debt: i32,
+    debt_spiral: bool,
+    terms_missed: i32,
+    enforcer_visits: i32,
+    stalled: bool,
+}
+
+impl GameRow {
+    fn new(report: &GameReport) -> Self {
+        let player = &report.engine.player;
+        let record = &player.record;
+        GameRow {
+            seed: report.seed,
+            net_worth: report.net_worth,
+            days_survived: report.days_survived,
+            died: player.health <= 0,
+            arrests: record.arrests,
+            convictions: record.convictions,
+            days_in_jail: record.days_in_jail,
+            raids: record.raids,
+            raid_losses: record.raid_losses,
+            cash_seized: record.cash_seized,
+            dirty_cash: player.dirty_cash,
+            final_debt: player.debt,
+            debt_spiral: is_debt_spiral(player.debt, report.engine.rules().starting_debt),
+            terms_missed: player.loan.terms_missed,
+            enforcer_visits: player.loan.enforcer_visits,
+            stalled: report.stalled,
+        }
+    }
+}
+
+fn is_debt_spiral(debt: i32, starting_debt: i32) -> bool {
+    let baseline = starting_debt.max(DEBT_SPIRAL_FLOOR) as i64;
+    debt as i64 > baseline * DEBT_SPIRAL_FACTOR as i64
+}
+
+/// The value `percent` of the way up `sorted`.
+fn percentile(sorted: &[i32], percent: usize) -> i32 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    sorted[(sorted.len() - 1) * percent / 100]
+}
+
+/// `count` out of `total`, with the percentage.
+fn share(count: usize, total: usize) -> String {
+    format!(
+        "{} ({:.1}%)",
+        count,
+        count as f64 * 100.0 / total.max(1) as f64
+    )
+}
+
+fn mean(values: impl Iterator<Item = i32>, total: usize) -> f64 {
+    values.map(f64::from).sum::<f64>() / total.max(1) as f64
+}
+
+fn summarize(name: &str, rows: &[GameRow]) -> String {
+    let total = rows.len();
+    let mut worths: Vec<i32> = rows.iter().map(|row| row.net_worth).collect();
+    worths.sort_unstable();
+
+    let count = |test: fn(&GameRow) -> bool| rows.iter().filter(|row| test(row)).count();
+    let worst_raid = rows.iter().map(|row| row.raid_losses).max().unwrap_or(0);
+
+    let mut text = String::new();
+    let _ = writeln!(text, "{} ({} games)", name, total);
+    let _ = writeln!(
+        text,
+        "  Net worth    mean ${:.0}  min ${}  p10 ${}  median ${}  p90 ${}  max ${}",
+        mean(worths.iter().copied(), total),
+        percentile(&worths, 0),
+        percentile(&worths, 10),
+        percentile(&worths, 50),
+        percentile(&worths, 90),
+        percentile(&worths, 100)
+    );
+    let _ = writeln!(
+        text,
+        "  Finished     {} in the black, mean {:.1} days survived",
+        share(count(|row| row.net_worth > 0), total),
+        mean(rows.iter().map(|row| row.days_survived), total)
+    );
+    let _ = writeln!(
+        text,
+        "  Died         {}",
+        share(count(|row| row.died), total)
+    );
+    let _ = writeln!(
+        text,
+        "  Arrested     {}, mean {:.2} arrests, {:.2} convictions, {:.1} days in jail",
+        share(count(|row| row.arrests > 0), total),
+        mean(rows.iter().map(|row| row.arrests), total),
+        mean(rows.iter().map(|row| row.convictions), total),
+        mean(rows.iter().map(|row| row.days_in_jail), total)
+    );
+    let _ = writeln!(
+        text,
+        "  Raided       {}, mean loss ${:.0}, worst ${}",
+        share(count(|row| row.raids > 0), total),
+        mean(rows.iter().map(|row| row.raid_losses), total),
+        worst_raid
+    );
+    let _ = writeln!(
+        text,
+        "  Dirty cash   {} had some seized, mean ${:.0} seized, ${:.0} still dirty at the end",
+        share(count(|row| row.cash_seized > 0), total),
+        mean(rows.iter().map(|row| row.cash_seized), total),
+        mean(rows.iter().map(|row| row.dirty_cash), total)
+    );
+    let _ = writeln!(
+        text,
+        "  Debt spiral  {} ended owing over {}x the starting debt or ${}, whichever is more, mean final debt ${:.0}",
+        share(count(|row| row.debt_spiral), total),
+        DEBT_SPIRAL_FACTOR,
+        DEBT_SPIRAL_FACTOR * DEBT_SPIRAL_FLOOR,
+        mean(rows.iter().map(|row| row.final_debt), total)
+    );
+    let _ = writeln!(
+        text,
+        "  Paid late    {}, mean {:.2} missed payments, {:.2} enforcer visits",
+        share(count(|row| row.terms_missed > 0), total),
+        mean(rows.iter().map(|row| row.terms_missed), total),
+        mean(rows.iter().map(|row| row.enforcer_visits), total)
+    );
+    if count(|row| row.stalled) > 0 {
+        let _ = writeln!(
+            text,
+            "  Stalled      {} stopped before the end",
+            share(count(|row| row.stalled), total)
+        );
+    }
+    text
+}
+
+fn csv(results: &[(String, Vec<GameRow>)]) -> String {
+    let mut text = String::from(
+        "strategy,seed,net_worth,days_survived,died,arrests,convictions,days_in_jail,\
+         raids,raid_losses,cash_seized,dirty_cash,final_debt,debt_spiral,terms_missed,enforcer_visits,stalled\n",
+    );
+    for (name, rows) in results {
+        for row in rows {
+            let _ = writeln!(
+                text,
+                "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                name,
+                row.seed,
+                row.net_worth,
+                row.days_survived,
+                row.died,
+                row.arrests,
+                row.convictions,
+                row.days_in_jail,
+                row.raids,
+                row.raid_losses,
+                row.cash_seized,
+                row.dirty_cash,
+                row.final_debt,
+                row.debt_spiral,
+                row.terms_missed,
+                row.enforcer_visits,
+                row.stalled
+            );
+        }
+    }
+    text
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(e) => {
+            if !e.is_empty() {
+                eprintln!("simulate: {}", e);
+            }
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let last_seed = options.first_seed + options.games.saturating_sub(1);
+    println!(
+        "{} games per strategy, seeds {} to {}",
+        options.games, options.first_seed, last_seed
+    );
+    println!("Content: {}", options.content.name);
+    println!("Rules: {}", options.rules.summary());
+    println!();
+
+    let mut results = Vec::new();
+    for name in &options.strategies {
+        let rows: Vec<GameRow> = (0..options.games)
+            .map(|i| {
+                let seed = options.first_seed + i;
+                let engine =
+                    GameEngine::with_rules(seed, options.content.clone(), options.rules.clone());
+                GameRow::new(&bot::play(engine, strategy(name, seed).as_mut()))
+            })
+            .collect();
+        println!("{}", summarize(name, &rows));
+        results.push((name.clone(), rows));
+    }
+
+    if let Some(path) = &options.csv {
+        if let Err(e) = fs::write(path, csv(&results)) {
+            eprintln!("simulate: could not write {}: {}", path.display(), e);
+            return ExitCode::FAILURE;
+        }
+        println!("Wrote one row per game to {}", path.display());
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::StashUpgrade;
+
+/// One thing the player can do, as data. `GameEngine::apply` carries it out
+/// exactly as the matching button in a frontend would.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Action {
+    Buy {
+        substance: String,
+        amount: i32,
+    },
+    Sell {
+        substance: String,
+        amount: i32,
+    },
+    Travel(String),
+    Borrow(i32),
+    Repay(i32),
+    BankDeposit(i32),
+    BankWithdraw(i32),
+    Heal,
+    BuyWeapon(String),
+    EquipWeapon(String),
+    /// Buy a stash house where the player is standing.
+    BuyStashHouse,
+    /// Sell the stash house where the player is standing.
+    SellStashHouse,
+    /// Buy an upgrade for the stash house where the player is standing.
+    UpgradeStashHouse(StashUpgrade),
+    /// Buy a front business where the player is standing.
+    BuyFront,
+    Launder(i32),
+    Deposit {
+        substance: String,
+        amount: i32,
+    },
+    Withdraw {
+        substance: String,
+        amount: i32,
+    },
+    PoliceSurrender,
+    PoliceFight,
+    PoliceRun,
+    PoliceNegotiate,
+    OfferBribe(i32),
+    RefuseBribe,
+    PostBail,
+    BorrowBail,
+    AwaitTrial,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Buy { substance, amount } => write!(f, "Buy {} {}", amount, substance),
+            Action::Sell { substance, amount } => write!(f, "Sell {} {}", amount, substance),
+            Action::Travel(location) => write!(f, "Travel to {}", location),
+            Action::Borrow(amount) => write!(f, "Borrow ${}", amount),
+            Action::Repay(amount) => write!(f, "Repay ${}", amount),
+            Action::BankDeposit(amount) => write!(f, "Pay ${} into the bank", amount),
+            Action::BankWithdraw(amount) => write!(f, "Take ${} out of the bank", amount),
+            Action::Heal => write!(f, "Go to hospital"),
+            Action::BuyWeapon(weapon) => write!(f, "Buy a {}", weapon),
+            Action::EquipWeapon(weapon) => write!(f, "Equip the {}", weapon),
+            Action::BuyStashHouse => write!(f, "Buy a stash house"),
+            Action::SellStashHouse => write!(f, "Sell the stash house"),
+            Action::UpgradeStashHouse(upgrade) => {
+                write!(f, "Upgrade the stash house: {}", upgrade)
+            }
+            Action::BuyFront => write!(f, "Buy a front business"),
+            Action::Launder(amount) => write!(f, "Launder ${}", amount),
+            Action::Deposit { substance, amount } => {
+                write!(f, "Stash {} {}", amount, substance)
+            }
+            Action::Withdraw { substance, amount } => {
+                write!(f, "Take {} {} from the stash", amount, substance)
+            }
+            Action::PoliceSurrender => write!(f, "Surrender to the police"),
+            Action::PoliceFight => write!(f, "Fight the police"),
+            Action::PoliceRun => write!(f, "Run from the police"),
+            Action::PoliceNegotiate => write!(f, "Talk to the police"),
+            Action::OfferBribe(amount) => write!(f, "Offer a ${} bribe", amount),
+            Action::RefuseBribe => write!(f, "Refuse to pay"),
+            Action::PostBail => write!(f, "Post bail"),
+            Action::BorrowBail => write!(f, "Borrow bail from the loan shark"),
+            Action::AwaitTrial => write!(f, "Wait for trial"),
+        }
+    }
+}
@@ -0,0 +1,584 @@
+//! Automated play.
+//!
+//! A `Strategy` looks at a read-only `GameView` and picks the next `Action`;
+//! `play` keeps asking it until the game ends. Nothing here touches a
+//! frontend, so whole games can be played in a loop to compare strategies or
+//! to check how a change to the rules shifts the scores.
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashMap;
+
+use super::{
+    Action, ContentPack, Custody, Front, GameEngine, GameError, GameRules, GameState, LoanAccount,
+    MarketEvent, Player, PoliceEncounter, PoliceState, Service, StashHouse, StashUpgrade, MAX_HEAT,
+};
+
+/// Most actions a strategy may take in one day. A strategy that goes past it
+/// is stuck, and the game is stopped where it is.
+pub const MAX_ACTIONS_PER_DAY: usize = 200;
+
+/// What a strategy gets to see of a game: everything on the player's screen,
+/// none of the engine's internals.
+#[derive(Clone, Copy)]
+pub struct GameView<'a> {
+    engine: &'a GameEngine,
+}
+
+impl<'a> GameView<'a> {
+    pub fn new(engine: &'a GameEngine) -> Self {
+        GameView { engine }
+    }
+
+    pub fn player(&self) -> &'a Player {
+        &self.engine.player
+    }
+
+    pub fn day(&self) -> i32 {
+        self.engine.player.day
+    }
+
+    /// Days left including today.
+    pub fn days_left(&self) -> i32 {
+        self.engine.rules().max_days - self.engine.player.day + 1
+    }
+
+    pub fn cash(&self) -> i32 {
+        self.engine.player.cash
+    }
+
+    /// The part of `cash` that is drug money.
+    pub fn dirty_cash(&self) -> i32 {
+        self.engine.player.dirty_cash
+    }
+
+    pub fn debt(&self) -> i32 {
+        self.engine.player.debt
+    }
+
+    pub fn loan(&self) -> &'a LoanAccount {
+        &self.engine.player.loan
+    }
+
+    pub fn credit_available(&self) -> i32 {
+        self.engine.credit_available()
+    }
+
+    pub fn bank_balance(&self) -> i32 {
+        self.engine.player.bank_balance
+    }
+
+    pub fn service_here(&self, service: Service) -> bool {
+        self.engine.service_here(service)
+    }
+
+    pub fn offers(&self, location: &str, service: Service) -> bool {
+        self.engine
+            .content()
+            .location(location)
+            .is_some_and(|l| l.offers(service))
+    }
+
+    pub fn location(&self) -> &'a str {
+        &self.engine.player.current_location
+    }
+
+    /// How many units of `substance` the player is carrying.
+    pub fn carried(&self, substance: &str) -> i32 {
+        self.engine
+            .player
+            .inventory
+            .get(substance)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    pub fn inventory(&self) -> &'a HashMap<String, i32> {
+        &self.engine.player.inventory
+    }
+
+    pub fn stash_houses(&self) -> &'a HashMap<String, StashHouse> {
+        &self.engine.player.stash_houses
+    }
+
+    pub fn fronts(&self) -> &'a HashMap<String, Front> {
+        &self.engine.player.fronts
+    }
+
+    pub fn front_price(&self, location: &str) -> i32 {
+        self.engine.front_price(location)
+    }
+
+    /// How much more the front here can launder today.
+    pub fn laundering_left(&self) -> i32 {
+        self.engine.laundering_left()
+    }
+
+    /// Today's price of `substance` where the player is standing.
+    pub fn price(&self, substance: &str) -> i32 {
+        self.engine.local_price(substance)
+    }
+
+    /// Today's price of `substance` in `location`.
+    pub fn price_in(&self, location: &str, substance: &str) -> i32 {
+        self.engine.market.price(location, substance)
+    }
+
+    /// Previous days' prices of `substance` in `location`, oldest first.
+    pub fn history(&self, location: &str, substance: &str) -> &'a [i32] {
+        self.engine.market.history(location, substance)
+    }
+
+    pub fn quote_buy(&self, substance: &str, amount: i32) -> i32 {
+        self.engine.quote_buy(substance, amount)
+    }
+
+    pub fn quote_sell(&self, substance: &str, amount: i32) -> i32 {
+        self.engine.quote_sell(substance, amount)
+    }
+
+    /// Largest amount of `substance` the player can both afford and carry.
+    pub fn max_buy(&self, substance: &str) -> i32 {
+        self.engine.max_buy(substance)
+    }
+
+    /// The news that is moving prices right now.
+    pub fn events(&self) -> &'a [MarketEvent] {
+        &self.engine.market.events
+    }
+
+    pub fn police(&self) -> Option<&'a PoliceEncounter> {
+        self.engine.police_encounter()
+    }
+
+    pub fn custody(&self) -> Option<&'a Custody> {
+        self.engine.custody()
+    }
+
+    pub fn heal_cost(&self) -> i32 {
+        self.engine.heal_cost()
+    }
+
+    pub fn net_worth(&self) -> i32 {
+        self.engine.net_worth()
+    }
+
+    pub fn substances(&self) -> impl Iterator<Item = &'a str> {
+        self.engine.content().substance_names()
+    }
+
+    pub fn locations(&self) -> impl Iterator<Item = &'a str> {
+        self.engine.content().location_names()
+    }
+
+    pub fn content(&self) -> &'a ContentPack {
+        self.engine.content()
+    }
+
+    pub fn rules(&self) -> &'a GameRules {
+        self.engine.rules()
+    }
+}
+
+/// A way of playing the game.
+pub trait Strategy {
+    /// What to do next. Called again after every action until the game ends.
+    fn next_action(&mut self, game: &GameView) -> Action;
+
+    /// Told when the engine refuses an action, so the strategy can try
+    /// something else next time.
+    fn rejected(&mut self, _action: &Action, _error: &GameError) {}
+}
+
+/// How a game played by a strategy went.
+pub struct GameReport {
+    pub seed: u64,
+    pub net_worth: i32,
+    pub days_survived: i32,
+    /// Actions the strategy chose, including refused ones.
+    pub actions: usize,
+    /// Actions the engine refused.
+    pub rejected: usize,
+    /// Whether the strategy got stuck before the game ended.
+    pub stalled: bool,
+    /// The game as it stood at the end.
+    pub engine: GameEngine,
+}
+
+impl GameReport {
+    /// Why the game ended, or `None` if it was stopped early.
+    pub fn ending(&self) -> Option<&str> {
+        match &self.engine.state {
+            GameState::GameOver(reason) => Some(reason),
+            GameState::Running => None,
+        }
+    }
+}
+
+/// Plays `engine` to the end with `strategy` in charge.
+pub fn play(mut engine: GameEngine, strategy: &mut dyn Strategy) -> GameReport {
+    let mut actions = 0;
+    let mut rejected = 0;
+    let mut stalled = false;
+    let mut day = engine.player.day;
+    let mut actions_today = 0;
+
+    while engine.is_running() {
+        if actions_today >= MAX_ACTIONS_PER_DAY {
+            stalled = true;
+            break;
+        }
+
+        let action = strategy.next_action(&GameView::new(&engine));
+        actions += 1;
+        actions_today += 1;
+        if let Err(e) = engine.apply(&action) {
+            rejected += 1;
+            strategy.rejected(&action, &e);
+        }
+        engine.update_state();
+
+        if engine.player.day != day {
+            day = engine.player.day;
+            actions_today = 0;
+        }
+    }
+
+    GameReport {
+        seed: engine.seed(),
+        net_worth: engine.net_worth(),
+        days_survived: engine.days_survived(),
+        actions,
+        rejected,
+        stalled,
+        engine,
+    }
+}
+
+/// Buys whatever sells for the most more in another borough today, goes
+/// there and sells it. Pays off the loan shark as soon as it can, routing its
+/// runs past him and the hospital when it needs them, and never argues with
+/// the police. Once out of debt it buys fronts as it can spare the money and
+/// launders through them whenever it passes one.
+///
+/// The default trader is greedy and puts everything into every run;
+/// `Trader::cautious` wants bigger margins, stakes half its cash and stops
+/// dealing while the police are interested.
+#[derive(Debug, Clone)]
+pub struct Trader {
+    /// Price rise in percent that makes a run worth it.
+    pub min_margin_percent: i32,
+    /// Share of cash, in percent, put into a single run.
+    pub stake_percent: i32,
+    /// Health below which the trader goes to hospital.
+    pub heal_below: i32,
+    /// Heat at which the trader stops buying until it cools off.
+    pub lie_low_at: i32,
+    /// Where the stock just bought is headed.
+    destination: Option<String>,
+    /// Set once a purchase has been refused here, so the trader moves on.
+    buying_refused: bool,
+}
+
+impl Trader {
+    /// Cash kept back from repaying debt, so there is something to trade with.
+    const CASH_RESERVE: i32 = 500;
+    /// A front is only bought with this many times its price in hand.
+    const FRONT_CASH_MULTIPLE: i32 = 4;
+
+    pub fn cautious() -> Self {
+        Trader {
+            min_margin_percent: 25,
+            stake_percent: 50,
+            heal_below: 70,
+            lie_low_at: 45,
+            ..Self::default()
+        }
+    }
+
+    fn travel(&mut self, location: String) -> Action {
+        self.destination = None;
+        self.buying_refused = false;
+        Action::Travel(location)
+    }
+
+    /// The service the trader wants next and can't use here: the hospital
+    /// when it's hurt, otherwise the loan shark while there's debt to pay.
+    fn errand(&self, game: &GameView) -> Option<Service> {
+        let hurt = game.player().health < self.heal_below && game.heal_cost() <= game.cash();
+        let can_repay = game.debt() > 0 && game.cash() > Self::CASH_RESERVE;
+        [(Service::Hospital, hurt), (Service::LoanShark, can_repay)]
+            .into_iter()
+            .find(|(_, wanted)| *wanted)
+            .map(|(service, _)| service)
+            .filter(|service| !game.service_here(*service))
+    }
+
+    /// The substance to buy here and where to sell it, if any run is worth
+    /// it. Only runs to a location offering `errand` count, if there is one.
+    fn best_run(&self, game: &GameView, errand: Option<Service>) -> Option<(String, String)> {
+        let mut best: Option<(i32, String, String)> = None;
+        for substance in game.substances() {
+            let here = game.price(substance);
+            if here <= 0 || game.max_buy(substance) <= 0 {
+                continue;
+            }
+            for location in game.locations() {
+                if errand.is_some_and(|service| !game.offers(location, service)) {
+                    continue;
+                }
+                let margin = (game.price_in(location, substance) - here) * 100 / here;
+                if margin >= self.min_margin_percent
+                    && best.as_ref().is_none_or(|(top, _, _)| margin > *top)
+                {
+                    best = Some((margin, substance.to_string(), location.to_string()));
+                }
+            }
+        }
+        best.map(|(_, substance, location)| (substance, location))
+    }
+}
+
+impl Default for Trader {
+    fn default() -> Self {
+        Trader {
+            min_margin_percent: 10,
+            stake_percent: 100,
+            heal_below: 40,
+            lie_low_at: MAX_HEAT + 1,
+            destination: None,
+            buying_refused: false,
+        }
+    }
+}
+
+impl Strategy for Trader {
+    fn next_action(&mut self, game: &GameView) -> Action {
+        if let Some(custody) = game.custody() {
+            return if custody.bail <= game.cash() {
+                Action::PostBail
+            } else if custody.bail > game.credit_available() {
+                Action::AwaitTrial
+            } else {
+                Action::BorrowBail
+            };
+        }
+        if let Some(encounter) = game.police() {
+            return match encounter.state {
+                PoliceState::Negotiating => Action::RefuseBribe,
+                _ => Action::PoliceSurrender,
+            };
+        }
+
+        if let Some(destination) = self.destination.clone() {
+            return self.travel(destination);
+        }
+
+        if game.player().health < self.heal_below
+            && game.heal_cost() <= game.cash()
+            && game.service_here(Service::Hospital)
+        {
+            return Action::Heal;
+        }
+
+        for substance in game.substances() {
+            let amount = game.carried(substance);
+            if amount > 0 && game.price(substance) > 0 {
+                return Action::Sell {
+                    substance: substance.to_string(),
+                    amount,
+                };
+            }
+        }
+
+        let repayable = game.debt().min(game.cash() - Self::CASH_RESERVE);
+        if repayable > 0 && game.service_here(Service::LoanShark) {
+            return Action::Repay(repayable);
+        }
+
+        let launderable = game.dirty_cash().min(game.laundering_left());
+        if launderable > 0 {
+            return Action::Launder(launderable);
+        }
+
+        let front_price = game.front_price(game.location());
+        if game.debt() == 0
+            && !game.fronts().contains_key(game.location())
+            && game.cash() >= front_price * Self::FRONT_CASH_MULTIPLE
+        {
+            return Action::BuyFront;
+        }
+
+        let errand = self.errand(game);
+
+        // Nothing bought on the last day can be sold again
+        if game.days_left() > 1 && !self.buying_refused && game.player().heat < self.lie_low_at {
+            if let Some((substance, location)) = self.best_run(game, errand) {
+                let amount = game.max_buy(&substance) * self.stake_percent / 100;
+                if amount > 0 {
+                    self.destination = Some(location);
+                    return Action::Buy { substance, amount };
+                }
+            }
+        }
+
+        let next = errand
+            .and_then(|service| game.locations().find(|l| game.offers(l, service)))
+            .map(str::to_string)
+            .unwrap_or_else(|| somewhere_else(game));
+        self.travel(next)
+    }
+
+    fn rejected(&mut self, action: &Action, _error: &GameError) {
+        if let Action::Buy { .. } = action {
+            self.destination = None;
+            self.buying_refused = true;
+        }
+    }
+}
+
+/// Presses buttons at random: trades, borrows, buys weapons, buys and sells
+/// stash houses and their upgrades, buys fronts and picks a random way out
+/// of every police stop. A baseline every other strategy should beat.
+#[derive(Debug, Clone)]
+pub struct RandomPlayer {
+    rng: ChaCha8Rng,
+}
+
+impl RandomPlayer {
+    pub fn new(seed: u64) -> Self {
+        RandomPlayer {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    fn pick<'s>(&mut self, options: &[&'s str]) -> Option<&'s str> {
+        if options.is_empty() {
+            None
+        } else {
+            Some(options[self.rng.gen_range(0..options.len())])
+        }
+    }
+}
+
+impl Strategy for RandomPlayer {
+    fn next_action(&mut self, game: &GameView) -> Action {
+        if game.custody().is_some() {
+            return match self.rng.gen_range(0..3) {
+                0 => Action::PostBail,
+                1 => Action::BorrowBail,
+                _ => Action::AwaitTrial,
+            };
+        }
+        if let Some(encounter) = game.police() {
+            if encounter.state == PoliceState::Negotiating {
+                let asking = game
+                    .police()
+                    .and_then(|encounter| encounter.negotiation.as_ref())
+                    .map(|negotiation| negotiation.asking_price)
+                    .unwrap_or(0)
+                    .min(game.cash());
+                return if asking > 0 && self.rng.gen_bool(0.5) {
+                    Action::OfferBribe(self.rng.gen_range(1..=asking))
+                } else {
+                    Action::RefuseBribe
+                };
+            }
+            return match self.rng.gen_range(0..4) {
+                0 => Action::PoliceSurrender,
+                1 => Action::PoliceFight,
+                2 => Action::PoliceRun,
+                _ => Action::PoliceNegotiate,
+            };
+        }
+
+        let substances: Vec<&str> = game.substances().collect();
+        let carried: Vec<&str> = substances
+            .iter()
+            .copied()
+            .filter(|substance| game.carried(substance) > 0)
+            .collect();
+        let stashed: Vec<&str> = game
+            .stash_houses()
+            .get(game.location())
+            .map(|stash| {
+                substances
+                    .iter()
+                    .copied()
+                    .filter(|substance| stash.inventory.get(*substance).copied().unwrap_or(0) > 0)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let action = match self.rng.gen_range(0..12) {
+            0..=2 => self.pick(&substances).and_then(|substance| {
+                let max = game.max_buy(substance);
+                (max > 0).then(|| Action::Buy {
+                    substance: substance.to_string(),
+                    amount: self.rng.gen_range(1..=max),
+                })
+            }),
+            3..=4 => self.pick(&carried).map(|substance| Action::Sell {
+                substance: substance.to_string(),
+                amount: self.rng.gen_range(1..=game.carried(substance)),
+            }),
+            5 if game.stash_houses().contains_key(game.location()) => {
+                self.pick(&carried).map(|substance| Action::Deposit {
+                    substance: substance.to_string(),
+                    amount: self.rng.gen_range(1..=game.carried(substance)),
+                })
+            }
+            5 => Some(Action::BuyStashHouse),
+            6 => self.pick(&stashed).map(|substance| {
+                let amount = game.stash_houses()[game.location()].inventory[substance];
+                Action::Withdraw {
+                    substance: substance.to_string(),
+                    amount: self.rng.gen_range(1..=amount),
+                }
+            }),
+            7 => {
+                let weapons: Vec<&str> = game.content().weapon_names().collect();
+                self.pick(&weapons)
+                    .map(|weapon| Action::BuyWeapon(weapon.to_string()))
+            }
+            8 => Some(match self.rng.gen_range(0..3) {
+                0 => Action::Borrow(self.rng.gen_range(1..=5) * 500),
+                1 => Action::Repay(game.debt().min(game.cash())),
+                _ => Action::Heal,
+            }),
+            9 if game.service_here(Service::Bank) => {
+                if self.rng.gen_bool(0.5) {
+                    (game.cash() > 0)
+                        .then(|| Action::BankDeposit(self.rng.gen_range(1..=game.cash())))
+                } else {
+                    (game.bank_balance() > 0)
+                        .then(|| Action::BankWithdraw(self.rng.gen_range(1..=game.bank_balance())))
+                }
+            }
+            10 if game.fronts().contains_key(game.location()) => (game.dirty_cash() > 0)
+                .then(|| Action::Launder(self.rng.gen_range(1..=game.dirty_cash()))),
+            10 => Some(Action::BuyFront),
+            11 if game.stash_houses().contains_key(game.location()) => {
+                if self.rng.gen_bool(0.2) {
+                    Some(Action::SellStashHouse)
+                } else {
+                    let upgrade = StashUpgrade::ALL[self.rng.gen_range(0..StashUpgrade::ALL.len())];
+                    Some(Action::UpgradeStashHouse(upgrade))
+                }
+            }
+            _ => None,
+        };
+
+        action.unwrap_or_else(|| Action::Travel(somewhere_else(game)))
+    }
+}
+
+/// The next borough along from the one the player is in.
+fn somewhere_else(game: &GameView) -> String {
+    let locations: Vec<&str> = game.locations().collect();
+    let here = locations
+        .iter()
+        .position(|location| *location == game.location())
+        .unwrap_or(0);
+    locations[(here + 1) % locations.len()].to_string()
+}
@@ -0,0 +1,565 @@
+//! Content packs: the city, the drugs and the weapons a game is played with.
+//!
+//! A pack is a TOML or JSON file; `content/default.toml` is the pack the game
+//! ships with and the one used unless another is loaded. Packs are checked
+//! with `ContentPack::validate` before a game can start with them.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::{save, MarketEventKind};
+
+const DEFAULT_PACK: &str = include_str!("../../content/default.toml");
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocationDef {
+    pub name: String,
+    pub stash_house_price: i32,
+    /// What a laundering front costs here; twice the stash house price if
+    /// not set.
+    #[serde(default)]
+    pub front_price: Option<i32>,
+    /// How much pricier (above 1.0) or cheaper (below 1.0) a substance is here
+    /// than on the street in general.
+    #[serde(default)]
+    pub price_modifiers: HashMap<String, f32>,
+    /// What the player can do here besides trade. Locations that don't list
+    /// any offer every service.
+    #[serde(default = "every_service")]
+    pub services: Vec<Service>,
+}
+
+impl LocationDef {
+    pub fn offers(&self, service: Service) -> bool {
+        self.services.contains(&service)
+    }
+}
+
+/// Something only some locations have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Service {
+    LoanShark,
+    Bank,
+    Hospital,
+    GunShop,
+    /// Sells stash houses. Stash houses already owned can be used anywhere.
+    StashBroker,
+}
+
+impl Service {
+    pub const ALL: [Service; 5] = [
+        Service::LoanShark,
+        Service::Bank,
+        Service::Hospital,
+        Service::GunShop,
+        Service::StashBroker,
+    ];
+}
+
+impl fmt::Display for Service {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Service::LoanShark => "loan shark",
+            Service::Bank => "bank",
+            Service::Hospital => "hospital",
+            Service::GunShop => "gun shop",
+            Service::StashBroker => "stash house broker",
+        };
+        f.write_str(name)
+    }
+}
+
+fn every_service() -> Vec<Service> {
+    Service::ALL.to_vec()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubstanceDef {
+    pub name: String,
+    /// Typical street price range before any local or news effects.
+    pub min_price: i32,
+    pub max_price: i32,
+    /// How many units have to be dumped in one borough to double its local
+    /// supply and halve the price.
+    #[serde(default = "default_market_depth")]
+    pub market_depth: f32,
+    /// Headlines for news about this substance, with `{substance}` and
+    /// `{location}` placeholders. Kinds not listed use the standard text.
+    #[serde(default)]
+    pub event_text: HashMap<MarketEventKind, String>,
+}
+
+fn default_market_depth() -> f32 {
+    200.0
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeaponDef {
+    pub name: String,
+    pub price: i32,
+    /// From 0 to 100.
+    pub power: i32,
+    /// Used up the first time it's used in a fight.
+    #[serde(default)]
+    pub consumable: bool,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentPack {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// The first location is where every game starts.
+    pub locations: Vec<LocationDef>,
+    pub substances: Vec<SubstanceDef>,
+    #[serde(default)]
+    pub weapons: Vec<WeaponDef>,
+}
+
+#[derive(Debug)]
+pub enum ContentError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Json(serde_json::Error),
+    UnknownFormat(String),
+    /// Every problem found in the pack, one per entry.
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for ContentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContentError::Io(e) => write!(f, "could not read the content pack: {}", e),
+            ContentError::Toml(e) => write!(f, "the content pack is not valid TOML: {}", e),
+            ContentError::Json(e) => write!(f, "the content pack is not valid JSON: {}", e),
+            ContentError::UnknownFormat(ext) => write!(
+                f,
+                "content packs must be .toml or .json files, not '{}'",
+                ext
+            ),
+            ContentError::Invalid(problems) => {
+                write!(f, "the content pack has problems: {}", problems.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for ContentError {}
+
+impl From<io::Error> for ContentError {
+    fn from(e: io::Error) -> Self {
+        ContentError::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for ContentError {
+    fn from(e: toml::de::Error) -> Self {
+        ContentError::Toml(e)
+    }
+}
+
+impl From<serde_json::Error> for ContentError {
+    fn from(e: serde_json::Error) -> Self {
+        ContentError::Json(e)
+    }
+}
+
+impl ContentPack {
+    /// The pack the game ships with.
+    pub fn builtin() -> &'static ContentPack {
+        static BUILTIN: OnceLock<ContentPack> = OnceLock::new();
+        BUILTIN.get_or_init(|| {
+            Self::from_toml_str(DEFAULT_PACK).expect("the built-in content pack is valid")
+        })
+    }
+
+    /// The pack a frontend starts with: `path` if one was given on the command
+    /// line, else `content.toml` or `content.json` in the data dir, else the
+    /// built-in pack. Also returns why a pack that was found couldn't be used.
+    pub fn startup(path: Option<PathBuf>) -> (ContentPack, Option<String>) {
+        let path = path.or_else(|| {
+            let dir = save::data_dir().ok()?;
+            ["content.toml", "content.json"]
+                .iter()
+                .map(|name| dir.join(name))
+                .find(|path| path.exists())
+        });
+
+        match path {
+            None => (ContentPack::default(), None),
+            Some(path) => match ContentPack::load(&path) {
+                Ok(content) => (content, None),
+                Err(e) => (
+                    ContentPack::default(),
+                    Some(format!(
+                        "Could not load content pack {}: {}. Using the built-in pack.",
+                        path.display(),
+                        e
+                    )),
+                ),
+            },
+        }
+    }
+
+    /// Reads and validates a pack, picking the format from the file extension.
+    pub fn load(path: &Path) -> Result<Self, ContentError> {
+        let text = fs::read_to_string(path)?;
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+
+        match extension.as_str() {
+            "toml" => Self::from_toml_str(&text),
+            "json" => Self::from_json_str(&text),
+            _ => Err(ContentError::UnknownFormat(extension)),
+        }
+    }
+
+    pub fn from_toml_str(text: &str) -> Result<Self, ContentError> {
+        let pack: ContentPack = toml::from_str(text)?;
+        pack.validate()?;
+        Ok(pack)
+    }
+
+    pub fn from_json_str(text: &str) -> Result<Self, ContentError> {
+        let pack: ContentPack = serde_json::from_str(text)?;
+        pack.validate()?;
+        Ok(pack)
+    }
+
+    /// Checks everything a game relies on, reporting every problem at once.
+    pub fn validate(&self) -> Result<(), ContentError> {
+        let mut problems = Vec::new();
+
+        if self.locations.is_empty() {
+            problems.push("there must be at least one location".to_string());
+        }
+        if self.substances.is_empty() {
+            problems.push("there must be at least one substance".to_string());
+        }
+
+        check_names(
+            "location",
+            self.locations.iter().map(|l| l.name.as_str()),
+            &mut problems,
+        );
+        check_names(
+            "substance",
+            self.substances.iter().map(|s| s.name.as_str()),
+            &mut problems,
+        );
+        check_names(
+            "weapon",
+            self.weapons.iter().map(|w| w.name.as_str()),
+            &mut problems,
+        );
+
+        for location in &self.locations {
+            if location.stash_house_price <= 0 {
+                problems.push(format!(
+                    "location '{}': stash_house_price must be positive",
+                    location.name
+                ));
+            }
+            if location.front_price.is_some_and(|price| price <= 0) {
+                problems.push(format!(
+                    "location '{}': front_price must be positive",
+                    location.name
+                ));
+            }
+            for (i, service) in location.services.iter().enumerate() {
+                if location.services[..i].contains(service) {
+                    problems.push(format!(
+                        "location '{}': {} is listed twice in services",
+                        location.name, service
+                    ));
+                }
+            }
+            let mut modified: Vec<(&String, &f32)> = location.price_modifiers.iter().collect();
+            modified.sort_by(|a, b| a.0.cmp(b.0));
+            for (substance, modifier) in modified {
+                if self.substance(substance).is_none() {
+                    problems.push(format!(
+                        "location '{}': price modifier for unknown substance '{}'",
+                        location.name, substance
+                    ));
+                }
+                if !is_positive(*modifier) {
+                    problems.push(format!(
+                        "location '{}': price modifier for '{}' must be above 0",
+                        location.name, substance
+                    ));
+                }
+            }
+        }
+
+        for substance in &self.substances {
+            if substance.min_price < 1 {
+                problems.push(format!(
+                    "substance '{}': min_price must be at least 1",
+                    substance.name
+                ));
+            }
+            if substance.max_price <= substance.min_price {
+                problems.push(format!(
+                    "substance '{}': max_price must be above min_price",
+                    substance.name
+                ));
+            }
+            if !is_positive(substance.market_depth) {
+                problems.push(format!(
+                    "substance '{}': market_depth must be above 0",
+                    substance.name
+                ));
+            }
+        }
+
+        for weapon in &self.weapons {
+            if weapon.price <= 0 {
+                problems.push(format!("weapon '{}': price must be positive", weapon.name));
+            }
+            if !(0..=100).contains(&weapon.power) {
+                problems.push(format!(
+                    "weapon '{}': power must be between 0 and 100",
+                    weapon.name
+                ));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ContentError::Invalid(problems))
+        }
+    }
+
+    pub fn location(&self, name: &str) -> Option<&LocationDef> {
+        self.locations.iter().find(|l| l.name == name)
+    }
+
+    pub fn substance(&self, name: &str) -> Option<&SubstanceDef> {
+        self.substances.iter().find(|s| s.name == name)
+    }
+
+    pub fn weapon(&self, name: &str) -> Option<&WeaponDef> {
+        self.weapons.iter().find(|w| w.name == name)
+    }
+
+    pub fn location_names(&self) -> impl Iterator<Item = &str> {
+        self.locations.iter().map(|l| l.name.as_str())
+    }
+
+    pub fn substance_names(&self) -> impl Iterator<Item = &str> {
+        self.substances.iter().map(|s| s.name.as_str())
+    }
+
+    pub fn weapon_names(&self) -> impl Iterator<Item = &str> {
+        self.weapons.iter().map(|w| w.name.as_str())
+    }
+
+    /// Names of the locations that offer `service`.
+    pub fn locations_with(&self, service: Service) -> impl Iterator<Item = &str> {
+        self.locations
+            .iter()
+            .filter(move |l| l.offers(service))
+            .map(|l| l.name.as_str())
+    }
+
+    /// Where every game starts.
+    pub fn starting_location(&self) -> &str {
+        &self.locations[0].name
+    }
+
+    /// What a laundering front costs in `location`.
+    pub fn front_price(&self, location: &str) -> i32 {
+        self.location(location)
+            .map(|l| l.front_price.unwrap_or(l.stash_house_price * 2))
+            .unwrap_or(0)
+    }
+
+    /// How much pricier or cheaper `substance` is in `location`.
+    pub fn price_modifier(&self, location: &str, substance: &str) -> f32 {
+        self.location(location)
+            .and_then(|l| l.price_modifiers.get(substance))
+            .copied()
+            .unwrap_or(1.0)
+    }
+
+    /// The usual price range of `substance` in `location`.
+    pub fn price_range(&self, location: &str, substance: &str) -> (i32, i32) {
+        let (min, max) = self
+            .substance(substance)
+            .map(|s| (s.min_price, s.max_price))
+            .unwrap_or((0, 1));
+        let modifier = self.price_modifier(location, substance);
+        let low = ((min as f32 * modifier) as i32).max(1);
+        let high = ((max as f32 * modifier) as i32).max(low + 1);
+        (low, high)
+    }
+
+    pub fn market_depth(&self, substance: &str) -> f32 {
+        self.substance(substance)
+            .map(|s| s.market_depth)
+            .unwrap_or_else(default_market_depth)
+    }
+}
+
+impl Default for ContentPack {
+    fn default() -> Self {
+        Self::builtin().clone()
+    }
+}
+
+fn check_names<'a>(kind: &str, names: impl Iterator<Item = &'a str>, problems: &mut Vec<String>) {
+    let mut seen = Vec::new();
+    for name in names {
+        if name.trim().is_empty() {
+            problems.push(format!("every {} needs a name", kind));
+        } else if seen.contains(&name) {
+            problems.push(format!("{} '{}' is listed twice", kind, name));
+        } else {
+            seen.push(name);
+        }
+    }
+}
+
+/// Rejects zero, negatives and NaN alike.
+fn is_positive(value: f32) -> bool {
+    value.is_finite() && value > 0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The problems `validate` finds in `pack`.
+    fn problems(pack: &ContentPack) -> Vec<String> {
+        match pack.validate() {
+            Ok(()) => Vec::new(),
+            Err(ContentError::Invalid(problems)) => problems,
+            Err(e) => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn the_builtin_pack_is_valid() {
+        assert!(problems(ContentPack::builtin()).is_empty());
+    }
+
+    #[test]
+    fn a_pack_with_nowhere_to_start_is_rejected() {
+        let mut pack = ContentPack::builtin().clone();
+        pack.locations.clear();
+
+        assert_eq!(
+            problems(&pack),
+            vec!["there must be at least one location".to_string()]
+        );
+    }
+
+    #[test]
+    fn duplicate_names_are_rejected() {
+        let mut pack = ContentPack::builtin().clone();
+        pack.locations.push(pack.locations[0].clone());
+        pack.substances.push(pack.substances[1].clone());
+        pack.weapons.push(pack.weapons[2].clone());
+
+        let found = problems(&pack);
+        assert!(found.contains(&format!(
+            "location '{}' is listed twice",
+            pack.locations[0].name
+        )));
+        assert!(found.contains(&format!(
+            "substance '{}' is listed twice",
+            pack.substances[1].name
+        )));
+        assert!(found.contains(&format!(
+            "weapon '{}' is listed twice",
+            pack.weapons[2].name
+        )));
+    }
+
+    #[test]
+    fn blank_names_are_rejected() {
+        let mut pack = ContentPack::builtin().clone();
+        pack.substances[0].name = "  ".to_string();
+
+        assert!(problems(&pack).contains(&"every substance needs a name".to_string()));
+    }
+
+    #[test]
+    fn prices_must_make_sense() {
+        let mut pack = ContentPack::builtin().clone();
+        pack.substances[0].min_price = 500;
+        pack.substances[0].max_price = 100;
+        pack.locations[0].stash_house_price = 0;
+        pack.weapons[0].power = 101;
+        let substance = pack.substances[0].name.clone();
+        let location = pack.locations[0].name.clone();
+        let weapon = pack.weapons[0].name.clone();
+
+        let found = problems(&pack);
+        assert!(found.contains(&format!(
+            "substance '{}': max_price must be above min_price",
+            substance
+        )));
+        assert!(found.contains(&format!(
+            "location '{}': stash_house_price must be positive",
+            location
+        )));
+        assert!(found.contains(&format!(
+            "weapon '{}': power must be between 0 and 100",
+            weapon
+        )));
+    }
+
+    #[test]
+    fn modifiers_for_unknown_substances_are_rejected() {
+        let mut pack = ContentPack::builtin().clone();
+        pack.locations[0]
+            .price_modifiers
+            .insert("Moonshine".to_string(), 1.5);
+        let location = pack.locations[0].name.clone();
+
+        assert!(problems(&pack).contains(&format!(
+            "location '{}': price modifier for unknown substance 'Moonshine'",
+            location
+        )));
+    }
+
+    #[test]
+    fn services_listed_twice_are_rejected() {
+        let mut pack = ContentPack::builtin().clone();
+        pack.locations[0].services = vec![Service::Bank, Service::Bank];
+        let location = pack.locations[0].name.clone();
+
+        assert!(problems(&pack).contains(&format!(
+            "location '{}': bank is listed twice in services",
+            location
+        )));
+    }
+
+    #[test]
+    fn loading_checks_the_pack() {
+        let text = r#"
+            name = "Empty"
+            locations = []
+            substances = []
+            weapons = []
+        "#;
+
+        assert!(matches!(
+            ContentPack::from_toml_str(text),
+            Err(ContentError::Invalid(_))
+        ));
+    }
+}
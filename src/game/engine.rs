@@ -0,0 +1,3292 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+use super::content::WeaponDef;
+use super::loan::{BARRED_AFTER_DAYS, ENFORCERS_AFTER_DAYS, MINIMUM_PAYMENT_PERCENT, TERM_DAYS};
+use super::police::round_up_to_ten;
+use super::{
+    Action, BribeNegotiation, ContentPack, CourtVerdict, Custody, Front, GameRules, Market,
+    MarketEvent, MarketEventKind, NetWorth, Player, PoliceEncounter, PoliceState, Service,
+    StashHouse, StashUpgrade, BROKER_CUT_PERCENT, EXTRA_SPACE_PERCENT, GUARD_WAGES_PERCENT,
+    MAX_EXTRA_SPACE_TIERS, MAX_HEAT,
+};
+
+/// Chance in percent of a police stop on a trip with no heat at all.
+const BASE_POLICE_STOP_CHANCE: i32 = 8;
+const HEAT_DECAY_PER_DAY: i32 = 5;
+const HEAT_FROM_FIGHT: i32 = 15;
+const HEAT_FROM_FLIGHT: i32 = 5;
+const HEAT_FROM_FAILED_BRIBE: i32 = 10;
+const HEAT_FROM_WEAPON_PURCHASE: i32 = 5;
+const HEAT_FROM_STASH_RAID: i32 = 10;
+/// Chance in percent that enforcers find a late payer on a given day, how
+/// much likelier each extra day late makes it and how likely it can get.
+const ENFORCER_BASE_CHANCE: i32 = 10;
+const ENFORCER_CHANCE_PER_DAY: i32 = 10;
+const ENFORCER_MAX_CHANCE: i32 = 60;
+/// Bail for a first arrest with no heat; heat raises it like a fine.
+const BASE_BAIL: i32 = 1000;
+const BAIL_PER_CONVICTION: i32 = 500;
+/// What a dealer pays for a used weapon, as a percentage of its price.
+const WEAPON_RESALE_PERCENT: i32 = 50;
+/// How far below their worth things go in the end-of-game fire sale.
+const FIRE_SALE_DISCOUNT_PERCENT: i32 = 25;
+/// Share of the dirty cash on the player that the police take on an arrest,
+/// and that follows the trail back from a raided stash house.
+const ARREST_SEIZURE_PERCENT: i32 = 25;
+const RAID_SEIZURE_PERCENT: i32 = 10;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub enum GameState {
+    Running,
+    GameOver(String),
+}
+
+/// Why an action was rejected. The `Display` text is what the frontends show.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameError {
+    NonPositiveAmount,
+    NotEnoughCash,
+    NotEnoughSpace,
+    NotEnoughInventory,
+    ExceedsDebt,
+    UnknownSubstance(String),
+    UnknownWeapon(String),
+    UnknownLocation(String),
+    WeaponNotOwned(String),
+    AlreadyHealthy,
+    NoStashHouse,
+    StashHouseAlreadyOwned,
+    UpgradeAlreadyOwned(StashUpgrade),
+    NotEnoughInStash,
+    NotEnoughStashSpace,
+    HeldByPolice,
+    NotStopped,
+    PoliceActionUnavailable,
+    NotInCustody,
+    NothingToUndo,
+    GameOver,
+    ServiceUnavailable(Service),
+    NotEnoughInBank,
+    NotEnoughCleanCash,
+    NoFront,
+    FrontAlreadyOwned,
+    NotEnoughDirtyCash,
+    OverLaunderingLimit,
+    OverWithdrawalLimit,
+    OverCreditLimit,
+    BarredFromLoans,
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameError::NonPositiveAmount => write!(f, "Amount must be positive."),
+            GameError::NotEnoughCash => write!(f, "Not enough cash."),
+            GameError::NotEnoughSpace => write!(f, "Not enough space in your inventory."),
+            GameError::NotEnoughInventory => write!(f, "Not enough in your inventory."),
+            GameError::ExceedsDebt => write!(f, "Amount exceeds debt."),
+            GameError::UnknownSubstance(name) => write!(f, "Unknown substance: {}.", name),
+            GameError::UnknownWeapon(name) => write!(f, "Unknown weapon: {}.", name),
+            GameError::UnknownLocation(name) => write!(f, "Unknown location: {}.", name),
+            GameError::WeaponNotOwned(name) => write!(f, "You don't own a {}.", name),
+            GameError::AlreadyHealthy => write!(f, "You're in perfect health!"),
+            GameError::NoStashHouse => write!(f, "You don't own a stash house in this location."),
+            GameError::StashHouseAlreadyOwned => {
+                write!(f, "You already own a stash house in this location.")
+            }
+            GameError::UpgradeAlreadyOwned(upgrade) => match upgrade {
+                StashUpgrade::ExtraSpace => write!(f, "Your stash house can't get any bigger."),
+                StashUpgrade::ReinforcedDoor => {
+                    write!(f, "Your stash house already has a reinforced door.")
+                }
+                StashUpgrade::Alarm => write!(f, "Your stash house already has an alarm system."),
+                StashUpgrade::Guards => write!(f, "Your stash house is already guarded."),
+            },
+            GameError::NotEnoughInStash => write!(f, "Not enough in stash."),
+            GameError::NotEnoughStashSpace => write!(f, "Not enough space in stash house."),
+            GameError::HeldByPolice => write!(f, "Not while the police are holding you."),
+            GameError::NotStopped => write!(f, "The police aren't holding you."),
+            GameError::PoliceActionUnavailable => write!(f, "You can't do that right now."),
+            GameError::NotInCustody => write!(f, "You aren't in custody."),
+            GameError::NothingToUndo => write!(f, "There's nothing to undo."),
+            GameError::GameOver => write!(f, "The game is over."),
+            GameError::ServiceUnavailable(service) => {
+                write!(f, "There's no {} in this location.", service)
+            }
+            GameError::NotEnoughInBank => write!(f, "Not enough in the bank."),
+            GameError::NotEnoughCleanCash => {
+                write!(
+                    f,
+                    "Not enough clean cash. Dirty money has to be laundered first."
+                )
+            }
+            GameError::NoFront => write!(f, "You don't own a front in this location."),
+            GameError::FrontAlreadyOwned => {
+                write!(f, "You already own a front in this location.")
+            }
+            GameError::NotEnoughDirtyCash => write!(f, "Not enough dirty cash."),
+            GameError::OverLaunderingLimit => {
+                write!(f, "That's more than your front can launder today.")
+            }
+            GameError::OverWithdrawalLimit => {
+                write!(f, "That's more than the bank will pay out today.")
+            }
+            GameError::OverCreditLimit => {
+                write!(f, "That's more than the loan shark will lend you.")
+            }
+            GameError::BarredFromLoans => {
+                write!(
+                    f,
+                    "The loan shark won't lend you another cent until you pay up."
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+/// Joins the reasons an action is refused into one line for display.
+pub fn describe_problems(prefix: &str, problems: &[GameError]) -> String {
+    let mut reason = prefix.to_string();
+    for problem in problems {
+        reason.push_str(&format!("{} ", problem));
+    }
+    reason
+}
+
+/// A completed buy or sell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trade {
+    pub substance: String,
+    pub amount: i32,
+    pub total: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TravelOutcome {
+    /// The player reached the destination and a new day started.
+    Arrived,
+    /// The police stopped the player before they could leave. The trip goes
+    /// ahead once the stop is settled.
+    PoliceStop,
+}
+
+/// Everything an undoable action can change, as it was before it.
+#[derive(Clone)]
+struct Snapshot {
+    player: Player,
+    market: Market,
+    /// Length of the message log.
+    messages: usize,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GameEngine {
+    pub player: Player,
+    pub market: Market,
+    pub state: GameState,
+    pub message_log: Vec<String>,
+    #[serde(default)]
+    pub police: Option<PoliceEncounter>,
+    #[serde(default)]
+    pub custody: Option<Custody>,
+    /// The city, drugs and weapons this game is played with.
+    #[serde(default)]
+    content: ContentPack,
+    /// How long the game lasts and what things cost.
+    #[serde(default)]
+    rules: GameRules,
+    /// What the end-of-game fire sale raised, once it has happened.
+    #[serde(default)]
+    fire_sale: Option<i32>,
+    /// Every action taken so far, in order; with the seed, the content and
+    /// the rules that is the whole game. `None` for games from saves that
+    /// predate recording.
+    #[serde(default)]
+    actions: Option<Vec<Action>>,
+    /// Undoable actions taken since the last one that can't be taken back,
+    /// newest last. Saves don't keep them.
+    #[serde(skip)]
+    undo: Vec<(Action, Snapshot)>,
+    /// Seed the game was started from; the same seed and the same actions
+    /// always replay the same game.
+    seed: u64,
+    rng: ChaCha8Rng,
+}
+
+impl GameEngine {
+    /// Starts a game from a fresh random seed.
+    pub fn new() -> Self {
+        Self::with_seed(rand::random())
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_content(seed, ContentPack::default())
+    }
+
+    /// Starts a game in the city described by `content`.
+    pub fn with_content(seed: u64, content: ContentPack) -> Self {
+        Self::with_rules(seed, content, GameRules::default())
+    }
+
+    /// Starts a game in the city described by `content`, played by `rules`.
+    pub fn with_rules(seed: u64, content: ContentPack, rules: GameRules) -> Self {
+        let mut engine = GameEngine {
+            player: Player::new(&content, &rules),
+            market: Market::new(&content),
+            state: GameState::Running,
+            message_log: Vec::new(),
+            police: None,
+            custody: None,
+            content,
+            rules,
+            fire_sale: None,
+            actions: Some(Vec::new()),
+            undo: Vec::new(),
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        };
+
+        engine
+            .message_log
+            .push("Welcome to DopeWars GUI Edition!".to_string());
+        engine.message_log.push(format!(
+            "You have {} days to make as much money as possible.",
+            engine.rules.max_days
+        ));
+        engine
+            .message_log
+            .push("Buy low, sell high, and watch out for the cops!".to_string());
+
+        // Generate initial market prices
+        let news = engine
+            .market
+            .generate_prices(&mut engine.rng, &engine.content);
+        engine.log_market_events(&news);
+
+        engine
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn content(&self) -> &ContentPack {
+        &self.content
+    }
+
+    pub fn rules(&self) -> &GameRules {
+        &self.rules
+    }
+
+    /// Every action taken so far, if the whole game was recorded.
+    pub fn actions(&self) -> Option<&[Action]> {
+        self.actions.as_deref()
+    }
+
+    /// Records an action that can't be undone. Nothing before it can be
+    /// undone either, so random outcomes can't be rerolled.
+    fn record(&mut self, action: Action) {
+        self.undo.clear();
+        if let Some(actions) = &mut self.actions {
+            actions.push(action);
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            player: self.player.clone(),
+            market: self.market.clone(),
+            messages: self.message_log.len(),
+        }
+    }
+
+    /// Records an action that only moves cash and stock around, along with
+    /// the game as it was before it.
+    fn record_undoable(&mut self, action: Action, before: Snapshot) {
+        if let Some(actions) = &mut self.actions {
+            actions.push(action.clone());
+        }
+        self.undo.push((action, before));
+    }
+
+    /// The action `undo` would take back, if there is one.
+    pub fn last_undoable(&self) -> Option<&Action> {
+        self.undo.last().map(|(action, _)| action)
+    }
+
+    /// Takes back the last buy, sell, deposit, withdrawal, loan, repayment
+    /// or change of weapon, as long as nothing random has happened since.
+    /// Bank deposits and withdrawals count too.
+    /// Returns the action that was undone.
+    pub fn undo(&mut self) -> Result<Action, GameError> {
+        self.require_running()?;
+        let (action, before) = self.undo.pop().ok_or(GameError::NothingToUndo)?;
+        self.player = before.player;
+        self.market = before.market;
+        self.message_log.truncate(before.messages);
+        if let Some(actions) = &mut self.actions {
+            actions.pop();
+        }
+
+        self.message_log.push(format!("Undone: {}.", action));
+        Ok(action)
+    }
+
+    /// Days played so far, not counting the day after the last one.
+    pub fn days_survived(&self) -> i32 {
+        self.player.day.min(self.rules.max_days)
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self.state, GameState::Running)
+    }
+
+    /// Nothing can be done once the game is over; the score is final.
+    fn require_running(&self) -> Result<(), GameError> {
+        if self.is_running() {
+            Ok(())
+        } else {
+            Err(GameError::GameOver)
+        }
+    }
+
+    /// Ends the game once the player runs out of days or health.
+    pub fn update_state(&mut self) {
+        if let GameState::Running = self.state {
+            if self.player.day > self.rules.max_days {
+                self.state = GameState::GameOver(format!(
+                    "Time's up! Your {} days are over.",
+                    self.rules.max_days
+                ));
+            } else if self.player.health <= 0 {
+                self.state = GameState::GameOver("You died from your injuries!".to_string());
+            }
+
+            if !self.is_running() && self.rules.fire_sale {
+                self.hold_fire_sale();
+            }
+        }
+    }
+
+    fn log_market_events(&mut self, news: &[MarketEvent]) {
+        for event in news {
+            self.message_log.push(event.headline());
+        }
+    }
+
+    fn require_substance(&self, substance: &str) -> Result<(), GameError> {
+        match self.content.substance(substance) {
+            Some(_) => Ok(()),
+            None => Err(GameError::UnknownSubstance(substance.to_string())),
+        }
+    }
+
+    fn weapon_def(&self, weapon: &str) -> Result<&WeaponDef, GameError> {
+        self.content
+            .weapon(weapon)
+            .ok_or_else(|| GameError::UnknownWeapon(weapon.to_string()))
+    }
+
+    /// A random substance from the content pack.
+    fn random_substance(&mut self) -> String {
+        let index = self.rng.gen_range(0..self.content.substances.len());
+        self.content.substances[index].name.clone()
+    }
+
+    fn held(&self, substance: &str) -> i32 {
+        self.player.inventory.get(substance).copied().unwrap_or(0)
+    }
+
+    // --- Trading ---
+
+    /// Today's price of `substance` where the player is standing.
+    pub fn local_price(&self, substance: &str) -> i32 {
+        self.market.price(&self.player.current_location, substance)
+    }
+
+    /// What buying `amount` of `substance` here would cost, price impact included.
+    pub fn quote_buy(&self, substance: &str, amount: i32) -> i32 {
+        self.market
+            .trade_total(&self.player.current_location, substance, amount)
+    }
+
+    /// What selling `amount` of `substance` here would earn, price impact included.
+    pub fn quote_sell(&self, substance: &str, amount: i32) -> i32 {
+        self.market
+            .trade_total(&self.player.current_location, substance, -amount)
+    }
+
+    /// The local price of `substance` after buying (`units` > 0) or selling
+    /// (`units` < 0) that many.
+    pub fn price_after_trade(&self, substance: &str, units: i32) -> i32 {
+        self.market
+            .price_after_trade(&self.player.current_location, substance, units)
+    }
+
+    /// Largest amount of `substance` the player can both afford and carry.
+    pub fn max_buy(&self, substance: &str) -> i32 {
+        if self.local_price(substance) <= 0 {
+            return 0;
+        }
+
+        // Buying pushes the price up, so search for the largest affordable amount
+        let mut low = 0;
+        let mut high = self.player.space_available().max(0);
+        while low < high {
+            let mid = (low + high + 1) / 2;
+            if self.quote_buy(substance, mid) <= self.player.cash {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        low
+    }
+
+    /// Every reason a purchase would be refused; empty if it is allowed.
+    pub fn check_buy(&self, substance: &str, amount: i32) -> Vec<GameError> {
+        let mut problems = Vec::new();
+        if let Err(e) = self.require_free() {
+            problems.push(e);
+        }
+        if let Err(e) = self.require_substance(substance) {
+            problems.push(e);
+            return problems;
+        }
+        if amount <= 0 {
+            problems.push(GameError::NonPositiveAmount);
+        }
+        if amount > self.player.space_available() {
+            problems.push(GameError::NotEnoughSpace);
+        }
+        if self.quote_buy(substance, amount) > self.player.cash {
+            problems.push(GameError::NotEnoughCash);
+        }
+        problems
+    }
+
+    pub fn buy(&mut self, substance: &str, amount: i32) -> Result<Trade, GameError> {
+        if let Some(problem) = self.check_buy(substance, amount).into_iter().next() {
+            return Err(problem);
+        }
+
+        let before = self.snapshot();
+
+        let total = self.quote_buy(substance, amount);
+        self.player.spend_dirty(total);
+        *self
+            .player
+            .inventory
+            .entry(substance.to_string())
+            .or_insert(0) += amount;
+
+        self.message_log.push(format!(
+            "Bought {} units of {} for ${}",
+            amount, substance, total
+        ));
+        self.move_market(substance, amount);
+        self.raise_heat_from_trade(total);
+
+        self.record_undoable(
+            Action::Buy {
+                substance: substance.to_string(),
+                amount,
+            },
+            before,
+        );
+        Ok(Trade {
+            substance: substance.to_string(),
+            amount,
+            total,
+        })
+    }
+
+    /// Every reason a sale would be refused; empty if it is allowed.
+    pub fn check_sell(&self, substance: &str, amount: i32) -> Vec<GameError> {
+        let mut problems = Vec::new();
+        if let Err(e) = self.require_free() {
+            problems.push(e);
+        }
+        if let Err(e) = self.require_substance(substance) {
+            problems.push(e);
+            return problems;
+        }
+        if amount <= 0 {
+            problems.push(GameError::NonPositiveAmount);
+        }
+        if amount > self.held(substance) {
+            problems.push(GameError::NotEnoughInventory);
+        }
+        problems
+    }
+
+    pub fn sell(&mut self, substance: &str, amount: i32) -> Result<Trade, GameError> {
+        if let Some(problem) = self.check_sell(substance, amount).into_iter().next() {
+            return Err(problem);
+        }
+
+        let before = self.snapshot();
+
+        let total = self.quote_sell(substance, amount);
+        self.player.cash += total;
+        self.player.dirty_cash += total;
+        *self
+            .player
+            .inventory
+            .entry(substance.to_string())
+            .or_insert(0) -= amount;
+
+        self.message_log.push(format!(
+            "Sold {} units of {} for ${}",
+            amount, substance, total
+        ));
+        self.move_market(substance, -amount);
+        self.raise_heat_from_trade(total);
+
+        self.record_undoable(
+            Action::Sell {
+                substance: substance.to_string(),
+                amount,
+            },
+            before,
+        );
+        Ok(Trade {
+            substance: substance.to_string(),
+            amount,
+            total,
+        })
+    }
+
+    /// Applies the player's price impact and tells them when it's noticeable.
+    fn move_market(&mut self, substance: &str, units: i32) {
+        let location = self.player.current_location.clone();
+        let before = self.local_price(substance);
+        self.market.record_trade(&location, substance, units);
+        let after = self.local_price(substance);
+
+        // Only mention moves of 10% or more
+        if (after - before).abs() * 10 >= before.max(1) {
+            let direction = if after > before { "up" } else { "down" };
+            self.message_log.push(format!(
+                "Word gets around: {} in {} is {} to ${}.",
+                substance, location, direction, after
+            ));
+        }
+    }
+
+    pub fn calculate_inventory_value(&self) -> i32 {
+        let mut total = 0;
+
+        for (substance, amount) in &self.player.inventory {
+            total += self.local_price(substance) * amount;
+        }
+
+        total
+    }
+
+    // --- Travel ---
+
+    pub fn travel(&mut self, new_location: &str) -> Result<TravelOutcome, GameError> {
+        if self.content.location(new_location).is_none() {
+            return Err(GameError::UnknownLocation(new_location.to_string()));
+        }
+        self.require_free()?;
+        self.record(Action::Travel(new_location.to_string()));
+
+        self.message_log
+            .push(format!("Traveling to {}...", new_location));
+
+        // The more heat you're carrying, the likelier the cops pull you over
+        if self.rng.gen_range(0..100) < self.police_stop_chance() {
+            // Hand over to the police; the trip goes ahead once the stop is settled
+            self.message_log
+                .push("🚨 You've been stopped by the police!".to_string());
+            self.police = Some(PoliceEncounter::new(new_location));
+            return Ok(TravelOutcome::PoliceStop);
+        }
+
+        let random_event = self.rng.gen_range(1..12);
+
+        let mut street_news = None;
+        match random_event {
+            1 => {
+                let message = "🔫 You were mugged! You lost some cash.".to_string();
+                self.message_log.push(message);
+
+                // Check if player has a weapon for defense
+                let mut defended = false;
+                if let Some(weapon) = self.player.active_weapon.clone() {
+                    let weapon_power = self.active_weapon_power();
+                    let defend_chance = 30 + (weapon_power / 2);
+
+                    if self.player.has_weapon() && self.rng.gen_range(0..100) < defend_chance {
+                        self.message_log.push(format!(
+                            "You used your {} to fight off the muggers!",
+                            weapon
+                        ));
+
+                        // Still might get hurt
+                        let damage = self.rng.gen_range(0..10);
+                        if damage > 0 {
+                            self.player.health -= damage;
+                            self.message_log
+                                .push(format!("You got hurt in the fight (-{} health)", damage));
+                        }
+
+                        defended = true;
+                    }
+                }
+
+                if !defended {
+                    let lost = self.rng.gen_range(100..500).min(self.player.cash);
+                    self.player.spend_dirty(lost);
+
+                    let message = format!("You lost ${}", lost);
+                    self.message_log.push(message);
+                }
+            }
+            2 => {
+                let message = "🏥 You got injured during travel!".to_string();
+                self.message_log.push(message);
+
+                let damage = self.rng.gen_range(5..20);
+                self.player.health -= damage;
+
+                let message = format!("You lost {} health points", damage);
+                self.message_log.push(message);
+
+                if self.player.health <= 0 {
+                    self.player.health = 0;
+                    self.message_log
+                        .push("You're severely injured and need medical attention!".to_string());
+                }
+            }
+            3 => {
+                // Substance price crash
+                let substance = self.random_substance();
+                let days = self.rng.gen_range(1..=2);
+                street_news = Some(
+                    MarketEvent::local(
+                        MarketEventKind::MarketFlooded,
+                        &substance,
+                        new_location,
+                        days,
+                    )
+                    .with_magnitude(1.0 / 3.0),
+                );
+            }
+            4 => {
+                // Substance price spike
+                let substance = self.random_substance();
+                let days = self.rng.gen_range(1..=2);
+                street_news = Some(
+                    MarketEvent::local(
+                        MarketEventKind::ShipmentBust,
+                        &substance,
+                        new_location,
+                        days,
+                    )
+                    .with_magnitude(3.0),
+                );
+            }
+            5 => {
+                // Find some drugs
+                let substance = self.random_substance();
+                let amount = self.rng.gen_range(1..5);
+
+                if self.player.space_available() >= amount {
+                    *self.player.inventory.entry(substance.clone()).or_insert(0) += amount;
+                    let message = format!(
+                        "Lucky! You found {} units of {} on the ground!",
+                        amount, substance
+                    );
+                    self.message_log.push(message);
+                } else {
+                    self.message_log
+                        .push("You found some drugs but had no space to carry them.".to_string());
+                }
+            }
+            _ => {
+                self.message_log.push("Journey was uneventful.".to_string());
+            }
+        }
+
+        self.arrive(new_location, street_news);
+
+        Ok(TravelOutcome::Arrived)
+    }
+
+    /// Ends a trip: the player reaches `new_location` and a new day starts.
+    fn arrive(&mut self, new_location: &str, street_news: Option<MarketEvent>) {
+        self.player.current_location = new_location.to_string();
+
+        let message = format!("You've arrived at {}.", new_location);
+        self.message_log.push(message);
+
+        self.start_new_day(street_news);
+    }
+
+    /// Moves the game on by a day: interest, heat, raids and new prices.
+    fn start_new_day(&mut self, street_news: Option<MarketEvent>) {
+        self.player.day += 1;
+        self.cool_down();
+        self.player.debt =
+            (self.player.debt as f32 * (1.0 + self.rules.loan_shark_interest)) as i32;
+
+        let message = format!(
+            "Your debt has increased to ${} due to interest.",
+            self.player.debt
+        );
+        self.message_log.push(message);
+
+        self.player.withdrawn_today = 0;
+        for front in self.player.fronts.values_mut() {
+            front.laundered_today = 0;
+        }
+        self.check_loan_terms();
+        self.pay_guards();
+
+        let savings_interest = (self.player.bank_balance as f32 * self.rules.bank_interest) as i32;
+        if savings_interest > 0 {
+            self.player.bank_balance += savings_interest;
+            self.message_log.push(format!(
+                "The bank paid ${} interest on your savings.",
+                savings_interest
+            ));
+        }
+
+        // Check for possible stash house raids
+        self.check_stash_house_raid();
+
+        // Generate new market prices
+        let mut news = self.market.generate_prices(&mut self.rng, &self.content);
+        // What you heard on the way in hits the new borough's prices straight away
+        if let Some(event) = street_news {
+            self.market.start_event(event.clone());
+            news.push(event);
+        }
+        self.log_market_events(&news);
+
+        self.update_state();
+    }
+
+    fn check_stash_house_raid(&mut self) {
+        // Only check if player has at least one stash house
+        if self.player.stash_houses.is_empty() {
+            return;
+        }
+
+        // 5% chance of a raid per travel if player has stash houses, more with heat
+        if self.rng.gen_range(0..100) < 5 + self.player.heat / 10 {
+            // Determine which stash house gets raided (if player has multiple)
+            // (sorted so the pick only depends on the seed, not on HashMap order)
+            let mut stash_locations: Vec<String> =
+                self.player.stash_houses.keys().cloned().collect();
+            stash_locations.sort();
+            let raided_location = &stash_locations[self.rng.gen_range(0..stash_locations.len())];
+
+            // An alarm or guards can see the police off before they get in
+            let protection = self.player.stash_houses[raided_location].raid_protection();
+            if protection > 0 && self.rng.gen_range(0..100) < protection {
+                self.message_log.push(format!(
+                    "The police came for your stash house in {}, but your security saw them off.",
+                    raided_location
+                ));
+                return;
+            }
+
+            self.message_log.push(format!(
+                "🚨 ALERT! Your stash house in {} was raided by police!",
+                raided_location
+            ));
+            self.raise_heat(HEAT_FROM_STASH_RAID);
+            self.player.record.raids += 1;
+
+            // Check if the stash has anything in it
+            if let Some(stash) = self.player.stash_houses.get_mut(raided_location) {
+                let seizure_reduction = stash.seizure_reduction();
+                let has_substances = stash.inventory.values().sum::<i32>() > 0;
+
+                if has_substances {
+                    // Determine how much is confiscated (50-100%, worse with heat,
+                    // less behind a reinforced door or with guards)
+                    let confiscation_percentage =
+                        self.rng.gen_range((50 + self.player.heat / 4)..=100)
+                            - stash.confiscation_reduction();
+
+                    self.message_log.push(format!(
+                        "Police confiscated {}% of your stash!",
+                        confiscation_percentage
+                    ));
+
+                    // Apply confiscation to each substance
+                    let mut confiscated_value = 0;
+                    for (substance, amount) in stash.inventory.iter_mut() {
+                        if *amount > 0 {
+                            let confiscated = (*amount * confiscation_percentage) / 100;
+                            *amount -= confiscated;
+                            confiscated_value +=
+                                self.market
+                                    .trade_total(raided_location, substance, -confiscated);
+                        }
+                    }
+                    self.player.record.raid_losses += confiscated_value;
+                    self.player.record.raid_losses += self.seize_dirty_cash(RAID_SEIZURE_PERCENT);
+
+                    // Potential fine
+                    let fine = self.rng.gen_range(1000..5000);
+                    let fine = self.scale_by_heat(fine).min(self.player.cash);
+                    if fine > 0 {
+                        self.player.spend_dirty(fine);
+                        self.player.record.raid_losses += fine;
+                        self.message_log
+                            .push(format!("You were fined ${} for the illegal stash!", fine));
+                    }
+                } else {
+                    self.message_log.push(
+                        "Luckily, your stash was empty so nothing was confiscated!".to_string(),
+                    );
+                }
+
+                // Small chance (10%, more with heat) the stash house is seized entirely
+                let seizure_chance = (10 + self.player.heat / 10) * (100 - seizure_reduction) / 100;
+                if self.rng.gen_range(0..100) < seizure_chance {
+                    self.message_log.push(format!(
+                        "Your stash house in {} was seized by authorities!",
+                        raided_location
+                    ));
+                    if let Some(stash) = self.player.stash_houses.remove(raided_location) {
+                        self.player.record.raid_losses += self.stash_house_value(&stash)
+                            + self.stock_value(raided_location, &stash.inventory);
+                    }
+                }
+            }
+        }
+    }
+
+    // --- Heat ---
+
+    /// Chance in percent that the police pull you over on a trip.
+    /// Known offenders get pulled over more.
+    pub fn police_stop_chance(&self) -> i32 {
+        BASE_POLICE_STOP_CHANCE + self.player.heat / 4 + self.player.record.convictions * 3
+    }
+
+    pub fn raise_heat(&mut self, amount: i32) {
+        self.player.heat = (self.player.heat + amount).clamp(0, MAX_HEAT);
+    }
+
+    /// Big deals get talked about. Every $2000 that changes hands adds a
+    /// point of heat, up to 10 for a single trade.
+    fn raise_heat_from_trade(&mut self, total: i32) {
+        let heat = (total / 2000).min(10);
+        if heat > 0 {
+            self.raise_heat(heat);
+            self.message_log
+                .push(format!("A deal that size gets noticed (+{} heat).", heat));
+        }
+    }
+
+    /// Heat fades a little every day, faster the hotter it is.
+    fn cool_down(&mut self) {
+        let decay = HEAT_DECAY_PER_DAY + self.player.heat / 10;
+        self.raise_heat(-decay);
+    }
+
+    /// Fines grow with heat: a wanted dealer pays up to twice as much.
+    fn scale_by_heat(&self, amount: i32) -> i32 {
+        amount + amount * self.player.heat / MAX_HEAT
+    }
+
+    // --- Police ---
+
+    /// The police stop the player is caught up in, if any.
+    pub fn police_encounter(&self) -> Option<&PoliceEncounter> {
+        self.police.as_ref()
+    }
+
+    fn require_free(&self) -> Result<(), GameError> {
+        self.require_running()?;
+        if self.police.is_some() || self.custody.is_some() {
+            Err(GameError::HeldByPolice)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks that the stop is in `expected` before a police action is taken.
+    fn require_police_state(&self, expected: PoliceState) -> Result<(), GameError> {
+        self.require_running()?;
+        match &self.police {
+            None => Err(GameError::NotStopped),
+            Some(encounter) if encounter.state == expected => Ok(()),
+            Some(_) => Err(GameError::PoliceActionUnavailable),
+        }
+    }
+
+    fn set_police_state(&mut self, state: PoliceState) {
+        if let Some(encounter) = self.police.as_mut() {
+            encounter.state = state;
+        }
+    }
+
+    pub fn confiscate_substances(&mut self) {
+        // Confiscate all substances
+        for (_, amount) in self.player.inventory.iter_mut() {
+            *amount = 0;
+        }
+
+        self.message_log
+            .push("All your substances have been confiscated!".to_string());
+    }
+
+    /// The police take `percent` of the dirty cash on the player as proceeds
+    /// of crime. Returns how much they took.
+    fn seize_dirty_cash(&mut self, percent: i32) -> i32 {
+        let seized = self.player.dirty_cash * percent / 100;
+        if seized > 0 {
+            self.player.cash -= seized;
+            self.player.dirty_cash -= seized;
+            self.player.record.cash_seized += seized;
+            self.message_log.push(format!(
+                "The police seized ${} in drug money you couldn't account for!",
+                seized
+            ));
+        }
+        seized
+    }
+
+    fn keep_conscious(&mut self) {
+        if self.player.health <= 0 {
+            self.player.health = 1; // Don't let player die from police fight
+            self.message_log.push(
+                "You're critically injured and need medical attention immediately!".to_string(),
+            );
+        }
+    }
+
+    /// Let the officer search you.
+    pub fn police_surrender(&mut self) -> Result<PoliceState, GameError> {
+        self.require_police_state(PoliceState::Stopped)?;
+        self.message_log
+            .push("You let the officer search you.".to_string());
+        self.set_police_state(PoliceState::Searching);
+        self.record(Action::PoliceSurrender);
+        Ok(self.resolve_police())
+    }
+
+    pub fn police_fight(&mut self) -> Result<PoliceState, GameError> {
+        self.require_police_state(PoliceState::Stopped)?;
+        self.set_police_state(PoliceState::Fighting);
+        self.record(Action::PoliceFight);
+        Ok(self.resolve_police())
+    }
+
+    pub fn police_run(&mut self) -> Result<PoliceState, GameError> {
+        self.require_police_state(PoliceState::Stopped)?;
+        self.set_police_state(PoliceState::Fleeing);
+        self.record(Action::PoliceRun);
+        Ok(self.resolve_police())
+    }
+
+    /// Hint to the officer that this could go away for the right price. The
+    /// officer names a price based on what you're carrying; how greedy and
+    /// how patient they are differs from cop to cop.
+    pub fn police_negotiate(&mut self) -> Result<PoliceState, GameError> {
+        self.require_police_state(PoliceState::Stopped)?;
+        self.message_log
+            .push("You ask the officer if there's some other way to settle this...".to_string());
+
+        let greed = self.rng.gen_range(0.3..0.8);
+        let patience = self.rng.gen_range(2..=5);
+        let value = self.calculate_inventory_value() as f32;
+        let asking_price = round_up_to_ten((value * (0.2 + greed * 0.5)) as i32 + 100);
+
+        self.message_log.push(format!(
+            "The officer wants ${} to forget this ever happened.",
+            asking_price
+        ));
+        if let Some(encounter) = self.police.as_mut() {
+            encounter.negotiation = Some(BribeNegotiation {
+                asking_price,
+                patience,
+                greed,
+                offers: Vec::new(),
+            });
+        }
+        self.set_police_state(PoliceState::Negotiating);
+        self.record(Action::PoliceNegotiate);
+        Ok(PoliceState::Negotiating)
+    }
+
+    /// The haggling in progress, if the player is bargaining with the police.
+    pub fn bribe_negotiation(&self) -> Option<&BribeNegotiation> {
+        self.police
+            .as_ref()
+            .and_then(|encounter| encounter.negotiation.as_ref())
+    }
+
+    /// Offer the officer `bribe` dollars. An offer that meets the asking price
+    /// is taken and ends the stop; a lower one gets a counter-offer, until the
+    /// officer runs out of patience and arrests you. Money only changes hands
+    /// once the officer accepts.
+    pub fn offer_bribe(&mut self, bribe: i32) -> Result<PoliceState, GameError> {
+        self.require_police_state(PoliceState::Negotiating)?;
+        if bribe <= 0 {
+            return Err(GameError::NonPositiveAmount);
+        }
+        if bribe > self.player.cash {
+            return Err(GameError::NotEnoughCash);
+        }
+        let Some(mut negotiation) = self.bribe_negotiation().cloned() else {
+            return Err(GameError::PoliceActionUnavailable);
+        };
+
+        negotiation.offers.push(bribe);
+        let counter = negotiation.counter(bribe);
+
+        let state = if counter <= bribe {
+            self.player.spend_dirty(bribe);
+            self.message_log.push(format!(
+                "You offered ${} as a bribe and the officer accepted!",
+                bribe
+            ));
+            self.message_log
+                .push("The officer lets you go with a warning.".to_string());
+            PoliceState::Escaped
+        } else {
+            // Lowballing wears the officer's patience down twice as fast
+            let insult = bribe * 4 < negotiation.asking_price;
+            negotiation.patience -= if insult { 2 } else { 1 };
+
+            if negotiation.patience <= 0 {
+                self.message_log.push(format!(
+                    "You offered ${} as a bribe but the officer has had enough of haggling!",
+                    bribe
+                ));
+                self.message_log
+                    .push("The officer seems offended and decides to arrest you.".to_string());
+                self.raise_heat(HEAT_FROM_FAILED_BRIBE);
+                self.arrest();
+                PoliceState::Arrested
+            } else {
+                negotiation.asking_price = counter;
+                if insult {
+                    self.message_log.push(format!(
+                        "The officer laughs at your ${} and asks for ${}.",
+                        bribe, counter
+                    ));
+                } else {
+                    self.message_log.push(format!(
+                        "You offered ${}. The officer counters with ${}.",
+                        bribe, counter
+                    ));
+                }
+                PoliceState::Negotiating
+            }
+        };
+
+        if let Some(encounter) = self.police.as_mut() {
+            encounter.negotiation = Some(negotiation);
+        }
+        self.set_police_state(state);
+        self.record(Action::OfferBribe(bribe));
+        Ok(self.resolve_police())
+    }
+
+    /// Back out of the bribe; the officer searches you instead.
+    pub fn refuse_bribe(&mut self) -> Result<PoliceState, GameError> {
+        self.require_police_state(PoliceState::Negotiating)?;
+        self.message_log
+            .push("You refused to offer a bribe.".to_string());
+        self.set_police_state(PoliceState::Searching);
+        self.record(Action::RefuseBribe);
+        Ok(self.resolve_police())
+    }
+
+    /// Plays out a search, fight or chase, then ends the stop once it is
+    /// settled. These are the only rules for how a stop turns out.
+    fn resolve_police(&mut self) -> PoliceState {
+        let Some(state) = self.police.as_ref().map(|encounter| encounter.state) else {
+            return PoliceState::Escaped;
+        };
+
+        let state = match state {
+            PoliceState::Searching => self.police_search(),
+            PoliceState::Fighting => {
+                self.raise_heat(HEAT_FROM_FIGHT);
+                self.police_fight_round()
+            }
+            PoliceState::Fleeing => {
+                self.raise_heat(HEAT_FROM_FLIGHT);
+                self.police_chase()
+            }
+            other => other,
+        };
+        self.set_police_state(state);
+
+        if state.is_over() {
+            self.end_police_encounter();
+        }
+        state
+    }
+
+    fn police_search(&mut self) -> PoliceState {
+        if self.player.total_items() == 0 {
+            self.message_log.push(
+                "The cops let you go since you aren't carrying anything illegal.".to_string(),
+            );
+            return PoliceState::Escaped;
+        }
+
+        // A rushed pat-down sometimes misses what you're carrying, unless
+        // you're already wanted or known to the police
+        let miss_chance = 20 - self.player.heat / 5 - self.player.record.arrests * 5;
+        if self.rng.gen_range(0..100) < miss_chance {
+            self.message_log
+                .push("The officer gives you a quick pat-down and misses your stash!".to_string());
+            self.message_log
+                .push("The officer lets you go with a warning.".to_string());
+            return PoliceState::Escaped;
+        }
+
+        self.message_log
+            .push("The officer searches you and finds your stash!".to_string());
+        self.arrest();
+        PoliceState::Arrested
+    }
+
+    fn police_fight_round(&mut self) -> PoliceState {
+        // A grenade or the like is thrown, and only works once
+        if let Some(weapon) = self.active_weapon_def().filter(|w| w.consumable).cloned() {
+            return self.throw_weapon(&weapon);
+        }
+
+        let weapon_power = self.active_weapon_power();
+        let weapon_name = self
+            .player
+            .active_weapon
+            .clone()
+            .unwrap_or_else(|| "weapon".to_string());
+        let fight_chance = if weapon_power > 0 {
+            20 + (weapon_power as f32 * 0.7) as i32
+        } else {
+            10
+        };
+
+        if self.rng.gen_range(0..100) < fight_chance {
+            if weapon_power > 0 {
+                self.message_log.push(format!(
+                    "You used your {} to fight off the cops and escaped!",
+                    weapon_name
+                ));
+            } else {
+                self.message_log.push(
+                    "You somehow managed to fight off the cops with your bare hands and escaped!"
+                        .to_string(),
+                );
+            }
+
+            // Take some damage
+            let damage = self.rng.gen_range(5..20);
+            self.player.health -= damage;
+            self.message_log
+                .push(format!("You got hurt in the fight (-{} health)", damage));
+            self.keep_conscious();
+
+            PoliceState::Escaped
+        } else {
+            if weapon_power > 0 {
+                self.message_log.push(format!(
+                    "Even with your {}, you couldn't fight off the cops. You've been arrested!",
+                    weapon_name
+                ));
+            } else {
+                self.message_log.push("You tried to fight the cops with your bare hands and failed miserably! You've been arrested!".to_string());
+            }
+
+            // Take more damage for failing
+            let damage = self.rng.gen_range(15..40);
+            self.player.health -= damage;
+            self.message_log
+                .push(format!("The cops roughed you up (-{} health)", damage));
+            self.keep_conscious();
+            self.arrest();
+
+            PoliceState::Arrested
+        }
+    }
+
+    fn throw_weapon(&mut self, weapon: &WeaponDef) -> PoliceState {
+        if let Some(amount) = self.player.weapons.get_mut(&weapon.name) {
+            *amount -= 1;
+            if *amount <= 0 {
+                self.player.active_weapon = None;
+            }
+        }
+
+        // Extremely effective but one-time use
+        let name = weapon.name.to_lowercase();
+        if self.rng.gen_range(0..100) < weapon.power {
+            self.message_log
+                .push(format!("💥 You threw a {} and escaped in the chaos!", name));
+            PoliceState::Escaped
+        } else {
+            self.message_log.push(format!(
+                "💥 Your {} didn't go off properly! The police arrested you!",
+                name
+            ));
+            self.arrest();
+            PoliceState::Arrested
+        }
+    }
+
+    fn police_chase(&mut self) -> PoliceState {
+        let run_chance = 30 + (self.player.health / 4); // Health affects running ability
+        if self.rng.gen_range(0..100) < run_chance {
+            self.message_log
+                .push("You outran the cops and got away!".to_string());
+            PoliceState::Escaped
+        } else {
+            self.message_log.push(
+                "You tried to run but the cops caught you! You've been arrested!".to_string(),
+            );
+            self.arrest();
+            PoliceState::Arrested
+        }
+    }
+
+    /// What an arrest costs, however it came about: the drugs and weapons on
+    /// you are gone, it goes on your record and you're taken into custody.
+    fn arrest(&mut self) {
+        self.confiscate_substances();
+        self.seize_dirty_cash(ARREST_SEIZURE_PERCENT);
+        if self.player.has_weapon() {
+            for (_, amount) in self.player.weapons.iter_mut() {
+                *amount = 0;
+            }
+            self.player.active_weapon = None;
+            self.message_log
+                .push("Your weapons have been confiscated!".to_string());
+        }
+
+        self.player.record.arrests += 1;
+
+        let destination = self
+            .police
+            .as_ref()
+            .map(|encounter| encounter.destination.clone())
+            .unwrap_or_else(|| self.player.current_location.clone());
+        let bail = round_up_to_ten(
+            self.scale_by_heat(BASE_BAIL) + self.player.record.convictions * BAIL_PER_CONVICTION,
+        );
+        let days_until_trial = self.rng.gen_range(1..=3) + self.player.record.convictions;
+
+        self.message_log.push(format!(
+            "You're booked at the precinct. Bail is set at ${}.",
+            bail
+        ));
+        self.custody = Some(Custody {
+            destination,
+            bail,
+            days_until_trial,
+        });
+    }
+
+    /// Clears a settled stop and finishes the trip it interrupted. After an
+    /// arrest the trip waits until the player gets out of custody.
+    fn end_police_encounter(&mut self) {
+        if let Some(encounter) = self.police.take() {
+            if self.custody.is_none() {
+                self.arrive(&encounter.destination, None);
+            }
+        }
+    }
+
+    // --- Jail and court ---
+
+    /// Where the player is being held after an arrest, if they are.
+    pub fn custody(&self) -> Option<&Custody> {
+        self.custody.as_ref()
+    }
+
+    /// Pay bail in cash and go straight to the hearing.
+    pub fn post_bail(&mut self) -> Result<CourtVerdict, GameError> {
+        self.require_running()?;
+        let bail = self.custody.as_ref().ok_or(GameError::NotInCustody)?.bail;
+        if bail > self.player.cash {
+            return Err(GameError::NotEnoughCash);
+        }
+
+        self.player.spend_dirty(bail);
+        self.message_log.push(format!("You post ${} bail.", bail));
+        self.record(Action::PostBail);
+        Ok(self.stand_trial())
+    }
+
+    /// Have the loan shark cover bail; it goes on your debt, so it has to
+    /// fit within the credit limit like any other loan.
+    pub fn borrow_bail(&mut self) -> Result<CourtVerdict, GameError> {
+        self.require_running()?;
+        let bail = self.custody.as_ref().ok_or(GameError::NotInCustody)?.bail;
+        if self.player.loan.barred {
+            return Err(GameError::BarredFromLoans);
+        }
+        if bail > self.credit_available() {
+            return Err(GameError::OverCreditLimit);
+        }
+
+        self.player.debt += bail;
+        self.lend(bail);
+        self.message_log.push(format!(
+            "The loan shark covers your ${} bail. Your debt is now ${}.",
+            bail, self.player.debt
+        ));
+        self.record(Action::BorrowBail);
+        Ok(self.stand_trial())
+    }
+
+    /// Sit in a cell until the case comes to court. Time served counts
+    /// against any sentence.
+    pub fn await_trial(&mut self) -> Result<CourtVerdict, GameError> {
+        self.require_running()?;
+        let days = self
+            .custody
+            .as_ref()
+            .ok_or(GameError::NotInCustody)?
+            .days_until_trial;
+
+        self.message_log.push(format!(
+            "You wait {} days in a cell for your hearing.",
+            days
+        ));
+        let served = self.serve_jail_time(days);
+        self.record(Action::AwaitTrial);
+        Ok(self.stand_trial_after(served))
+    }
+
+    fn stand_trial(&mut self) -> CourtVerdict {
+        self.stand_trial_after(0)
+    }
+
+    /// The court weighs your heat and your record. A conviction means more
+    /// days inside, less whatever was already served waiting for trial.
+    fn stand_trial_after(&mut self, days_served: i32) -> CourtVerdict {
+        let record = &self.player.record;
+        let conviction_chance =
+            (15 + self.player.heat / 2 + record.convictions * 15 + (record.arrests - 1).max(0) * 5)
+                .clamp(5, 90);
+        let roll = self.rng.gen_range(0..100);
+
+        let verdict = if roll < conviction_chance {
+            let sentence = 2 + record.convictions * 2 + self.player.heat / 20;
+            self.player.record.convictions += 1;
+            CourtVerdict::Jailed((sentence - days_served).max(0))
+        } else if roll < conviction_chance + 40 {
+            let fine = self.rng.gen_range(500..2500);
+            CourtVerdict::Fined(self.scale_by_heat(fine).min(self.player.cash))
+        } else {
+            CourtVerdict::Dismissed
+        };
+
+        match verdict {
+            CourtVerdict::Dismissed => self
+                .message_log
+                .push("⚖️ The judge throws the case out. You're free to go.".to_string()),
+            CourtVerdict::Fined(fine) => {
+                self.player.spend_dirty(fine);
+                self.message_log.push(format!(
+                    "⚖️ The judge lets you off with a ${} fine for possession.",
+                    fine
+                ));
+            }
+            CourtVerdict::Jailed(days) => {
+                self.message_log.push(format!(
+                    "⚖️ Guilty! You're sentenced to {} more days in jail.",
+                    days
+                ));
+                self.serve_jail_time(days);
+            }
+        }
+
+        self.release();
+        verdict
+    }
+
+    /// Lets `days` go by in a cell, stopping early if the game ends. Returns
+    /// the days actually served.
+    fn serve_jail_time(&mut self, days: i32) -> i32 {
+        let mut served = 0;
+        while served < days && self.is_running() {
+            self.start_new_day(None);
+            served += 1;
+        }
+        self.player.record.days_in_jail += served;
+        served
+    }
+
+    fn release(&mut self) {
+        if let Some(custody) = self.custody.take() {
+            if self.is_running() {
+                self.message_log
+                    .push("You walk out of the precinct.".to_string());
+                self.arrive(&custody.destination, None);
+            }
+        }
+    }
+
+    // --- Services ---
+
+    /// Whether `service` can be used where the player is standing.
+    pub fn service_here(&self, service: Service) -> bool {
+        self.content
+            .location(&self.player.current_location)
+            .is_some_and(|location| location.offers(service))
+    }
+
+    /// Tells the player which locations offer `service`.
+    pub fn where_to_find(&self, service: Service) -> String {
+        let locations: Vec<&str> = self.content.locations_with(service).collect();
+        if locations.is_empty() {
+            format!("There's no {} anywhere in this city.", service)
+        } else {
+            format!("You'll find a {} in {}.", service, locations.join(", "))
+        }
+    }
+
+    fn require_service(&self, service: Service) -> Result<(), GameError> {
+        if self.service_here(service) {
+            Ok(())
+        } else {
+            Err(GameError::ServiceUnavailable(service))
+        }
+    }
+
+    // --- Loan shark ---
+
+    /// The most the loan shark will let the player owe: the starting loan
+    /// plus half of everything they own, scaled by how well they've paid.
+    pub fn credit_limit(&self) -> i32 {
+        let worth = self.net_worth_breakdown();
+        let assets = (worth.total_wide() + worth.debt as i64).max(0);
+        let limit = (self.rules.starting_debt as i64 + assets / 2)
+            * self.player.loan.trust_percent() as i64
+            / 100;
+        limit.clamp(0, i32::MAX as i64) as i32
+    }
+
+    /// How much more the loan shark will lend right now.
+    pub fn credit_available(&self) -> i32 {
+        if self.player.loan.barred {
+            0
+        } else {
+            (self.credit_limit() - self.player.debt).max(0)
+        }
+    }
+
+    pub fn check_borrow(&self, amount: i32) -> Vec<GameError> {
+        let mut problems = Vec::new();
+        if let Err(e) = self.require_free() {
+            problems.push(e);
+        }
+        if let Err(e) = self.require_service(Service::LoanShark) {
+            problems.push(e);
+        }
+        if amount <= 0 {
+            problems.push(GameError::NonPositiveAmount);
+        }
+        if self.player.loan.barred {
+            problems.push(GameError::BarredFromLoans);
+        } else if amount > self.credit_available() {
+            problems.push(GameError::OverCreditLimit);
+        }
+        problems
+    }
+
+    /// Borrow from the loan shark. Returns the new debt.
+    pub fn borrow(&mut self, amount: i32) -> Result<i32, GameError> {
+        if let Some(problem) = self.check_borrow(amount).into_iter().next() {
+            return Err(problem);
+        }
+
+        let before = self.snapshot();
+
+        self.player.cash += amount;
+        self.player.debt += amount;
+        self.lend(amount);
+
+        self.message_log.push(format!(
+            "You borrowed ${}, your debt is now ${}",
+            amount, self.player.debt
+        ));
+
+        self.record_undoable(Action::Borrow(amount), before);
+        Ok(self.player.debt)
+    }
+
+    pub fn check_repay(&self, amount: i32) -> Vec<GameError> {
+        let mut problems = Vec::new();
+        if let Err(e) = self.require_free() {
+            problems.push(e);
+        }
+        if let Err(e) = self.require_service(Service::LoanShark) {
+            problems.push(e);
+        }
+        if amount <= 0 {
+            problems.push(GameError::NonPositiveAmount);
+        }
+        if amount > self.player.cash {
+            problems.push(GameError::NotEnoughCash);
+        }
+        if amount > self.player.debt {
+            problems.push(GameError::ExceedsDebt);
+        }
+        problems
+    }
+
+    /// Pay back part of the loan. Returns the remaining debt.
+    pub fn repay(&mut self, amount: i32) -> Result<i32, GameError> {
+        if let Some(problem) = self.check_repay(amount).into_iter().next() {
+            return Err(problem);
+        }
+
+        let before = self.snapshot();
+
+        self.player.spend_dirty(amount);
+        self.player.debt -= amount;
+
+        self.message_log.push(format!(
+            "You repaid ${}, your debt is now ${}",
+            amount, self.player.debt
+        ));
+        self.credit_repayment(amount);
+
+        self.record_undoable(Action::Repay(amount), before);
+        Ok(self.player.debt)
+    }
+
+    /// Adds a new loan to what's due this term.
+    fn lend(&mut self, amount: i32) {
+        let day = self.player.day;
+        let debt = self.player.debt;
+        let loan = &mut self.player.loan;
+        if loan.due_day.is_none() {
+            loan.start_term(day, debt);
+        } else {
+            loan.minimum_payment += amount * MINIMUM_PAYMENT_PERCENT / 100;
+        }
+    }
+
+    fn credit_repayment(&mut self, amount: i32) {
+        let day = self.player.day;
+        let debt = self.player.debt;
+        let loan = &mut self.player.loan;
+        let was_overdue = loan.days_overdue(day) > 0;
+        loan.paid_this_term += amount;
+
+        if debt == 0 {
+            if !was_overdue && loan.due_day.is_some() {
+                loan.terms_met += 1;
+            }
+            loan.start_term(day, 0);
+            if loan.barred {
+                loan.barred = false;
+                self.message_log
+                    .push("The loan shark is happy to do business with you again.".to_string());
+            }
+        } else if was_overdue && loan.payment_due() == 0 {
+            // Caught up, so the clock starts again from today
+            loan.start_term(day, debt);
+            self.message_log.push(format!(
+                "The loan shark calls off his people. Next payment due on day {}.",
+                day + TERM_DAYS
+            ));
+        }
+    }
+
+    /// Moves the repayment plan on a day and goes after late payers.
+    fn check_loan_terms(&mut self) {
+        let day = self.player.day;
+        let debt = self.player.debt;
+        let loan = &mut self.player.loan;
+
+        match loan.due_day {
+            // Games from older saves start their first term now
+            None if debt > 0 => loan.start_term(day, debt),
+            Some(due) if day > due && loan.payment_due() == 0 => {
+                loan.terms_met += 1;
+                loan.start_term(due, debt);
+            }
+            _ => {}
+        }
+
+        let overdue = loan.days_overdue(day);
+        if overdue == 0 {
+            if loan.payment_due() > 0 && loan.due_day == Some(day) {
+                let message = format!(
+                    "The loan shark expects ${} from you by the end of today.",
+                    loan.payment_due()
+                );
+                self.message_log.push(message);
+            }
+            return;
+        }
+
+        if overdue == 1 {
+            loan.terms_missed += 1;
+        }
+        let message = format!(
+            "💀 You're {} day{} late paying the loan shark ${}. His people are asking around for you.",
+            overdue,
+            if overdue == 1 { "" } else { "s" },
+            loan.payment_due()
+        );
+        self.message_log.push(message);
+
+        if overdue >= BARRED_AFTER_DAYS && !loan.barred {
+            loan.barred = true;
+            self.message_log.push(
+                "The loan shark won't lend you another cent until you've paid him off.".to_string(),
+            );
+        }
+
+        // Nobody gets to you in a cell
+        let chance =
+            (ENFORCER_BASE_CHANCE + ENFORCER_CHANCE_PER_DAY * overdue).min(ENFORCER_MAX_CHANCE);
+        if overdue >= ENFORCERS_AFTER_DAYS
+            && self.custody.is_none()
+            && self.rng.gen_range(0..100) < chance
+        {
+            self.enforcer_visit();
+        }
+    }
+
+    /// The loan shark's people take the most valuable stock the player is
+    /// carrying off the debt, or rough them up if there's nothing to take.
+    fn enforcer_visit(&mut self) {
+        self.player.loan.enforcer_visits += 1;
+        self.message_log
+            .push("💀 The loan shark's enforcers caught up with you!".to_string());
+
+        let here = self.player.current_location.clone();
+        let seized = self
+            .player
+            .inventory
+            .iter()
+            .filter(|(_, amount)| **amount > 0)
+            .map(|(substance, amount)| {
+                let value = self.market.trade_total(&here, substance, -amount);
+                (value, substance.clone(), *amount)
+            })
+            .max();
+        if let Some((value, substance, amount)) = seized {
+            self.player.inventory.insert(substance.clone(), 0);
+            let credited = value.min(self.player.debt);
+            self.player.debt -= credited;
+            self.message_log.push(format!(
+                "They took your {} units of {} and knocked ${} off your debt.",
+                amount, substance, credited
+            ));
+            self.credit_repayment(credited);
+            return;
+        }
+
+        let damage = self.rng.gen_range(5..15);
+        self.player.health = (self.player.health - damage).max(0);
+        self.message_log.push(format!(
+            "They beat you to make the point (-{} health).",
+            damage
+        ));
+    }
+
+    // --- Bank ---
+
+    /// How much more the bank will pay out today, or `None` if the rules
+    /// set no limit.
+    pub fn bank_withdrawal_left(&self) -> Option<i32> {
+        self.rules
+            .bank_withdrawal_limit
+            .map(|limit| (limit - self.player.withdrawn_today).max(0))
+    }
+
+    pub fn check_bank_deposit(&self, amount: i32) -> Vec<GameError> {
+        let mut problems = Vec::new();
+        if let Err(e) = self.require_free() {
+            problems.push(e);
+        }
+        if let Err(e) = self.require_service(Service::Bank) {
+            problems.push(e);
+        }
+        if amount <= 0 {
+            problems.push(GameError::NonPositiveAmount);
+        }
+        if amount > self.player.cash {
+            problems.push(GameError::NotEnoughCash);
+        } else if amount > self.player.clean_cash() {
+            problems.push(GameError::NotEnoughCleanCash);
+        }
+        problems
+    }
+
+    /// Pay clean cash into the bank. Returns the new balance.
+    pub fn bank_deposit(&mut self, amount: i32) -> Result<i32, GameError> {
+        if let Some(problem) = self.check_bank_deposit(amount).into_iter().next() {
+            return Err(problem);
+        }
+
+        let before = self.snapshot();
+
+        self.player.cash -= amount;
+        self.player.bank_balance += amount;
+
+        self.message_log.push(format!(
+            "You paid ${} into the bank, your balance is now ${}",
+            amount, self.player.bank_balance
+        ));
+
+        self.record_undoable(Action::BankDeposit(amount), before);
+        Ok(self.player.bank_balance)
+    }
+
+    pub fn check_bank_withdraw(&self, amount: i32) -> Vec<GameError> {
+        let mut problems = Vec::new();
+        if let Err(e) = self.require_free() {
+            problems.push(e);
+        }
+        if let Err(e) = self.require_service(Service::Bank) {
+            problems.push(e);
+        }
+        if amount <= 0 {
+            problems.push(GameError::NonPositiveAmount);
+        }
+        if amount > self.player.bank_balance {
+            problems.push(GameError::NotEnoughInBank);
+        }
+        if self
+            .bank_withdrawal_left()
+            .is_some_and(|left| amount > left)
+        {
+            problems.push(GameError::OverWithdrawalLimit);
+        }
+        problems
+    }
+
+    /// Take savings out of the bank. Returns the new balance.
+    pub fn bank_withdraw(&mut self, amount: i32) -> Result<i32, GameError> {
+        if let Some(problem) = self.check_bank_withdraw(amount).into_iter().next() {
+            return Err(problem);
+        }
+
+        let before = self.snapshot();
+
+        self.player.cash += amount;
+        self.player.bank_balance -= amount;
+        self.player.withdrawn_today += amount;
+
+        self.message_log.push(format!(
+            "You took ${} out of the bank, your balance is now ${}",
+            amount, self.player.bank_balance
+        ));
+
+        self.record_undoable(Action::BankWithdraw(amount), before);
+        Ok(self.player.bank_balance)
+    }
+
+    // --- Hospital ---
+
+    /// What a full treatment costs right now.
+    pub fn heal_cost(&self) -> i32 {
+        (100 - self.player.health).max(0) * self.rules.hospital_cost_per_point
+    }
+
+    /// Get fully healed at the hospital. Returns what it cost.
+    pub fn heal(&mut self) -> Result<i32, GameError> {
+        self.require_free()?;
+        self.require_service(Service::Hospital)?;
+        if self.player.health >= 100 {
+            return Err(GameError::AlreadyHealthy);
+        }
+
+        let total_cost = self.heal_cost();
+        if total_cost > self.player.cash {
+            return Err(GameError::NotEnoughCash);
+        }
+
+        self.player.spend(total_cost);
+        self.player.health = 100;
+
+        self.message_log
+            .push("You've been treated and are now at full health!".to_string());
+
+        self.record(Action::Heal);
+        Ok(total_cost)
+    }
+
+    // --- Weapons ---
+
+    pub fn weapon_price(&self, weapon: &str) -> Result<i32, GameError> {
+        self.weapon_def(weapon).map(|w| w.price)
+    }
+
+    pub fn weapon_power(&self, weapon: &str) -> Result<i32, GameError> {
+        self.weapon_def(weapon).map(|w| w.power)
+    }
+
+    fn active_weapon_def(&self) -> Option<&WeaponDef> {
+        self.player
+            .active_weapon
+            .as_deref()
+            .and_then(|weapon| self.content.weapon(weapon))
+    }
+
+    /// Power of the equipped weapon, or 0 when unarmed.
+    pub fn active_weapon_power(&self) -> i32 {
+        self.active_weapon_def().map(|w| w.power).unwrap_or(0)
+    }
+
+    pub fn buy_weapon(&mut self, weapon: &str) -> Result<i32, GameError> {
+        self.require_free()?;
+        self.require_service(Service::GunShop)?;
+        let price = self.weapon_price(weapon)?;
+        if price > self.player.cash {
+            return Err(GameError::NotEnoughCash);
+        }
+
+        self.player.spend_dirty(price);
+        *self.player.weapons.entry(weapon.to_string()).or_insert(0) += 1;
+
+        self.message_log
+            .push(format!("Bought a {} for ${}", weapon, price));
+        self.raise_heat(HEAT_FROM_WEAPON_PURCHASE);
+
+        // If this is the player's first weapon, automatically equip it
+        if self.player.active_weapon.is_none() {
+            self.player.active_weapon = Some(weapon.to_string());
+            self.message_log
+                .push(format!("You equipped your new {}.", weapon));
+        }
+
+        self.record(Action::BuyWeapon(weapon.to_string()));
+        Ok(price)
+    }
+
+    pub fn equip_weapon(&mut self, weapon: &str) -> Result<(), GameError> {
+        self.require_free()?;
+        self.weapon_def(weapon)?;
+        if self.player.weapons.get(weapon).copied().unwrap_or(0) <= 0 {
+            return Err(GameError::WeaponNotOwned(weapon.to_string()));
+        }
+
+        let before = self.snapshot();
+        self.player.active_weapon = Some(weapon.to_string());
+        self.message_log.push(format!("You equipped a {}.", weapon));
+
+        self.record_undoable(Action::EquipWeapon(weapon.to_string()), before);
+        Ok(())
+    }
+
+    // --- Stash houses ---
+
+    /// What a stash house in `location` costs today. Property prices drift
+    /// around the price listed in the content pack.
+    pub fn stash_house_price(&self, location: &str) -> i32 {
+        let listed = self
+            .content
+            .location(location)
+            .map(|l| l.stash_house_price)
+            .unwrap_or(0);
+        (listed as f32 * self.market.property_index(location)).round() as i32
+    }
+
+    /// How much property prices in `location` moved since yesterday, in percent.
+    pub fn property_price_change(&self, location: &str) -> i32 {
+        match self.market.property_history(location).last() {
+            Some(&yesterday) if yesterday > 0.0 => {
+                ((self.market.property_index(location) / yesterday - 1.0) * 100.0).round() as i32
+            }
+            _ => 0,
+        }
+    }
+
+    /// Buy a stash house where the player is standing. Returns the price paid.
+    pub fn buy_stash_house(&mut self) -> Result<i32, GameError> {
+        self.require_free()?;
+        self.require_service(Service::StashBroker)?;
+        if self.player.owns_stash_house_in_current_location() {
+            return Err(GameError::StashHouseAlreadyOwned);
+        }
+
+        let location = self.player.current_location.clone();
+        let price = self.stash_house_price(&location);
+        if price > self.player.cash {
+            return Err(GameError::NotEnoughCash);
+        }
+
+        self.player.spend_dirty(price);
+        self.player.stash_houses.insert(
+            location.clone(),
+            StashHouse::new(location.clone(), self.rules.stash_house_capacity),
+        );
+
+        self.message_log.push(format!(
+            "Purchased a stash house in {} for ${}",
+            location, price
+        ));
+
+        self.record(Action::BuyStashHouse);
+        Ok(price)
+    }
+
+    /// What the broker pays for the player's stash house in `location`, or
+    /// `None` if the player has none there.
+    pub fn stash_house_sale_price(&self, location: &str) -> Option<i32> {
+        let stash = self.player.stash_houses.get(location)?;
+        let value = self.stash_house_value(stash);
+        Some(value - value * BROKER_CUT_PERCENT / 100)
+    }
+
+    /// Sell the stash house where the player is standing. As much of what's
+    /// in it as fits goes in the player's pockets and the rest is lost with
+    /// the house. The broker pays cash, no questions asked, so the money is
+    /// dirty. Returns the price paid.
+    pub fn sell_stash_house(&mut self) -> Result<i32, GameError> {
+        self.require_free()?;
+        self.require_service(Service::StashBroker)?;
+        let location = self.player.current_location.clone();
+        let price = self
+            .stash_house_sale_price(&location)
+            .ok_or(GameError::NoStashHouse)?;
+        let Some(stash) = self.player.stash_houses.remove(&location) else {
+            return Err(GameError::NoStashHouse);
+        };
+
+        let mut lost = 0;
+        for substance in self.content.substance_names() {
+            let stored = stash.inventory.get(substance).copied().unwrap_or(0);
+            let taken = stored.min(self.player.space_available()).max(0);
+            *self
+                .player
+                .inventory
+                .entry(substance.to_string())
+                .or_insert(0) += taken;
+            lost += stored - taken;
+        }
+
+        self.player.cash += price;
+        self.player.dirty_cash += price;
+        self.message_log.push(format!(
+            "Sold your stash house in {} for ${}.",
+            location, price
+        ));
+        if lost > 0 {
+            self.message_log.push(format!(
+                "You couldn't carry {} units from the stash, so they went with the house.",
+                lost
+            ));
+        }
+
+        self.record(Action::SellStashHouse);
+        Ok(price)
+    }
+
+    /// What `upgrade` costs for the stash house in `location`, or `None` if
+    /// there's no house there or it can't take any more of it. Each extra
+    /// space tier costs more than the last.
+    pub fn stash_upgrade_price(&self, location: &str, upgrade: StashUpgrade) -> Option<i32> {
+        let stash = self.player.stash_houses.get(location)?;
+        let owned = stash.upgrade_count(upgrade);
+        let limit = match upgrade {
+            StashUpgrade::ExtraSpace => MAX_EXTRA_SPACE_TIERS,
+            _ => 1,
+        };
+        if owned >= limit {
+            return None;
+        }
+        Some(self.stash_house_price(location) * upgrade.price_percent() * (owned + 1) / 100)
+    }
+
+    /// What the house and everything built into it would fetch. Guards are
+    /// staff, not property, so they don't count.
+    pub fn stash_house_value(&self, stash: &StashHouse) -> i32 {
+        let price = self.stash_house_price(&stash.location);
+        let tiers = stash.upgrade_count(StashUpgrade::ExtraSpace);
+        let space = price * StashUpgrade::ExtraSpace.price_percent() * tiers * (tiers + 1) / 200;
+        let fittings = [StashUpgrade::ReinforcedDoor, StashUpgrade::Alarm]
+            .into_iter()
+            .filter(|&upgrade| stash.has_upgrade(upgrade))
+            .map(|upgrade| price * upgrade.price_percent() / 100)
+            .sum::<i32>();
+        price + space + fittings
+    }
+
+    /// What guards on the stash house in `location` are paid each day.
+    pub fn guard_wages(&self, location: &str) -> i32 {
+        self.stash_house_price(location) * GUARD_WAGES_PERCENT / 100
+    }
+
+    /// Buy `upgrade` for the stash house where the player is standing.
+    /// Returns the price paid.
+    pub fn buy_stash_upgrade(&mut self, upgrade: StashUpgrade) -> Result<i32, GameError> {
+        self.require_free()?;
+        if !self.player.owns_stash_house_in_current_location() {
+            return Err(GameError::NoStashHouse);
+        }
+
+        let location = self.player.current_location.clone();
+        let price = self
+            .stash_upgrade_price(&location, upgrade)
+            .ok_or(GameError::UpgradeAlreadyOwned(upgrade))?;
+        if price > self.player.cash {
+            return Err(GameError::NotEnoughCash);
+        }
+
+        self.player.spend_dirty(price);
+        let extra_space = self.rules.stash_house_capacity * EXTRA_SPACE_PERCENT / 100;
+        if let Some(stash) = self.player.stash_houses.get_mut(&location) {
+            stash.upgrades.push(upgrade);
+            if upgrade == StashUpgrade::ExtraSpace {
+                stash.capacity += extra_space;
+            }
+        }
+
+        self.message_log.push(format!(
+            "{} for your stash house in {}: ${}",
+            upgrade, location, price
+        ));
+
+        self.record(Action::UpgradeStashHouse(upgrade));
+        Ok(price)
+    }
+
+    /// Pays every stash house's guards for the day. Guards who can't be paid
+    /// walk off the job.
+    fn pay_guards(&mut self) {
+        let mut guarded: Vec<String> = self
+            .player
+            .stash_houses
+            .values()
+            .filter(|stash| stash.has_upgrade(StashUpgrade::Guards))
+            .map(|stash| stash.location.clone())
+            .collect();
+        guarded.sort();
+
+        for location in guarded {
+            let wages = self.guard_wages(&location);
+            if wages <= self.player.cash {
+                self.player.spend_dirty(wages);
+                self.message_log
+                    .push(format!("You paid your guards in {} ${}.", location, wages));
+            } else if let Some(stash) = self.player.stash_houses.get_mut(&location) {
+                stash.upgrades.retain(|&u| u != StashUpgrade::Guards);
+                self.message_log.push(format!(
+                    "You couldn't pay your guards in {}, so they walked off the job.",
+                    location
+                ));
+            }
+        }
+    }
+
+    pub fn check_deposit(&self, substance: &str, amount: i32) -> Vec<GameError> {
+        let mut problems = Vec::new();
+        if let Err(e) = self.require_free() {
+            problems.push(e);
+        }
+        if let Err(e) = self.require_substance(substance) {
+            problems.push(e);
+            return problems;
+        }
+        let Some(stash) = self.player.stash_houses.get(&self.player.current_location) else {
+            problems.push(GameError::NoStashHouse);
+            return problems;
+        };
+        if amount <= 0 {
+            problems.push(GameError::NonPositiveAmount);
+        }
+        if amount > self.held(substance) {
+            problems.push(GameError::NotEnoughInventory);
+        }
+        if amount > stash.space_available() {
+            problems.push(GameError::NotEnoughStashSpace);
+        }
+        problems
+    }
+
+    /// Move drugs from the player into the local stash house.
+    pub fn deposit(&mut self, substance: &str, amount: i32) -> Result<(), GameError> {
+        if let Some(problem) = self.check_deposit(substance, amount).into_iter().next() {
+            return Err(problem);
+        }
+
+        let before = self.snapshot();
+
+        let location = self.player.current_location.clone();
+        *self
+            .player
+            .inventory
+            .entry(substance.to_string())
+            .or_insert(0) -= amount;
+        if let Some(stash) = self.player.stash_houses.get_mut(&location) {
+            *stash.inventory.entry(substance.to_string()).or_insert(0) += amount;
+        }
+
+        self.message_log.push(format!(
+            "Deposited {} units of {} to your stash",
+            amount, substance
+        ));
+
+        self.record_undoable(
+            Action::Deposit {
+                substance: substance.to_string(),
+                amount,
+            },
+            before,
+        );
+        Ok(())
+    }
+
+    pub fn check_withdraw(&self, substance: &str, amount: i32) -> Vec<GameError> {
+        let mut problems = Vec::new();
+        if let Err(e) = self.require_free() {
+            problems.push(e);
+        }
+        if let Err(e) = self.require_substance(substance) {
+            problems.push(e);
+            return problems;
+        }
+        let Some(stash) = self.player.stash_houses.get(&self.player.current_location) else {
+            problems.push(GameError::NoStashHouse);
+            return problems;
+        };
+        if amount <= 0 {
+            problems.push(GameError::NonPositiveAmount);
+        }
+        if amount > stash.inventory.get(substance).copied().unwrap_or(0) {
+            problems.push(GameError::NotEnoughInStash);
+        }
+        if amount > self.player.space_available() {
+            problems.push(GameError::NotEnoughSpace);
+        }
+        problems
+    }
+
+    /// Move drugs from the local stash house back to the player.
+    pub fn withdraw(&mut self, substance: &str, amount: i32) -> Result<(), GameError> {
+        if let Some(problem) = self.check_withdraw(substance, amount).into_iter().next() {
+            return Err(problem);
+        }
+
+        let before = self.snapshot();
+
+        let location = self.player.current_location.clone();
+        if let Some(stash) = self.player.stash_houses.get_mut(&location) {
+            *stash.inventory.entry(substance.to_string()).or_insert(0) -= amount;
+        }
+        *self
+            .player
+            .inventory
+            .entry(substance.to_string())
+            .or_insert(0) += amount;
+
+        self.message_log.push(format!(
+            "Withdrew {} units of {} from your stash",
+            amount, substance
+        ));
+
+        self.record_undoable(
+            Action::Withdraw {
+                substance: substance.to_string(),
+                amount,
+            },
+            before,
+        );
+        Ok(())
+    }
+
+    // --- Fronts ---
+
+    pub fn front_price(&self, location: &str) -> i32 {
+        self.content.front_price(location)
+    }
+
+    /// Buy a front business where the player is standing. Returns the price
+    /// paid.
+    pub fn buy_front(&mut self) -> Result<i32, GameError> {
+        self.require_free()?;
+        if self.player.owns_front_in_current_location() {
+            return Err(GameError::FrontAlreadyOwned);
+        }
+
+        let location = self.player.current_location.clone();
+        let price = self.front_price(&location);
+        if price > self.player.cash {
+            return Err(GameError::NotEnoughCash);
+        }
+
+        self.player.spend(price);
+        self.player
+            .fronts
+            .insert(location.clone(), Front::new(location.clone()));
+
+        self.message_log.push(format!(
+            "Bought a front business in {} for ${}",
+            location, price
+        ));
+
+        self.record(Action::BuyFront);
+        Ok(price)
+    }
+
+    /// How much more the front here can launder today; 0 without one.
+    pub fn laundering_left(&self) -> i32 {
+        self.player
+            .fronts
+            .get(&self.player.current_location)
+            .map_or(0, |front| {
+                (self.rules.laundering_limit - front.laundered_today).max(0)
+            })
+    }
+
+    /// The front's cut for laundering `amount`.
+    pub fn laundering_fee(&self, amount: i32) -> i32 {
+        (amount as f32 * self.rules.laundering_fee).round() as i32
+    }
+
+    pub fn check_launder(&self, amount: i32) -> Vec<GameError> {
+        let mut problems = Vec::new();
+        if let Err(e) = self.require_free() {
+            problems.push(e);
+        }
+        if !self.player.owns_front_in_current_location() {
+            problems.push(GameError::NoFront);
+            return problems;
+        }
+        if amount <= 0 {
+            problems.push(GameError::NonPositiveAmount);
+        }
+        if amount > self.player.dirty_cash {
+            problems.push(GameError::NotEnoughDirtyCash);
+        }
+        if amount > self.laundering_left() {
+            problems.push(GameError::OverLaunderingLimit);
+        }
+        problems
+    }
+
+    /// Put dirty cash through the front here. Returns the clean money it
+    /// came back as, after the front's cut.
+    pub fn launder(&mut self, amount: i32) -> Result<i32, GameError> {
+        if let Some(problem) = self.check_launder(amount).into_iter().next() {
+            return Err(problem);
+        }
+
+        let before = self.snapshot();
+
+        let fee = self.laundering_fee(amount);
+        self.player.dirty_cash -= amount;
+        self.player.cash -= fee;
+        let location = self.player.current_location.clone();
+        if let Some(front) = self.player.fronts.get_mut(&location) {
+            front.laundered_today += amount;
+            front.total_laundered += amount;
+        }
+
+        self.message_log.push(format!(
+            "Your front in {} laundered ${}. After its ${} cut that's ${} clean.",
+            location,
+            amount,
+            fee,
+            amount - fee
+        ));
+
+        self.record_undoable(Action::Launder(amount), before);
+        Ok(amount - fee)
+    }
+
+    // --- Net worth ---
+
+    /// What the player is worth right now, item by item.
+    pub fn net_worth_breakdown(&self) -> NetWorth {
+        let carried_stock = self.stock_value(&self.player.current_location, &self.player.inventory);
+        let stashed_stock = self
+            .player
+            .stash_houses
+            .values()
+            .map(|stash| self.stock_value(&stash.location, &stash.inventory))
+            .sum();
+        let property = self
+            .player
+            .stash_houses
+            .values()
+            .map(|stash| self.stash_house_value(stash))
+            .sum::<i32>()
+            + self
+                .player
+                .fronts
+                .keys()
+                .map(|location| self.content.front_price(location))
+                .sum::<i32>();
+        let weapons = self
+            .player
+            .weapons
+            .iter()
+            .map(|(weapon, count)| {
+                self.weapon_price(weapon).unwrap_or(0) * count * WEAPON_RESALE_PERCENT / 100
+            })
+            .sum();
+
+        let dirty_cash = self.player.dirty_cash;
+        NetWorth {
+            cash: self.player.clean_cash(),
+            dirty_cash,
+            dirty_cash_penalty: (dirty_cash as f32 * self.rules.dirty_cash_penalty) as i32,
+            bank: self.player.bank_balance,
+            debt: self.player.debt,
+            carried_stock,
+            stashed_stock,
+            property,
+            weapons,
+        }
+    }
+
+    pub fn net_worth(&self) -> i32 {
+        self.net_worth_breakdown().total()
+    }
+
+    /// What the end-of-game fire sale raised, if the rules called for one.
+    pub fn fire_sale_proceeds(&self) -> Option<i32> {
+        self.fire_sale
+    }
+
+    /// What selling all of `inventory` in `location` would fetch today.
+    fn stock_value(&self, location: &str, inventory: &HashMap<String, i32>) -> i32 {
+        inventory
+            .iter()
+            .filter(|(_, amount)| **amount > 0)
+            .map(|(substance, amount)| self.market.trade_total(location, substance, -amount))
+            .sum()
+    }
+
+    /// Turns drugs, weapons, stash houses and fronts into cash at a discount,
+    /// and pays out the bank in full. What the drugs fetch is dirty.
+    fn hold_fire_sale(&mut self) {
+        let worth = self.net_worth_breakdown();
+        let goods = worth.carried_stock + worth.stashed_stock + worth.property + worth.weapons;
+        let proceeds = goods - goods * FIRE_SALE_DISCOUNT_PERCENT / 100;
+        let stock = worth.carried_stock + worth.stashed_stock;
+
+        self.player.cash += proceeds;
+        self.player.dirty_cash += stock - stock * FIRE_SALE_DISCOUNT_PERCENT / 100;
+        for amount in self.player.inventory.values_mut() {
+            *amount = 0;
+        }
+        for count in self.player.weapons.values_mut() {
+            *count = 0;
+        }
+        self.player.active_weapon = None;
+        self.player.stash_houses.clear();
+        self.player.fronts.clear();
+        self.player.cash += self.player.bank_balance;
+        self.player.bank_balance = 0;
+        self.fire_sale = Some(proceeds);
+
+        self.message_log.push(format!(
+            "Fire sale! Everything you owned went for ${}.",
+            proceeds
+        ));
+    }
+
+    // --- Actions ---
+
+    /// Carries out `action` through the same method a frontend would call.
+    pub fn apply(&mut self, action: &Action) -> Result<(), GameError> {
+        self.require_running()?;
+        match action {
+            Action::Buy { substance, amount } => self.buy(substance, *amount).map(drop),
+            Action::Sell { substance, amount } => self.sell(substance, *amount).map(drop),
+            Action::Travel(location) => self.travel(location).map(drop),
+            Action::Borrow(amount) => self.borrow(*amount).map(drop),
+            Action::Repay(amount) => self.repay(*amount).map(drop),
+            Action::BankDeposit(amount) => self.bank_deposit(*amount).map(drop),
+            Action::BankWithdraw(amount) => self.bank_withdraw(*amount).map(drop),
+            Action::Heal => self.heal().map(drop),
+            Action::BuyWeapon(weapon) => self.buy_weapon(weapon).map(drop),
+            Action::EquipWeapon(weapon) => self.equip_weapon(weapon),
+            Action::BuyStashHouse => self.buy_stash_house().map(drop),
+            Action::SellStashHouse => self.sell_stash_house().map(drop),
+            Action::UpgradeStashHouse(upgrade) => self.buy_stash_upgrade(*upgrade).map(drop),
+            Action::BuyFront => self.buy_front().map(drop),
+            Action::Launder(amount) => self.launder(*amount).map(drop),
+            Action::Deposit { substance, amount } => self.deposit(substance, *amount),
+            Action::Withdraw { substance, amount } => self.withdraw(substance, *amount),
+            Action::PoliceSurrender => self.police_surrender().map(drop),
+            Action::PoliceFight => self.police_fight().map(drop),
+            Action::PoliceRun => self.police_run().map(drop),
+            Action::PoliceNegotiate => self.police_negotiate().map(drop),
+            Action::OfferBribe(bribe) => self.offer_bribe(*bribe).map(drop),
+            Action::RefuseBribe => self.refuse_bribe().map(drop),
+            Action::PostBail => self.post_bail().map(drop),
+            Action::BorrowBail => self.borrow_bail().map(drop),
+            Action::AwaitTrial => self.await_trial().map(drop),
+        }
+    }
+}
+
+impl Default for GameEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every test plays the same game, so prices and dice rolls are fixed.
+    const SEED: u64 = 7;
+
+    fn engine() -> GameEngine {
+        GameEngine::with_seed(SEED)
+    }
+
+    #[test]
+    fn buy_moves_cash_into_stock() {
+        let mut game = engine();
+        let cash = game.player.cash;
+        let quote = game.quote_buy("Weed", 5);
+
+        let trade = game.buy("Weed", 5).unwrap();
+
+        assert_eq!(trade.total, quote);
+        assert_eq!(game.player.cash, cash - quote);
+        assert_eq!(game.player.inventory["Weed"], 5);
+    }
+
+    #[test]
+    fn buy_without_the_cash_is_refused() {
+        let mut game = engine();
+        game.player.cash = 0;
+
+        assert_eq!(game.buy("Weed", 1), Err(GameError::NotEnoughCash));
+        assert_eq!(game.player.inventory["Weed"], 0);
+    }
+
+    #[test]
+    fn buy_without_the_space_is_refused() {
+        let mut game = engine();
+        game.player.cash = 10_000_000;
+        let too_many = game.player.carrying_capacity + 1;
+
+        assert_eq!(game.buy("Weed", too_many), Err(GameError::NotEnoughSpace));
+        assert_eq!(game.player.cash, 10_000_000);
+    }
+
+    #[test]
+    fn sell_turns_stock_into_cash() {
+        let mut game = engine();
+        game.buy("Weed", 5).unwrap();
+        let cash = game.player.cash;
+        let quote = game.quote_sell("Weed", 5);
+
+        let trade = game.sell("Weed", 5).unwrap();
+
+        assert_eq!(trade.total, quote);
+        assert_eq!(game.player.cash, cash + quote);
+        assert_eq!(game.player.inventory["Weed"], 0);
+    }
+
+    #[test]
+    fn selling_more_than_you_hold_is_refused() {
+        let mut game = engine();
+
+        assert_eq!(game.sell("Weed", 1), Err(GameError::NotEnoughInventory));
+    }
+
+    #[test]
+    fn travel_moves_on_a_day() {
+        let mut game = engine();
+
+        assert_eq!(game.travel("Brooklyn"), Ok(TravelOutcome::Arrived));
+        assert_eq!(game.player.current_location, "Brooklyn");
+        assert_eq!(game.player.day, 2);
+    }
+
+    #[test]
+    fn travel_to_nowhere_is_refused() {
+        let mut game = engine();
+
+        assert_eq!(
+            game.travel("Atlantis"),
+            Err(GameError::UnknownLocation("Atlantis".to_string()))
+        );
+        assert_eq!(game.player.day, 1);
+    }
+
+    #[test]
+    fn borrow_and_repay_move_cash_and_debt() {
+        let mut game = engine();
+        let cash = game.player.cash;
+        let debt = game.player.debt;
+
+        assert_eq!(game.borrow(1000), Ok(debt + 1000));
+        assert_eq!(game.player.cash, cash + 1000);
+
+        assert_eq!(game.repay(1500), Ok(debt - 500));
+        assert_eq!(game.player.cash, cash - 500);
+    }
+
+    #[test]
+    fn nothing_else_can_be_done_while_held_by_police() {
+        let mut game = engine();
+        game.player.cash = 100_000;
+        game.player.health = 50;
+        game.player.weapons.insert("Knife".to_string(), 1);
+        game.police = Some(PoliceEncounter::new("Brooklyn"));
+
+        assert_eq!(game.borrow(100), Err(GameError::HeldByPolice));
+        assert_eq!(game.repay(100), Err(GameError::HeldByPolice));
+        assert_eq!(game.heal(), Err(GameError::HeldByPolice));
+        assert_eq!(game.buy_weapon("Knife"), Err(GameError::HeldByPolice));
+        assert_eq!(game.equip_weapon("Knife"), Err(GameError::HeldByPolice));
+        assert_eq!(game.buy_stash_house(), Err(GameError::HeldByPolice));
+        assert_eq!(game.player.cash, 100_000);
+    }
+
+    #[test]
+    fn nothing_can_be_done_once_the_game_is_over() {
+        let mut game = engine();
+        game.buy("Weed", 1).unwrap();
+        game.player.day = game.rules().max_days + 1;
+        game.update_state();
+        let worth = game.net_worth();
+
+        assert_eq!(game.buy("Weed", 1).map(drop), Err(GameError::GameOver));
+        assert_eq!(game.sell("Weed", 1).map(drop), Err(GameError::GameOver));
+        assert_eq!(game.travel("Brooklyn"), Err(GameError::GameOver));
+        assert_eq!(game.undo(), Err(GameError::GameOver));
+        assert_eq!(
+            game.apply(&Action::Travel("Brooklyn".to_string())),
+            Err(GameError::GameOver)
+        );
+        assert_eq!(game.net_worth(), worth);
+        assert_eq!(game.player.current_location, "Bronx");
+    }
+
+    #[test]
+    fn undo_puts_everything_back() {
+        let mut game = engine();
+        game.buy("Weed", 3).unwrap();
+        let player = serde_json::to_value(&game.player).unwrap();
+        let market = serde_json::to_value(&game.market).unwrap();
+        let log = game.message_log.clone();
+        let actions = game.actions().unwrap().to_vec();
+
+        game.sell("Weed", 2).unwrap();
+        assert_eq!(
+            game.undo(),
+            Ok(Action::Sell {
+                substance: "Weed".to_string(),
+                amount: 2
+            })
+        );
+
+        assert_eq!(serde_json::to_value(&game.player).unwrap(), player);
+        assert_eq!(serde_json::to_value(&game.market).unwrap(), market);
+        assert_eq!(game.message_log[..log.len()], log[..]);
+        assert_eq!(game.message_log.len(), log.len() + 1);
+        assert_eq!(game.actions().unwrap(), &actions[..]);
+    }
+
+    #[test]
+    fn travel_cannot_be_undone_and_ends_what_can() {
+        let mut game = engine();
+        game.buy("Weed", 3).unwrap();
+        assert!(game.last_undoable().is_some());
+
+        game.travel("Brooklyn").unwrap();
+
+        assert_eq!(game.last_undoable(), None);
+        assert_eq!(game.undo(), Err(GameError::NothingToUndo));
+        assert_eq!(game.player.inventory["Weed"], 3);
+        assert_eq!(game.player.current_location, "Brooklyn");
+    }
+
+    #[test]
+    fn bail_has_to_fit_within_the_credit_limit() {
+        let mut game = engine();
+        game.custody = Some(Custody {
+            destination: "Brooklyn".to_string(),
+            bail: game.credit_available() + 1,
+            days_until_trial: 3,
+        });
+        let debt = game.player.debt;
+
+        assert_eq!(game.borrow_bail(), Err(GameError::OverCreditLimit));
+        assert_eq!(game.player.debt, debt);
+        assert!(game.custody().is_some());
+    }
+
+    #[test]
+    fn the_credit_limit_copes_with_a_fortune() {
+        let mut game = engine();
+        game.player.bank_balance = i32::MAX;
+        game.player.cash = i32::MAX / 2;
+
+        assert_eq!(game.net_worth(), i32::MAX);
+        assert!(game.credit_limit() > game.rules().starting_debt);
+    }
+
+    #[test]
+    fn the_loan_shark_takes_dirty_money_first() {
+        let mut game = engine();
+        game.player.cash = 3000;
+        game.player.dirty_cash = 2000;
+
+        game.repay(1500).unwrap();
+
+        assert_eq!(game.player.clean_cash(), 1000);
+        assert_eq!(game.player.dirty_cash, 500);
+    }
+
+    #[test]
+    fn the_hospital_takes_clean_money_first() {
+        let mut game = engine();
+        game.player.current_location = "Brooklyn".to_string();
+        game.player.health = 40;
+        let cost = game.heal_cost();
+        game.player.cash = cost + 500 + 2000;
+        game.player.dirty_cash = 2000;
+
+        assert_eq!(game.heal(), Ok(cost));
+        assert_eq!(game.player.clean_cash(), 500);
+        assert_eq!(game.player.dirty_cash, 2000);
+
+        // Once the clean money runs out the rest comes out of the dirty
+        game.player.health = 40;
+        game.player.cash = cost + 2000;
+        game.player.dirty_cash = 2000 + cost / 2;
+        game.heal().unwrap();
+        assert_eq!(game.player.clean_cash(), 0);
+        assert_eq!(game.player.dirty_cash, 2000);
+    }
+
+    #[test]
+    fn bail_takes_dirty_money_first() {
+        let mut game = engine();
+        game.player.cash = 5000;
+        game.player.dirty_cash = 3000;
+        game.custody = Some(Custody {
+            destination: "Brooklyn".to_string(),
+            bail: 1000,
+            days_until_trial: 3,
+        });
+
+        let verdict = game.post_bail().unwrap();
+
+        let fine = match verdict {
+            CourtVerdict::Fined(fine) => fine,
+            _ => 0,
+        };
+        assert_eq!(game.player.clean_cash(), 2000 - (fine - 2000).max(0));
+        assert_eq!(game.player.dirty_cash, (2000 - fine).max(0));
+    }
+
+    #[test]
+    fn fronts_cant_be_bought_in_custody() {
+        let mut game = engine();
+        game.player.cash = 1_000_000;
+        game.custody = Some(Custody {
+            destination: "Brooklyn".to_string(),
+            bail: 1000,
+            days_until_trial: 3,
+        });
+
+        assert_eq!(game.buy_front(), Err(GameError::HeldByPolice));
+        assert!(game.player.fronts.is_empty());
+    }
+
+    #[test]
+    fn repaying_more_than_you_have_is_refused() {
+        let mut game = engine();
+        game.player.cash = 100;
+
+        assert_eq!(game.repay(200), Err(GameError::NotEnoughCash));
+        assert_eq!(game.player.cash, 100);
+    }
+
+    /// A game standing in Brooklyn, which has a bank.
+    fn at_the_bank() -> GameEngine {
+        let mut game = engine();
+        game.player.current_location = "Brooklyn".to_string();
+        game
+    }
+
+    #[test]
+    fn only_clean_cash_goes_in_the_bank() {
+        let mut game = at_the_bank();
+        game.player.cash = 3000;
+        game.player.dirty_cash = 2000;
+
+        assert_eq!(game.bank_deposit(1500), Err(GameError::NotEnoughCleanCash));
+        assert_eq!(game.bank_deposit(4000), Err(GameError::NotEnoughCash));
+        assert_eq!(game.bank_deposit(1000), Ok(1000));
+        assert_eq!(game.player.cash, 2000);
+        assert_eq!(game.player.dirty_cash, 2000);
+
+        game.player.current_location = "Bronx".to_string();
+        assert_eq!(
+            game.bank_deposit(1),
+            Err(GameError::ServiceUnavailable(Service::Bank))
+        );
+    }
+
+    #[test]
+    fn withdrawals_stop_at_the_daily_limit_until_the_next_day() {
+        let mut game = at_the_bank();
+        game.rules.bank_withdrawal_limit = Some(1000);
+        game.player.bank_balance = 5000;
+
+        assert_eq!(game.bank_withdraw(600), Ok(4400));
+        assert_eq!(game.bank_withdrawal_left(), Some(400));
+        assert_eq!(game.bank_withdraw(500), Err(GameError::OverWithdrawalLimit));
+        assert_eq!(game.bank_withdraw(400), Ok(4000));
+        assert_eq!(game.player.withdrawn_today, 1000);
+
+        game.start_new_day(None);
+        assert_eq!(game.player.withdrawn_today, 0);
+        assert_eq!(game.bank_withdrawal_left(), Some(1000));
+    }
+
+    #[test]
+    fn savings_earn_interest_every_day() {
+        let mut game = at_the_bank();
+        game.player.bank_balance = 10_000;
+        let interest = (10_000.0 * game.rules.bank_interest) as i32;
+        assert!(interest > 0);
+
+        game.start_new_day(None);
+
+        assert_eq!(game.player.bank_balance, 10_000 + interest);
+    }
+
+    /// What the police did over a run of visits to one stash house.
+    #[derive(Default)]
+    struct Raids {
+        seen_off: i32,
+        raided: i32,
+        seized: i32,
+        /// Least and most of the stock taken, in percent, from houses that
+        /// weren't seized.
+        least_taken: i32,
+        most_taken: i32,
+    }
+
+    /// Gives the police `visits` chances at a Bronx stash house holding 100
+    /// Weed, putting the house back as it was before each one.
+    fn raid_stash_house(upgrades: &[StashUpgrade], visits: usize) -> Raids {
+        let mut game = engine();
+        let mut raids = Raids {
+            least_taken: 100,
+            ..Raids::default()
+        };
+        for _ in 0..visits {
+            let mut stash = StashHouse::new("Bronx".to_string(), 1000);
+            stash.inventory.insert("Weed".to_string(), 100);
+            stash.upgrades = upgrades.to_vec();
+            game.player.stash_houses.insert("Bronx".to_string(), stash);
+            game.player.heat = 0;
+            let logged = game.message_log.len();
+            let raided = game.player.record.raids;
+
+            game.check_stash_house_raid();
+
+            if game.player.record.raids > raided {
+                raids.raided += 1;
+                match game.player.stash_houses.get("Bronx") {
+                    Some(stash) => {
+                        let taken = 100 - stash.inventory["Weed"];
+                        raids.least_taken = raids.least_taken.min(taken);
+                        raids.most_taken = raids.most_taken.max(taken);
+                    }
+                    None => raids.seized += 1,
+                }
+            } else if game.message_log.len() > logged {
+                raids.seen_off += 1;
+            }
+        }
+        raids
+    }
+
+    #[test]
+    fn extra_space_costs_more_each_tier_up_to_the_cap() {
+        let mut game = engine();
+        game.player.cash = 1_000_000;
+        game.buy_stash_house().unwrap();
+        let price = game.stash_house_price("Bronx");
+        let capacity = game.rules().stash_house_capacity;
+
+        for tier in 1..=MAX_EXTRA_SPACE_TIERS {
+            let expected = price * StashUpgrade::ExtraSpace.price_percent() * tier / 100;
+            assert_eq!(
+                game.stash_upgrade_price("Bronx", StashUpgrade::ExtraSpace),
+                Some(expected)
+            );
+            assert_eq!(
+                game.buy_stash_upgrade(StashUpgrade::ExtraSpace),
+                Ok(expected)
+            );
+            assert_eq!(
+                game.player.stash_houses["Bronx"].capacity,
+                capacity + capacity * EXTRA_SPACE_PERCENT / 100 * tier
+            );
+        }
+
+        assert_eq!(
+            game.stash_upgrade_price("Bronx", StashUpgrade::ExtraSpace),
+            None
+        );
+        assert_eq!(
+            game.buy_stash_upgrade(StashUpgrade::ExtraSpace),
+            Err(GameError::UpgradeAlreadyOwned(StashUpgrade::ExtraSpace))
+        );
+    }
+
+    #[test]
+    fn other_upgrades_can_only_be_bought_once() {
+        let mut game = engine();
+        game.player.cash = 1_000_000;
+        assert_eq!(
+            game.buy_stash_upgrade(StashUpgrade::Alarm),
+            Err(GameError::NoStashHouse)
+        );
+        game.buy_stash_house().unwrap();
+        let price = game.stash_house_price("Bronx");
+
+        for upgrade in [
+            StashUpgrade::ReinforcedDoor,
+            StashUpgrade::Alarm,
+            StashUpgrade::Guards,
+        ] {
+            let cash = game.player.cash;
+            assert_eq!(
+                game.buy_stash_upgrade(upgrade),
+                Ok(price * upgrade.price_percent() / 100)
+            );
+            assert_eq!(
+                game.player.cash,
+                cash - price * upgrade.price_percent() / 100
+            );
+            assert_eq!(
+                game.buy_stash_upgrade(upgrade),
+                Err(GameError::UpgradeAlreadyOwned(upgrade))
+            );
+        }
+
+        let stash = &game.player.stash_houses["Bronx"];
+        assert_eq!(stash.raid_protection(), 75);
+        assert_eq!(stash.confiscation_reduction(), 30);
+        assert_eq!(stash.seizure_reduction(), 50);
+    }
+
+    #[test]
+    fn raids_on_a_bare_stash_house_take_at_least_half() {
+        let raids = raid_stash_house(&[], 20_000);
+
+        assert_eq!(raids.seen_off, 0);
+        assert!(raids.raided > 0);
+        assert!(raids.least_taken >= 50);
+        assert_eq!(raids.most_taken, 100);
+    }
+
+    #[test]
+    fn an_alarm_sees_off_about_half_the_raids() {
+        let raids = raid_stash_house(&[StashUpgrade::Alarm], 20_000);
+        let visits = raids.seen_off + raids.raided;
+
+        assert!(visits > 500);
+        assert!((raids.seen_off * 100 / visits - 50).abs() <= 5);
+    }
+
+    #[test]
+    fn a_reinforced_door_and_guards_hold_on_to_stock() {
+        let raids = raid_stash_house(
+            &[StashUpgrade::ReinforcedDoor, StashUpgrade::Guards],
+            20_000,
+        );
+
+        assert!(raids.raided > 0);
+        assert!(raids.least_taken >= 20);
+        assert!(raids.most_taken <= 70);
+    }
+
+    #[test]
+    fn a_reinforced_door_halves_the_chance_of_seizure() {
+        let bare = raid_stash_house(&[], 100_000);
+        let door = raid_stash_house(&[StashUpgrade::ReinforcedDoor], 100_000);
+
+        // 10% of raids end in seizure without the door, 5% with it
+        let bare_rate = bare.seized * 1000 / bare.raided;
+        let door_rate = door.seized * 1000 / door.raided;
+        assert!((bare_rate - 100).abs() <= 20, "{}", bare_rate);
+        assert!((door_rate - 50).abs() <= 15, "{}", door_rate);
+    }
+
+    #[test]
+    fn guards_are_paid_daily_and_walk_when_they_are_not() {
+        let mut game = engine();
+        game.player.cash = 1_000_000;
+        game.buy_stash_house().unwrap();
+        game.buy_stash_upgrade(StashUpgrade::Guards).unwrap();
+        let wages = game.guard_wages("Bronx");
+        assert_eq!(
+            wages,
+            game.stash_house_price("Bronx") * GUARD_WAGES_PERCENT / 100
+        );
+
+        let cash = game.player.cash;
+        game.pay_guards();
+        assert_eq!(game.player.cash, cash - wages);
+        assert!(game.player.stash_houses["Bronx"].has_upgrade(StashUpgrade::Guards));
+
+        game.player.cash = wages - 1;
+        game.pay_guards();
+        assert_eq!(game.player.cash, wages - 1);
+        assert!(!game.player.stash_houses["Bronx"].has_upgrade(StashUpgrade::Guards));
+    }
+
+    #[test]
+    fn selling_a_stash_house_fills_your_pockets_and_loses_the_rest() {
+        let mut game = engine();
+        game.player.cash = 1_000_000;
+        game.buy_stash_house().unwrap();
+        game.player.inventory.insert("Weed".to_string(), 10);
+        let space = game.player.space_available();
+        let stash = game.player.stash_houses.get_mut("Bronx").unwrap();
+        stash.inventory.insert("Weed".to_string(), space + 25);
+
+        game.sell_stash_house().unwrap();
+
+        assert!(game.player.stash_houses.is_empty());
+        assert_eq!(game.player.inventory["Weed"], 10 + space);
+        assert_eq!(game.player.space_available(), 0);
+        assert!(game.message_log.last().unwrap().contains("25 units"));
+    }
+
+    #[test]
+    fn a_sold_stash_house_is_paid_for_in_dirty_cash() {
+        let mut game = engine();
+        game.player.cash = 1_000_000;
+        game.buy_stash_house().unwrap();
+        game.buy_stash_upgrade(StashUpgrade::Alarm).unwrap();
+        let value = game.stash_house_value(&game.player.stash_houses["Bronx"]);
+        let price = value - value * BROKER_CUT_PERCENT / 100;
+        assert_eq!(game.stash_house_sale_price("Bronx"), Some(price));
+        let clean = game.player.clean_cash();
+        let dirty = game.player.dirty_cash;
+
+        assert_eq!(game.sell_stash_house(), Ok(price));
+
+        assert_eq!(game.player.clean_cash(), clean);
+        assert_eq!(game.player.dirty_cash, dirty + price);
+        assert_eq!(game.sell_stash_house(), Err(GameError::NoStashHouse));
+    }
+
+    #[test]
+    fn stash_houses_count_in_net_worth() {
+        let mut game = engine();
+        game.player.cash = 1_000_000;
+        let before = game.net_worth_breakdown();
+        assert_eq!(before.property, 0);
+
+        game.buy_stash_house().unwrap();
+        game.buy_stash_upgrade(StashUpgrade::ExtraSpace).unwrap();
+        game.buy_stash_upgrade(StashUpgrade::Guards).unwrap();
+        let stash = game.player.stash_houses["Bronx"].clone();
+        let price = game.stash_house_price("Bronx");
+        let worth = game.net_worth_breakdown();
+
+        // Extra space is part of the house, guards are not
+        assert_eq!(game.stash_house_value(&stash), price + price / 2);
+        assert_eq!(worth.property, game.stash_house_value(&stash));
+
+        // Stock in the house is valued where it sits
+        game.player
+            .stash_houses
+            .get_mut("Bronx")
+            .unwrap()
+            .inventory
+            .insert("Weed".to_string(), 10);
+        let stocked = game.net_worth_breakdown();
+        assert_eq!(
+            stocked.stashed_stock,
+            game.market.trade_total("Bronx", "Weed", -10)
+        );
+        assert_eq!(stocked.total(), worth.total() + stocked.stashed_stock);
+    }
+
+    /// A game pulled over on the way to Brooklyn and haggling with an
+    /// officer who asks $1000, splits the difference and will hear three
+    /// counter-offers.
+    fn haggling() -> GameEngine {
+        let mut game = engine();
+        game.player.cash = 5000;
+        game.police = Some(PoliceEncounter::new("Brooklyn"));
+        assert_eq!(game.police_negotiate(), Ok(PoliceState::Negotiating));
+        let negotiation = game.bribe_negotiation().unwrap();
+        assert!(negotiation.asking_price > 0);
+        assert!((2..=5).contains(&negotiation.patience));
+
+        let encounter = game.police.as_mut().unwrap();
+        encounter.negotiation = Some(BribeNegotiation {
+            asking_price: 1000,
+            patience: 3,
+            greed: 0.5,
+            offers: Vec::new(),
+        });
+        game
+    }
+
+    #[test]
+    fn the_officer_counters_until_the_price_is_met() {
+        let mut game = haggling();
+
+        assert_eq!(game.offer_bribe(600), Ok(PoliceState::Negotiating));
+        let negotiation = game.bribe_negotiation().unwrap();
+        assert_eq!(negotiation.asking_price, 800);
+        assert_eq!(negotiation.patience, 2);
+        assert_eq!(negotiation.offers, [600]);
+        // Nothing is paid until the officer takes the money
+        assert_eq!(game.player.cash, 5000);
+
+        assert_eq!(game.offer_bribe(700), Ok(PoliceState::Negotiating));
+        assert_eq!(game.bribe_negotiation().unwrap().asking_price, 750);
+
+        assert_eq!(game.offer_bribe(750), Ok(PoliceState::Escaped));
+        assert_eq!(game.player.cash, 4250);
+        assert!(game.police.is_none());
+        assert!(game.custody.is_none());
+        assert_eq!(game.player.current_location, "Brooklyn");
+    }
+
+    #[test]
+    fn lowballing_runs_out_the_officers_patience() {
+        let mut game = haggling();
+
+        // Under a quarter of the asking price costs two rounds of patience
+        assert_eq!(game.offer_bribe(200), Ok(PoliceState::Negotiating));
+        assert_eq!(game.bribe_negotiation().unwrap().patience, 1);
+        assert_eq!(game.offer_bribe(1), Ok(PoliceState::Arrested));
+
+        assert_eq!(game.player.cash, 5000);
+        assert_eq!(game.player.record.arrests, 1);
+        assert_eq!(game.custody().unwrap().destination, "Brooklyn");
+    }
+
+    #[test]
+    fn bribes_are_only_offered_while_haggling() {
+        let mut game = haggling();
+        assert_eq!(game.offer_bribe(0), Err(GameError::NonPositiveAmount));
+        assert_eq!(game.offer_bribe(6000), Err(GameError::NotEnoughCash));
+
+        // Once refused, the search settles the stop one way or the other
+        game.refuse_bribe().unwrap();
+        assert!(game.police.is_none());
+        assert_eq!(game.offer_bribe(100), Err(GameError::NotStopped));
+
+        game.police = Some(PoliceEncounter::new("Brooklyn"));
+        assert_eq!(
+            game.offer_bribe(100),
+            Err(GameError::PoliceActionUnavailable)
+        );
+    }
+
+    /// A game with nothing owed, held on the way to Brooklyn until a trial
+    /// `days_until_trial` days away.
+    fn held_for_trial(seed: u64, days_until_trial: i32) -> GameEngine {
+        let mut game = GameEngine::with_seed(seed);
+        game.player.cash = 5000;
+        game.player.debt = 0;
+        game.custody = Some(Custody {
+            destination: "Brooklyn".to_string(),
+            bail: 1000,
+            days_until_trial,
+        });
+        game
+    }
+
+    #[test]
+    fn every_day_waiting_for_trial_and_serving_counts_as_jail() {
+        let mut verdicts = Vec::new();
+        for seed in 0..100 {
+            let mut game = held_for_trial(seed, 1);
+            let day = game.player.day;
+
+            let verdict = game.await_trial().unwrap();
+
+            let sentence = match verdict {
+                CourtVerdict::Dismissed => {
+                    assert_eq!(game.player.cash, 5000);
+                    0
+                }
+                CourtVerdict::Fined(fine) => {
+                    assert!(fine > 0);
+                    assert_eq!(game.player.cash, 5000 - fine);
+                    0
+                }
+                CourtVerdict::Jailed(days) => {
+                    // Two days for a first conviction, less the one served
+                    assert_eq!(days, 1);
+                    assert_eq!(game.player.record.convictions, 1);
+                    days
+                }
+            };
+            assert_eq!(game.player.record.days_in_jail, 1 + sentence);
+            // Released the next morning where the trip was headed
+            assert_eq!(game.player.day, day + 1 + sentence + 1);
+            assert_eq!(game.player.current_location, "Brooklyn");
+            assert!(game.custody().is_none());
+            verdicts.push(verdict);
+        }
+
+        assert!(verdicts.contains(&CourtVerdict::Dismissed));
+        assert!(verdicts.iter().any(|v| matches!(v, CourtVerdict::Fined(_))));
+        assert!(verdicts.contains(&CourtVerdict::Jailed(1)));
+    }
+
+    #[test]
+    fn a_record_and_heat_make_a_conviction_likelier_and_longer() {
+        let mut first_offences = 0;
+        let mut repeat_offences = 0;
+        for seed in 0..200 {
+            let mut game = held_for_trial(seed, 1);
+            if let CourtVerdict::Jailed(_) = game.post_bail().unwrap() {
+                first_offences += 1;
+            }
+
+            let mut game = held_for_trial(seed, 1);
+            game.player.heat = 60;
+            game.player.record.arrests = 4;
+            game.player.record.convictions = 3;
+            if let CourtVerdict::Jailed(days) = game.post_bail().unwrap() {
+                // Two days, two more per conviction and one per 20 heat
+                assert_eq!(days, 2 + 3 * 2 + 3);
+                assert_eq!(game.player.record.days_in_jail, days);
+                repeat_offences += 1;
+            }
+        }
+
+        // 15% for a first offence, 90% for a known offender
+        assert!((15..=45).contains(&first_offences), "{}", first_offences);
+        assert!(repeat_offences >= 165, "{}", repeat_offences);
+    }
+
+    #[test]
+    fn an_arrest_takes_the_weapons_and_goes_on_the_record() {
+        let mut game = engine();
+        game.player.cash = 1_000_000;
+        game.buy_weapon("Pistol").unwrap();
+        game.player.inventory.insert("Weed".to_string(), 10);
+        game.police = Some(PoliceEncounter::new("Brooklyn"));
+        game.police_negotiate().unwrap();
+        game.police
+            .as_mut()
+            .unwrap()
+            .negotiation
+            .as_mut()
+            .unwrap()
+            .patience = 1;
+
+        assert_eq!(game.offer_bribe(1), Ok(PoliceState::Arrested));
+
+        assert!(!game.player.has_weapon());
+        assert_eq!(game.player.active_weapon, None);
+        assert_eq!(game.player.total_items(), 0);
+        assert_eq!(game.player.record.arrests, 1);
+        let custody = game.custody().unwrap();
+        assert!(custody.bail > 0);
+        assert!(custody.days_until_trial >= 1);
+    }
+
+    /// A player with something in every column of the net worth breakdown.
+    fn well_off() -> GameEngine {
+        let mut game = engine();
+        game.player.cash = 100_000;
+        game.buy_stash_house().unwrap();
+        game.buy_weapon("Pistol").unwrap();
+        game.player.cash = 5000;
+        game.player.dirty_cash = 1000;
+        game.player.bank_balance = 2000;
+        game.player.debt = 3000;
+        game.player.inventory.insert("Weed".to_string(), 10);
+        game.player
+            .stash_houses
+            .get_mut("Bronx")
+            .unwrap()
+            .inventory
+            .insert("Heroin".to_string(), 5);
+        game
+    }
+
+    #[test]
+    fn net_worth_counts_everything_the_player_owns() {
+        let game = well_off();
+        let worth = game.net_worth_breakdown();
+
+        assert_eq!(worth.cash, 4000);
+        assert_eq!(worth.dirty_cash, 1000);
+        assert_eq!(
+            worth.dirty_cash_penalty,
+            (1000.0 * game.rules().dirty_cash_penalty) as i32
+        );
+        assert_eq!(worth.bank, 2000);
+        assert_eq!(worth.debt, 3000);
+        assert_eq!(
+            worth.carried_stock,
+            game.market.trade_total("Bronx", "Weed", -10)
+        );
+        assert_eq!(
+            worth.stashed_stock,
+            game.market.trade_total("Bronx", "Heroin", -5)
+        );
+        assert_eq!(worth.property, game.stash_house_price("Bronx"));
+        assert_eq!(
+            worth.weapons,
+            game.weapon_price("Pistol").unwrap() * WEAPON_RESALE_PERCENT / 100
+        );
+        assert_eq!(game.net_worth(), worth.total());
+    }
+
+    #[test]
+    fn a_fire_sale_turns_everything_into_cash_at_a_discount() {
+        let mut game = well_off();
+        let worth = game.net_worth_breakdown();
+        let goods = worth.carried_stock + worth.stashed_stock + worth.property + worth.weapons;
+        let stock = worth.carried_stock + worth.stashed_stock;
+        let proceeds = goods - goods * FIRE_SALE_DISCOUNT_PERCENT / 100;
+
+        game.rules.fire_sale = true;
+        game.player.day = game.rules().max_days + 1;
+        game.update_state();
+
+        assert!(!game.is_running());
+        assert_eq!(game.fire_sale_proceeds(), Some(proceeds));
+        assert_eq!(game.player.cash, 5000 + proceeds + 2000);
+        assert_eq!(
+            game.player.dirty_cash,
+            1000 + stock - stock * FIRE_SALE_DISCOUNT_PERCENT / 100
+        );
+        assert_eq!(game.player.bank_balance, 0);
+        assert_eq!(game.player.total_items(), 0);
+        assert!(!game.player.has_weapon());
+        assert!(game.player.stash_houses.is_empty());
+
+        let after = game.net_worth_breakdown();
+        assert_eq!(after.carried_stock + after.stashed_stock, 0);
+        assert_eq!(after.property + after.weapons, 0);
+    }
+}
@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+
+/// What happened to the market.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MarketEventKind {
+    EnforcementCrackdown,
+    CartelBust,
+    SyntheticFlood,
+    Recession,
+    ShipmentBust,
+    MarketFlooded,
+    PremiumProduct,
+    Contamination,
+}
+
+impl MarketEventKind {
+    /// The usual price multiplier for this kind of news.
+    pub fn default_magnitude(self) -> f32 {
+        match self {
+            MarketEventKind::EnforcementCrackdown => 1.5, // Prices up 50%
+            MarketEventKind::CartelBust => 2.0,           // Prices doubled
+            MarketEventKind::SyntheticFlood => 0.5,       // Prices halved
+            MarketEventKind::Recession => 0.7,            // Prices 30% down
+            MarketEventKind::ShipmentBust => 5.0,
+            MarketEventKind::MarketFlooded => 0.2,
+            MarketEventKind::PremiumProduct => 3.0,
+            MarketEventKind::Contamination => 1.0 / 3.0,
+        }
+    }
+}
+
+/// A piece of news that moves prices for a few days.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketEvent {
+    pub kind: MarketEventKind,
+    /// Substances affected; empty means every substance.
+    pub substances: Vec<String>,
+    /// Boroughs affected; empty means the whole city.
+    pub locations: Vec<String>,
+    /// Multiplier applied to affected prices while the event lasts.
+    pub magnitude: f32,
+    /// Days the event still applies to, counting today.
+    pub days_remaining: i32,
+    /// Headline from the content pack, with `{substance}` and `{location}`
+    /// placeholders; the standard text for the kind is used when missing.
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+impl MarketEvent {
+    /// News that moves every price in the city.
+    pub fn global(kind: MarketEventKind, days: i32) -> Self {
+        MarketEvent {
+            kind,
+            substances: Vec::new(),
+            locations: Vec::new(),
+            magnitude: kind.default_magnitude(),
+            days_remaining: days,
+            text: None,
+        }
+    }
+
+    /// News about one substance in one borough.
+    pub fn local(kind: MarketEventKind, substance: &str, location: &str, days: i32) -> Self {
+        MarketEvent {
+            kind,
+            substances: vec![substance.to_string()],
+            locations: vec![location.to_string()],
+            magnitude: kind.default_magnitude(),
+            days_remaining: days,
+            text: None,
+        }
+    }
+
+    pub fn with_magnitude(mut self, magnitude: f32) -> Self {
+        self.magnitude = magnitude;
+        self
+    }
+
+    pub fn with_text(mut self, text: Option<String>) -> Self {
+        self.text = text;
+        self
+    }
+
+    pub fn applies_to(&self, location: &str, substance: &str) -> bool {
+        (self.locations.is_empty() || self.locations.iter().any(|l| l == location))
+            && (self.substances.is_empty() || self.substances.iter().any(|s| s == substance))
+    }
+
+    pub fn headline(&self) -> String {
+        let substance = if self.substances.is_empty() {
+            "drugs".to_string()
+        } else {
+            self.substances.join(" and ")
+        };
+        let location = if self.locations.is_empty() {
+            "the city".to_string()
+        } else {
+            self.locations.join(" and ")
+        };
+
+        if let Some(text) = &self.text {
+            return format!(
+                "📰 {}",
+                text.replace("{substance}", &substance)
+                    .replace("{location}", &location)
+            );
+        }
+
+        match self.kind {
+            MarketEventKind::EnforcementCrackdown => {
+                "📰 GLOBAL EVENT: International drug enforcement operation! All prices increased!"
+                    .to_string()
+            }
+            MarketEventKind::CartelBust => {
+                "📰 GLOBAL EVENT: Major drug cartel busted! Supply chains disrupted!".to_string()
+            }
+            MarketEventKind::SyntheticFlood => {
+                "📰 GLOBAL EVENT: New synthetic alternatives flooding market! All prices decreased!"
+                    .to_string()
+            }
+            MarketEventKind::Recession => {
+                "📰 GLOBAL EVENT: Economic recession! Drug demand down!".to_string()
+            }
+            MarketEventKind::ShipmentBust => format!(
+                "📰 Breaking news! Police busted a {} shipment in {}! Prices skyrocketing!",
+                substance, location
+            ),
+            MarketEventKind::MarketFlooded => format!(
+                "📰 {} is flooded with {}! Prices have crashed!",
+                location, substance
+            ),
+            MarketEventKind::PremiumProduct => format!(
+                "📰 New high-quality {} on the market in {}! Premium prices!",
+                substance, location
+            ),
+            MarketEventKind::Contamination => format!(
+                "📰 Contaminated {} discovered in {}! Demand plummeting!",
+                substance, location
+            ),
+        }
+    }
+
+    /// Short description of the price effect, e.g. "prices +50%".
+    pub fn effect(&self) -> String {
+        let percent = ((self.magnitude - 1.0) * 100.0).round() as i32;
+        if percent >= 0 {
+            format!("prices +{}%", percent)
+        } else {
+            format!("prices {}%", percent)
+        }
+    }
+}
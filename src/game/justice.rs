@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+/// Everything the police and the courts remember about you.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CriminalRecord {
+    pub arrests: i32,
+    pub convictions: i32,
+    pub days_in_jail: i32,
+    /// Times the police have raided one of your stash houses.
+    #[serde(default)]
+    pub raids: i32,
+    /// What those raids cost: stock confiscated, fines and houses seized.
+    #[serde(default)]
+    pub raid_losses: i32,
+    /// Drug money taken as proceeds of crime.
+    #[serde(default)]
+    pub cash_seized: i32,
+}
+
+impl CriminalRecord {
+    pub fn is_clean(&self) -> bool {
+        self.arrests == 0
+    }
+}
+
+/// Held at the precinct after an arrest, waiting for bail or trial.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Custody {
+    /// Where the player was headed; they are released there.
+    pub destination: String,
+    /// What it costs to walk out today and come back for the hearing.
+    pub bail: i32,
+    /// Days in a cell before the case comes to court if bail isn't paid.
+    pub days_until_trial: i32,
+}
+
+/// How a court case ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CourtVerdict {
+    Dismissed,
+    Fined(i32),
+    /// Sentenced to this many more days in jail.
+    Jailed(i32),
+}
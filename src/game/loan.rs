@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+/// Days the loan shark gives for each repayment.
+pub const TERM_DAYS: i32 = 5;
+/// Share of the debt, in percent, that has to be paid back every term.
+pub const MINIMUM_PAYMENT_PERCENT: i32 = 20;
+/// Days late before enforcers start coming round.
+pub const ENFORCERS_AFTER_DAYS: i32 = 2;
+/// Days late before the loan shark stops lending.
+pub const BARRED_AFTER_DAYS: i32 = 3;
+
+/// The loan shark's side of the debt: when the next payment is due and how
+/// the player has paid so far.
+///
+/// The debt is paid back in terms of `TERM_DAYS`. Each term a slice of it is
+/// due; paying it on time earns more credit, missing it costs credit and
+/// sends the loan shark's people after the player.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LoanAccount {
+    /// Last day of the current term; `None` while nothing is owed.
+    pub due_day: Option<i32>,
+    /// What has to be paid back by `due_day`.
+    pub minimum_payment: i32,
+    /// Paid back so far this term.
+    pub paid_this_term: i32,
+    /// Terms paid in full and on time.
+    pub terms_met: i32,
+    /// Terms that went overdue.
+    pub terms_missed: i32,
+    /// Refused any more loans until the debt is cleared.
+    pub barred: bool,
+    /// Times the enforcers have caught up with the player.
+    pub enforcer_visits: i32,
+}
+
+impl LoanAccount {
+    /// What is still owed this term.
+    pub fn payment_due(&self) -> i32 {
+        (self.minimum_payment - self.paid_this_term).max(0)
+    }
+
+    /// How many days late this term's payment is on `day`.
+    pub fn days_overdue(&self, day: i32) -> i32 {
+        match self.due_day {
+            Some(due) if self.payment_due() > 0 => (day - due).max(0),
+            _ => 0,
+        }
+    }
+
+    /// How far the loan shark trusts the player, in percent: each term paid
+    /// on time adds to it and each one missed takes away more.
+    pub fn trust_percent(&self) -> i32 {
+        (100 + 20 * self.terms_met - 50 * self.terms_missed).clamp(0, 200)
+    }
+
+    /// Starts a new term on `day` for a debt of `debt`.
+    pub(crate) fn start_term(&mut self, day: i32, debt: i32) {
+        if debt <= 0 {
+            self.due_day = None;
+            self.minimum_payment = 0;
+        } else {
+            self.due_day = Some(day + TERM_DAYS);
+            self.minimum_payment = (debt * MINIMUM_PAYMENT_PERCENT / 100).max(1);
+        }
+        self.paid_this_term = 0;
+    }
+}
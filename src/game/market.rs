@@ -0,0 +1,443 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::{ContentPack, MarketEvent, MarketEventKind};
+
+/// Share of the gap to the long-run level that closes each day.
+const PRICE_REVERSION: f32 = 0.3;
+const SUPPLY_REVERSION: f32 = 0.35;
+/// Daily random swing of the underlying price, as a share of its fair value.
+const PRICE_VOLATILITY: f32 = 0.35;
+/// Daily random swing of local supply and demand.
+const SUPPLY_VOLATILITY: f32 = 0.1;
+/// Supply never drops below this, however much the player buys up.
+const MIN_SUPPLY: f32 = 0.2;
+/// Share of the gap to listed property prices that closes each day.
+const PROPERTY_REVERSION: f32 = 0.1;
+/// Daily random swing of property prices.
+const PROPERTY_VOLATILITY: f32 = 0.06;
+/// How far property prices can fall below or rise above the listed price.
+const MIN_PROPERTY_INDEX: f32 = 0.5;
+const MAX_PROPERTY_INDEX: f32 = 2.0;
+
+/// The forces behind one substance's price in one borough.
+///
+/// The price on the street is `base_price * demand / supply` before news and
+/// global events are applied. `base_price` wanders around the borough's fair
+/// value; `supply` and `demand` sit around 1.0 and drift back there after the
+/// player or the dealers push them away. `depth` is how many units have to
+/// be dumped here to double the supply and halve the price.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MarketConditions {
+    pub base_price: f32,
+    pub supply: f32,
+    pub demand: f32,
+    #[serde(default = "default_depth")]
+    pub depth: f32,
+}
+
+fn default_depth() -> f32 {
+    200.0
+}
+
+impl MarketConditions {
+    fn starting_at(base_price: f32, depth: f32) -> Self {
+        MarketConditions {
+            base_price,
+            supply: 1.0,
+            demand: 1.0,
+            depth,
+        }
+    }
+}
+
+/// Prices and their recent history in one borough.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LocalMarket {
+    pub prices: HashMap<String, i32>,
+    pub price_history: HashMap<String, Vec<i32>>,
+    #[serde(default)]
+    pub conditions: HashMap<String, MarketConditions>,
+    /// Property prices as a multiple of the borough's listed price.
+    #[serde(default = "default_property_index")]
+    pub property_index: f32,
+    /// Previous days' `property_index`, oldest first.
+    #[serde(default)]
+    pub property_history: Vec<f32>,
+}
+
+fn default_property_index() -> f32 {
+    1.0
+}
+
+impl LocalMarket {
+    fn new(content: &ContentPack) -> Self {
+        let mut price_history = HashMap::new();
+        // Initialize price history for each substance
+        for substance in content.substance_names() {
+            price_history.insert(substance.to_string(), Vec::new());
+        }
+
+        LocalMarket {
+            prices: HashMap::new(),
+            price_history,
+            conditions: HashMap::new(),
+            property_index: default_property_index(),
+            property_history: Vec::new(),
+        }
+    }
+
+    /// Local supply of `substance` and how deep its market is.
+    fn supply(&self, substance: &str) -> (f32, f32) {
+        self.conditions
+            .get(substance)
+            .map(|c| (c.supply, c.depth))
+            .unwrap_or((1.0, default_depth()))
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Market {
+    pub locations: HashMap<String, LocalMarket>,
+    /// News that is still moving prices.
+    pub events: Vec<MarketEvent>,
+    pub max_history: usize,
+}
+
+impl Market {
+    pub fn new(content: &ContentPack) -> Self {
+        let mut locations = HashMap::new();
+        for location in content.location_names() {
+            locations.insert(location.to_string(), LocalMarket::new(content));
+        }
+
+        Market {
+            locations,
+            events: Vec::new(),
+            max_history: 10, // Keep track of the last 10 prices
+        }
+    }
+
+    /// Today's price of `substance` in `location`, or 0 if it isn't traded there.
+    pub fn price(&self, location: &str, substance: &str) -> i32 {
+        self.locations
+            .get(location)
+            .and_then(|local| local.prices.get(substance))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Property prices in `location` as a multiple of its listed price.
+    pub fn property_index(&self, location: &str) -> f32 {
+        self.locations
+            .get(location)
+            .map_or(default_property_index(), |local| local.property_index)
+    }
+
+    /// Previous days' property index in `location`, oldest first.
+    pub fn property_history(&self, location: &str) -> &[f32] {
+        self.locations
+            .get(location)
+            .map_or(&[], |local| &local.property_history)
+    }
+
+    /// Previous days' prices of `substance` in `location`, oldest first.
+    pub fn history(&self, location: &str, substance: &str) -> &[i32] {
+        self.locations
+            .get(location)
+            .and_then(|local| local.price_history.get(substance))
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    /// What trading `units` of `substance` in `location` would come to, buying
+    /// when `units` is positive and selling when negative. Every unit moves the
+    /// price, so the total is charged at the average of the price before and
+    /// after the trade.
+    pub fn trade_total(&self, location: &str, substance: &str, units: i32) -> i32 {
+        let before = self.price(location, substance);
+        let after = self.price_after_trade(location, substance, units);
+        ((before + after) as i64 * units.abs() as i64 / 2) as i32
+    }
+
+    /// The local price once a trade of `units` has gone through.
+    pub fn price_after_trade(&self, location: &str, substance: &str, units: i32) -> i32 {
+        let Some(local) = self.locations.get(location) else {
+            return 0;
+        };
+        let (supply, depth) = local.supply(substance);
+        let new_supply = Self::supply_after_trade(supply, depth, units);
+        let price = local.prices.get(substance).copied().unwrap_or(0);
+        ((price as f32 * supply / new_supply) as i32).max(1)
+    }
+
+    fn supply_after_trade(supply: f32, depth: f32, units: i32) -> f32 {
+        (supply - units as f32 / depth).max(MIN_SUPPLY)
+    }
+
+    /// Moves the local market after the player bought (`units` > 0) or sold
+    /// (`units` < 0). The new price holds for the rest of the day and fades
+    /// over the following days as supply recovers.
+    pub fn record_trade(&mut self, location: &str, substance: &str, units: i32) {
+        let new_price = self.price_after_trade(location, substance, units);
+        let Some(local) = self.locations.get_mut(location) else {
+            return;
+        };
+
+        let old_price = local.prices.get(substance).copied().unwrap_or(0) as f32;
+        let conditions = local
+            .conditions
+            .entry(substance.to_string())
+            .or_insert_with(|| MarketConditions::starting_at(old_price, default_depth()));
+        conditions.supply = Self::supply_after_trade(conditions.supply, conditions.depth, units);
+
+        local.prices.insert(substance.to_string(), new_price);
+    }
+
+    /// Starts an event right away, moving today's affected prices too. Today
+    /// has already gone into the price history, so its entry is moved with
+    /// the price.
+    pub fn start_event(&mut self, event: MarketEvent) {
+        for (location, local) in self.locations.iter_mut() {
+            for (substance, price) in local.prices.iter_mut() {
+                if event.applies_to(location, substance) {
+                    *price = ((*price as f32 * event.magnitude) as i32).max(1);
+                    if let Some(today) = local
+                        .price_history
+                        .get_mut(substance)
+                        .and_then(|history| history.last_mut())
+                    {
+                        *today = *price;
+                    }
+                }
+            }
+        }
+        self.events.push(event);
+    }
+
+    /// Moves the market on by a day. Returns the news that broke today.
+    pub fn generate_prices<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        content: &ContentPack,
+    ) -> Vec<MarketEvent> {
+        // Yesterday's news gets a day older
+        for event in self.events.iter_mut() {
+            event.days_remaining -= 1;
+        }
+        self.events.retain(|event| event.days_remaining > 0);
+
+        let mut started = Vec::new();
+
+        // Chance for global market event
+        let global_kind = match rng.gen_range(0..40) {
+            0 => Some(MarketEventKind::EnforcementCrackdown),
+            1 => Some(MarketEventKind::CartelBust),
+            2 => Some(MarketEventKind::SyntheticFlood),
+            3 => Some(MarketEventKind::Recession),
+            _ => None, // No global effect
+        };
+        if let Some(kind) = global_kind {
+            started.push(MarketEvent::global(kind, rng.gen_range(2..=4)));
+        }
+
+        // Random event: sometimes a substance's price spikes or crashes in one borough
+        for substance in content.substances.iter() {
+            let kind = match rng.gen_range(0..40) {
+                0 => MarketEventKind::ShipmentBust,
+                1 => MarketEventKind::MarketFlooded,
+                2 => MarketEventKind::PremiumProduct,
+                3 => MarketEventKind::Contamination,
+                _ => continue,
+            };
+            let location = &content.locations[rng.gen_range(0..content.locations.len())].name;
+            let text = substance.event_text.get(&kind).cloned();
+            started.push(
+                MarketEvent::local(kind, &substance.name, location, rng.gen_range(1..=3))
+                    .with_text(text),
+            );
+        }
+
+        self.events.extend(started.iter().cloned());
+
+        // Generate prices for each substance in each borough
+        for location in content.location_names() {
+            let local = self
+                .locations
+                .entry(location.to_string())
+                .or_insert_with(|| LocalMarket::new(content));
+
+            for substance in content.substance_names() {
+                let (low, high) = content.price_range(location, substance);
+                let depth = content.market_depth(substance);
+                let fair_price = (low + high) as f32 / 2.0;
+
+                // Yesterday's market drifts back towards normal, plus today's noise
+                let conditions = local
+                    .conditions
+                    .entry(substance.to_string())
+                    .or_insert_with(|| {
+                        MarketConditions::starting_at(rng.gen_range(low..high) as f32, depth)
+                    });
+                conditions.depth = depth;
+                conditions.base_price += PRICE_REVERSION * (fair_price - conditions.base_price)
+                    + fair_price * rng.gen_range(-PRICE_VOLATILITY..PRICE_VOLATILITY);
+                conditions.base_price = conditions.base_price.clamp(low as f32, high as f32);
+                conditions.supply += SUPPLY_REVERSION * (1.0 - conditions.supply)
+                    + rng.gen_range(-SUPPLY_VOLATILITY..SUPPLY_VOLATILITY);
+                conditions.supply = conditions.supply.max(MIN_SUPPLY);
+                conditions.demand += SUPPLY_REVERSION * (1.0 - conditions.demand)
+                    + rng.gen_range(-SUPPLY_VOLATILITY..SUPPLY_VOLATILITY);
+                conditions.demand = conditions.demand.max(MIN_SUPPLY);
+
+                let base_price = conditions.base_price * conditions.demand / conditions.supply;
+
+                let event_modifier: f32 = self
+                    .events
+                    .iter()
+                    .filter(|event| event.applies_to(location, substance))
+                    .map(|event| event.magnitude)
+                    .product();
+
+                // Apply the news
+                let final_price = (base_price * event_modifier) as i32;
+
+                // Ensure price is always at least 1
+                let final_price = std::cmp::max(final_price, 1);
+
+                local.prices.insert(substance.to_string(), final_price);
+
+                // Update price history
+                let history = local
+                    .price_history
+                    .entry(substance.to_string())
+                    .or_default();
+                history.push(final_price);
+                // Keep only the most recent prices based on max_history
+                if history.len() > self.max_history {
+                    history.remove(0);
+                }
+            }
+
+            // Property drifts slowly back towards the listed price
+            local.property_history.push(local.property_index);
+            if local.property_history.len() > self.max_history {
+                local.property_history.remove(0);
+            }
+            local.property_index += PROPERTY_REVERSION * (1.0 - local.property_index)
+                + rng.gen_range(-PROPERTY_VOLATILITY..PROPERTY_VOLATILITY);
+            local.property_index = local
+                .property_index
+                .clamp(MIN_PROPERTY_INDEX, MAX_PROPERTY_INDEX);
+        }
+
+        started
+    }
+}
+
+impl Default for Market {
+    fn default() -> Self {
+        Self::new(ContentPack::builtin())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    /// A market that has been through `days` days of trading.
+    fn market_after(days: usize) -> (Market, ChaCha8Rng) {
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let mut market = Market::default();
+        for _ in 0..days {
+            market.generate_prices(&mut rng, ContentPack::builtin());
+        }
+        (market, rng)
+    }
+
+    #[test]
+    fn news_that_breaks_today_shows_in_todays_history() {
+        let (mut market, _) = market_after(3);
+        let weed = market.price("Bronx", "Weed");
+        let heroin = market.price("Bronx", "Heroin");
+
+        market.start_event(MarketEvent::local(
+            MarketEventKind::ShipmentBust,
+            "Weed",
+            "Bronx",
+            2,
+        ));
+
+        assert_eq!(market.price("Bronx", "Weed"), weed * 5);
+        assert_eq!(market.history("Bronx", "Weed").len(), 3);
+        assert_eq!(market.history("Bronx", "Weed").last(), Some(&(weed * 5)));
+        // Nothing else moved
+        assert_eq!(market.price("Bronx", "Heroin"), heroin);
+        assert_eq!(market.history("Bronx", "Heroin").last(), Some(&heroin));
+    }
+
+    #[test]
+    fn trades_are_charged_at_the_average_of_the_price_before_and_after() {
+        let (market, _) = market_after(1);
+        let price = market.price("Bronx", "Weed");
+
+        let bought = market.price_after_trade("Bronx", "Weed", 50);
+        assert!(bought > price);
+        assert_eq!(
+            market.trade_total("Bronx", "Weed", 50),
+            (price + bought) * 50 / 2
+        );
+
+        let sold = market.price_after_trade("Bronx", "Weed", -50);
+        assert!(sold < price);
+        assert_eq!(
+            market.trade_total("Bronx", "Weed", -50),
+            (price + sold) * 50 / 2
+        );
+    }
+
+    #[test]
+    fn buying_up_the_supply_only_goes_so_far() {
+        let (market, _) = market_after(1);
+        let price = market.price("Bronx", "Weed");
+        let supply = market.locations["Bronx"].conditions["Weed"].supply;
+
+        assert_eq!(
+            market.price_after_trade("Bronx", "Weed", 1_000_000),
+            (price as f32 * supply / MIN_SUPPLY) as i32
+        );
+    }
+
+    #[test]
+    fn a_dumped_market_recovers_over_the_following_days() {
+        let (mut market, mut rng) = market_after(1);
+        let price = market.price("Bronx", "Heroin");
+
+        market.record_trade("Bronx", "Heroin", -300);
+
+        let dumped = market.price("Bronx", "Heroin");
+        assert!(dumped < price / 2);
+        // Every other borough is untouched
+        assert_eq!(
+            market.locations["Brooklyn"].conditions["Heroin"].supply,
+            market_after(1).0.locations["Brooklyn"].conditions["Heroin"].supply
+        );
+
+        let glut = market.locations["Bronx"].conditions["Heroin"].supply - 1.0;
+        market.generate_prices(&mut rng, ContentPack::builtin());
+        let next_day = market.locations["Bronx"].conditions["Heroin"].supply - 1.0;
+        // About a third of the glut clears overnight, give or take the day's noise
+        assert!((next_day - glut * (1.0 - SUPPLY_REVERSION)).abs() <= SUPPLY_VOLATILITY);
+
+        for _ in 0..10 {
+            market.generate_prices(&mut rng, ContentPack::builtin());
+        }
+        let conditions = &market.locations["Bronx"].conditions["Heroin"];
+        assert!((conditions.supply - 1.0).abs() < 0.35);
+        let (low, high) = ContentPack::builtin().price_range("Bronx", "Heroin");
+        assert!((low as f32..=high as f32).contains(&conditions.base_price));
+    }
+}
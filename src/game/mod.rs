@@ -0,0 +1,50 @@
+//! Game rules, independent of any frontend.
+//!
+//! `GameEngine` owns the `Player`, the `Market` and the `GameState` and
+//! exposes every action a frontend can take. The egui app in `main.rs` only
+//! renders the engine's state and dispatches button clicks to it.
+//!
+//! The city, the drugs and the weapons come from a `ContentPack`; how long
+//! the game lasts and what the player starts with come from `GameRules`.
+//!
+//! Every action is also an `Action` value that `GameEngine::apply` carries
+//! out, which is how `bot` strategies play without a frontend. The engine
+//! records every action it takes, so a game can be played back from a
+//! `replay`.
+
+mod action;
+pub mod bot;
+pub mod content;
+mod engine;
+mod events;
+mod justice;
+mod loan;
+mod market;
+mod player;
+mod police;
+pub mod replay;
+mod rules;
+pub mod save;
+pub mod scores;
+mod stash;
+mod valuation;
+
+pub use action::Action;
+pub use content::{ContentPack, Service};
+pub use engine::{describe_problems, GameEngine, GameError, GameState, Trade, TravelOutcome};
+pub use events::{MarketEvent, MarketEventKind};
+pub use justice::{CourtVerdict, CriminalRecord, Custody};
+pub use loan::{
+    LoanAccount, BARRED_AFTER_DAYS, ENFORCERS_AFTER_DAYS, MINIMUM_PAYMENT_PERCENT, TERM_DAYS,
+};
+pub use market::{LocalMarket, Market, MarketConditions};
+pub use player::{Front, Player, StashHouse};
+pub use police::{BribeNegotiation, PoliceEncounter, PoliceState};
+pub use rules::{Difficulty, GameRules};
+pub use stash::{
+    StashUpgrade, BROKER_CUT_PERCENT, EXTRA_SPACE_PERCENT, GUARD_WAGES_PERCENT,
+    MAX_EXTRA_SPACE_TIERS,
+};
+pub use valuation::NetWorth;
+
+pub const MAX_HEAT: i32 = 100;
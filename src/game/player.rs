@@ -0,0 +1,242 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::{ContentPack, CriminalRecord, GameRules, LoanAccount, StashUpgrade};
+
+// Define a struct for stash houses
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StashHouse {
+    pub location: String,
+    pub inventory: HashMap<String, i32>,
+    pub capacity: i32,
+    /// Everything bought for the house, in the order it was bought. Extra
+    /// space can appear more than once.
+    #[serde(default)]
+    pub upgrades: Vec<StashUpgrade>,
+}
+
+impl StashHouse {
+    pub fn new(location: String, capacity: i32) -> Self {
+        StashHouse {
+            location,
+            inventory: HashMap::new(),
+            capacity,
+            upgrades: Vec::new(),
+        }
+    }
+
+    /// How many times `upgrade` has been bought for the house.
+    pub fn upgrade_count(&self, upgrade: StashUpgrade) -> i32 {
+        self.upgrades.iter().filter(|&&u| u == upgrade).count() as i32
+    }
+
+    pub fn has_upgrade(&self, upgrade: StashUpgrade) -> bool {
+        self.upgrades.contains(&upgrade)
+    }
+
+    /// The upgrades the house has, as one line.
+    pub fn describe_upgrades(&self) -> String {
+        StashUpgrade::ALL
+            .into_iter()
+            .filter(|&upgrade| self.has_upgrade(upgrade))
+            .map(|upgrade| match upgrade {
+                StashUpgrade::ExtraSpace => {
+                    format!("{} x{}", upgrade, self.upgrade_count(upgrade))
+                }
+                _ => upgrade.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Chance in percent that a raid on the house comes to nothing.
+    pub fn raid_protection(&self) -> i32 {
+        self.upgrades.iter().map(|u| u.raid_protection()).sum()
+    }
+
+    /// Percentage points knocked off how much of the stock a raid takes.
+    pub fn confiscation_reduction(&self) -> i32 {
+        self.upgrades
+            .iter()
+            .map(|u| u.confiscation_reduction())
+            .sum()
+    }
+
+    /// Percentage by which the chance of the house being seized is cut.
+    pub fn seizure_reduction(&self) -> i32 {
+        self.upgrades
+            .iter()
+            .map(|u| u.seizure_reduction())
+            .sum::<i32>()
+            .min(100)
+    }
+
+    pub fn total_items(&self) -> i32 {
+        self.inventory.values().sum()
+    }
+
+    pub fn space_available(&self) -> i32 {
+        self.capacity - self.total_items()
+    }
+}
+
+/// A business bought to pass dirty money off as its takings.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Front {
+    pub location: String,
+    /// Dirty money put through it so far today.
+    pub laundered_today: i32,
+    /// Dirty money put through it over the whole game.
+    pub total_laundered: i32,
+}
+
+impl Front {
+    pub fn new(location: String) -> Self {
+        Front {
+            location,
+            laundered_today: 0,
+            total_laundered: 0,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Player {
+    pub cash: i32,
+    /// The part of `cash` that came from dealing and can't be explained.
+    #[serde(default)]
+    pub dirty_cash: i32,
+    pub debt: i32,
+    pub inventory: HashMap<String, i32>,
+    pub current_location: String,
+    pub day: i32,
+    pub health: i32,
+    pub weapons: HashMap<String, i32>,
+    pub active_weapon: Option<String>,
+    pub stash_houses: HashMap<String, StashHouse>,
+    /// How much attention the police are paying you, from 0 to `MAX_HEAT`.
+    #[serde(default)]
+    pub heat: i32,
+    #[serde(default)]
+    pub record: CriminalRecord,
+    /// How many units fit in the player's pockets.
+    #[serde(default = "default_carrying_capacity")]
+    pub carrying_capacity: i32,
+    /// Savings in the bank, out of reach of muggers and the police.
+    #[serde(default)]
+    pub bank_balance: i32,
+    /// Taken out of the bank so far today.
+    #[serde(default)]
+    pub withdrawn_today: i32,
+    /// When the loan shark wants paying and how well he's been paid.
+    #[serde(default)]
+    pub loan: LoanAccount,
+    #[serde(default)]
+    pub fronts: HashMap<String, Front>,
+}
+
+fn default_carrying_capacity() -> i32 {
+    GameRules::normal().carrying_capacity
+}
+
+impl Player {
+    pub fn new(content: &ContentPack, rules: &GameRules) -> Self {
+        let mut inventory = HashMap::new();
+        for substance in content.substance_names() {
+            inventory.insert(substance.to_string(), 0);
+        }
+
+        let mut weapons = HashMap::new();
+        for weapon in content.weapon_names() {
+            weapons.insert(weapon.to_string(), 0);
+        }
+
+        let mut loan = LoanAccount::default();
+        loan.start_term(1, rules.starting_debt);
+
+        Player {
+            cash: rules.starting_cash,
+            dirty_cash: 0,
+            debt: rules.starting_debt,
+            inventory,
+            current_location: content.starting_location().to_string(),
+            day: 1,
+            health: 100,
+            weapons,
+            active_weapon: None,
+            stash_houses: HashMap::new(),
+            heat: 0,
+            record: CriminalRecord::default(),
+            carrying_capacity: rules.carrying_capacity,
+            bank_balance: 0,
+            withdrawn_today: 0,
+            loan,
+            fronts: HashMap::new(),
+        }
+    }
+
+    /// Cash that would stand up to a bank or a tax inspector.
+    pub fn clean_cash(&self) -> i32 {
+        self.cash - self.dirty_cash
+    }
+
+    /// Pays `amount` out of cash, clean money first, dipping into dirty money
+    /// only once the clean runs out.
+    ///
+    /// This is how legitimate businesses get paid: the hospital and whoever
+    /// sells a front keep books and won't knowingly take drug money.
+    /// Everyone else gets `spend_dirty`.
+    pub fn spend(&mut self, amount: i32) {
+        let from_dirty = (amount - self.clean_cash().max(0)).clamp(0, self.dirty_cash);
+        self.cash -= amount;
+        self.dirty_cash -= from_dirty;
+    }
+
+    /// Pays `amount` out of cash, dirty money first.
+    ///
+    /// This is how the street gets paid: dealers, gun sellers, the loan
+    /// shark, stash brokers, the crews who fit out and guard stash houses,
+    /// and muggers, none of whom ask where the money came from. The police
+    /// and the courts are paid this way too: bail, fines and bribes take
+    /// the proceeds of crime before anything clean is touched.
+    pub fn spend_dirty(&mut self, amount: i32) {
+        self.cash -= amount;
+        self.dirty_cash = (self.dirty_cash - amount).max(0);
+    }
+
+    pub fn total_items(&self) -> i32 {
+        self.inventory.values().sum()
+    }
+
+    pub fn space_available(&self) -> i32 {
+        self.carrying_capacity - self.total_items()
+    }
+
+    pub fn has_weapon(&self) -> bool {
+        self.weapons.values().sum::<i32>() > 0
+    }
+
+    /// How the street would describe your heat.
+    pub fn heat_description(&self) -> &'static str {
+        match self.heat {
+            0..=19 => "Cool",
+            20..=44 => "Noticed",
+            45..=74 => "Wanted",
+            _ => "Most Wanted",
+        }
+    }
+
+    pub fn owns_stash_house_in_current_location(&self) -> bool {
+        self.stash_houses.contains_key(&self.current_location)
+    }
+
+    pub fn owns_front_in_current_location(&self) -> bool {
+        self.fronts.contains_key(&self.current_location)
+    }
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Self::new(ContentPack::builtin(), &GameRules::default())
+    }
+}
@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+
+/// Where a police stop has got to.
+///
+/// Every stop starts in `Stopped`. Complying leads to `Searching`, fighting to
+/// `Fighting`, running to `Fleeing` and offering money to `Negotiating`; each of
+/// those ends in `Arrested` or `Escaped`, after which the interrupted trip
+/// goes ahead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PoliceState {
+    /// Pulled over; the player hasn't reacted yet.
+    Stopped,
+    /// The officer is going through the player's pockets.
+    Searching,
+    Fighting,
+    Fleeing,
+    /// Talking money with the officer.
+    Negotiating,
+    Arrested,
+    Escaped,
+}
+
+impl PoliceState {
+    /// Whether the encounter has been settled one way or the other.
+    pub fn is_over(self) -> bool {
+        matches!(self, PoliceState::Arrested | PoliceState::Escaped)
+    }
+}
+
+/// A police stop in progress.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoliceEncounter {
+    pub state: PoliceState,
+    /// Where the player was headed; the trip finishes once the stop is over.
+    pub destination: String,
+    /// The haggling, once the player has brought up a bribe.
+    #[serde(default)]
+    pub negotiation: Option<BribeNegotiation>,
+}
+
+impl PoliceEncounter {
+    pub fn new(destination: &str) -> Self {
+        PoliceEncounter {
+            state: PoliceState::Stopped,
+            destination: destination.to_string(),
+            negotiation: None,
+        }
+    }
+}
+
+/// Haggling over a bribe. The officer opens with a price and comes down a
+/// little after each counter-offer, until they either take the money or run
+/// out of patience.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BribeNegotiation {
+    /// What the officer wants right now.
+    pub asking_price: i32,
+    /// Counter-offers the officer will still sit through.
+    pub patience: i32,
+    /// How little the officer gives up on a counter-offer, from 0.0 (takes
+    /// whatever you offer) to 1.0 (won't budge).
+    pub greed: f32,
+    /// The player's offers so far, oldest first.
+    pub offers: Vec<i32>,
+}
+
+impl BribeNegotiation {
+    /// The officer's next price after hearing `offer`. Equal to `offer` when
+    /// the gap is too small to argue about.
+    pub fn counter(&self, offer: i32) -> i32 {
+        if offer >= self.asking_price {
+            return offer;
+        }
+        let gap = (self.asking_price - offer) as f32;
+        (offer + round_up_to_ten((gap * self.greed) as i32)).min(self.asking_price)
+    }
+}
+
+pub(crate) fn round_up_to_ten(amount: i32) -> i32 {
+    (amount + 9) / 10 * 10
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn negotiation(greed: f32) -> BribeNegotiation {
+        BribeNegotiation {
+            asking_price: 1000,
+            patience: 3,
+            greed,
+            offers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn the_officer_meets_you_part_of_the_way() {
+        // Half the gap, rounded up to the next ten
+        assert_eq!(negotiation(0.5).counter(600), 800);
+        assert_eq!(negotiation(0.5).counter(595), 805);
+        assert_eq!(negotiation(0.25).counter(600), 700);
+    }
+
+    #[test]
+    fn the_counter_offer_stays_between_the_offer_and_the_asking_price() {
+        // A greedy officer doesn't move at all
+        assert_eq!(negotiation(1.0).counter(600), 1000);
+        // One that will take anything takes the offer
+        assert_eq!(negotiation(0.0).counter(600), 600);
+        // Rounding never pushes the price past what was asked
+        assert_eq!(negotiation(0.5).counter(995), 1000);
+        // Meeting or beating the price settles it
+        assert_eq!(negotiation(0.5).counter(1000), 1000);
+        assert_eq!(negotiation(0.5).counter(1200), 1200);
+    }
+}
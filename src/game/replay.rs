@@ -0,0 +1,181 @@
+//! Game replays.
+//!
+//! The same seed, content pack, rules and actions always play out the same
+//! game, so that is all a replay stores. Games are written to `REPLAY_DIR`
+//! in the data dir when they end, and frontends also `record` a game the
+//! player quits or leaves for another; `Replay::frames` plays one back into
+//! the state of the game after every action.
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::save::{self, SaveError};
+use super::{Action, ContentPack, GameEngine, GameError, GameRules};
+
+pub const REPLAY_VERSION: u32 = 1;
+
+/// Folder in the data dir that games are recorded to.
+pub const REPLAY_DIR: &str = "replays";
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    pub content: ContentPack,
+    pub rules: GameRules,
+    pub actions: Vec<Action>,
+}
+
+/// A recorded action that the engine refused on playback, which means the
+/// replay was recorded under different game rules than this build's.
+#[derive(Debug)]
+pub struct Diverged {
+    /// Index of the refused action.
+    pub step: usize,
+    pub error: GameError,
+}
+
+impl fmt::Display for Diverged {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the replay stops matching the game at step {}: {}",
+            self.step + 1,
+            self.error
+        )
+    }
+}
+
+impl std::error::Error for Diverged {}
+
+impl Replay {
+    /// The replay of `game` so far, or `None` if it was loaded from a save
+    /// made before games were recorded.
+    pub fn from_game(game: &GameEngine) -> Option<Self> {
+        Some(Replay {
+            version: REPLAY_VERSION,
+            seed: game.seed(),
+            content: game.content().clone(),
+            rules: game.rules().clone(),
+            actions: game.actions()?.to_vec(),
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, SaveError> {
+        let (value, _) = save::read_versioned(path, REPLAY_VERSION)?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SaveError> {
+        let json = serde_json::to_string_pretty(self)?;
+        save::write_atomic(path, json.as_bytes())
+    }
+
+    /// Plays the game back: its state before the first action, then after
+    /// each one.
+    pub fn frames(&self) -> Result<Vec<GameEngine>, Diverged> {
+        let mut game = GameEngine::with_rules(self.seed, self.content.clone(), self.rules.clone());
+        let mut frames = vec![game.clone()];
+
+        for (step, action) in self.actions.iter().enumerate() {
+            game.apply(action)
+                .map_err(|error| Diverged { step, error })?;
+            game.update_state();
+            frames.push(game.clone());
+        }
+
+        Ok(frames)
+    }
+}
+
+/// Writes `game`'s replay to a new file in `REPLAY_DIR` and returns where it
+/// went. Returns `None` when there is nothing to keep: no action has been
+/// taken yet, or the game came from a save made before recording began.
+pub fn record(game: &GameEngine) -> Option<Result<PathBuf, SaveError>> {
+    let replay = Replay::from_game(game).filter(|replay| !replay.actions.is_empty())?;
+    Some(replay_path(game).and_then(|path| replay.save(&path).map(|()| path)))
+}
+
+/// Where a game's replay is recorded: named for when it was recorded and
+/// the seed it was played from.
+pub fn replay_path(game: &GameEngine) -> Result<PathBuf, SaveError> {
+    let name = format!(
+        "{}-{}.json",
+        Local::now().format("%Y-%m-%d-%H%M%S"),
+        game.seed()
+    );
+    Ok(save::data_dir()?.join(REPLAY_DIR).join(name))
+}
+
+/// Every recorded replay, newest first.
+pub fn list_replays() -> Result<Vec<PathBuf>, SaveError> {
+    let dir = save::data_dir()?.join(REPLAY_DIR);
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut paths = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            paths.push(path);
+        }
+    }
+    // The names start with the date, so this sorts by age
+    paths.sort();
+    paths.reverse();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::bot::{self, Trader};
+
+    #[test]
+    fn frames_end_where_the_recorded_game_did() {
+        let recorded = bot::play(GameEngine::with_seed(7), &mut Trader::default()).engine;
+        let replay = Replay::from_game(&recorded).unwrap();
+
+        let frames = replay.frames().unwrap();
+        let last = frames.last().unwrap();
+
+        assert_eq!(frames.len(), replay.actions.len() + 1);
+        assert!(!last.is_running());
+        assert_eq!(
+            serde_json::to_value(&last.player).unwrap(),
+            serde_json::to_value(&recorded.player).unwrap()
+        );
+        assert_eq!(last.net_worth(), recorded.net_worth());
+    }
+
+    #[test]
+    fn a_replay_survives_saving_and_loading() {
+        let mut game = GameEngine::with_seed(7);
+        game.buy("Weed", 2).unwrap();
+        game.travel("Brooklyn").unwrap();
+        let replay = Replay::from_game(&game).unwrap();
+
+        let path = std::env::temp_dir().join(format!(
+            "dopewars-gui-test-{}-replay.json",
+            std::process::id()
+        ));
+        replay.save(&path).unwrap();
+        let loaded = Replay::load(&path);
+        let _ = fs::remove_file(&path);
+        let loaded = loaded.unwrap();
+
+        assert_eq!(loaded.seed, 7);
+        assert_eq!(loaded.actions, replay.actions);
+    }
+
+    #[test]
+    fn a_game_with_nothing_done_leaves_no_replay() {
+        assert!(record(&GameEngine::with_seed(7)).is_none());
+    }
+}
@@ -0,0 +1,240 @@
+//! The numbers a game is played by: how long it lasts, what you start with
+//! and what things cost. Scores are only comparable between games played
+//! under the same rules.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A named set of rules, or `Custom` once any number has been changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Custom,
+}
+
+impl Difficulty {
+    pub const PRESETS: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Custom => "Custom",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameRules {
+    pub difficulty: Difficulty,
+    pub max_days: i32,
+    pub starting_cash: i32,
+    pub starting_debt: i32,
+    pub carrying_capacity: i32,
+    /// Added to the loan shark debt every day, as a fraction of it.
+    pub loan_shark_interest: f32,
+    pub stash_house_capacity: i32,
+    pub hospital_cost_per_point: i32,
+    /// Sell off everything at a discount when the game ends, so only cash
+    /// counts towards the score.
+    #[serde(default)]
+    pub fire_sale: bool,
+    /// Paid on bank savings every day, as a fraction of them.
+    #[serde(default = "default_bank_interest")]
+    pub bank_interest: f32,
+    /// Most that can be taken out of the bank in one day; `None` for no limit.
+    #[serde(default)]
+    pub bank_withdrawal_limit: Option<i32>,
+    /// Taken by a front for laundering dirty money, as a fraction of it.
+    #[serde(default = "default_laundering_fee")]
+    pub laundering_fee: f32,
+    /// Most dirty money one front can launder in a day.
+    #[serde(default = "default_laundering_limit")]
+    pub laundering_limit: i32,
+    /// How much of its face value dirty cash loses in the net worth, as a
+    /// fraction: at 1.0 only clean money counts towards the score.
+    #[serde(default = "default_dirty_cash_penalty")]
+    pub dirty_cash_penalty: f32,
+}
+
+fn default_bank_interest() -> f32 {
+    GameRules::normal().bank_interest
+}
+
+fn default_laundering_fee() -> f32 {
+    GameRules::normal().laundering_fee
+}
+
+fn default_laundering_limit() -> i32 {
+    GameRules::normal().laundering_limit
+}
+
+fn default_dirty_cash_penalty() -> f32 {
+    GameRules::normal().dirty_cash_penalty
+}
+
+impl GameRules {
+    pub fn easy() -> Self {
+        GameRules {
+            difficulty: Difficulty::Easy,
+            max_days: 45,
+            starting_cash: 5000,
+            starting_debt: 2500,
+            carrying_capacity: 150,
+            loan_shark_interest: 0.05,
+            stash_house_capacity: 300,
+            hospital_cost_per_point: 30,
+            fire_sale: false,
+            bank_interest: 0.02,
+            bank_withdrawal_limit: None,
+            laundering_fee: 0.1,
+            laundering_limit: 10000,
+            dirty_cash_penalty: 0.25,
+        }
+    }
+
+    /// The rules the game has always been played by.
+    pub fn normal() -> Self {
+        GameRules {
+            difficulty: Difficulty::Normal,
+            max_days: 30,
+            starting_cash: 2000,
+            starting_debt: 5000,
+            carrying_capacity: 100,
+            loan_shark_interest: 0.1,
+            stash_house_capacity: 200,
+            hospital_cost_per_point: 50,
+            fire_sale: false,
+            bank_interest: 0.01,
+            bank_withdrawal_limit: None,
+            laundering_fee: 0.15,
+            laundering_limit: 5000,
+            dirty_cash_penalty: 0.5,
+        }
+    }
+
+    pub fn hard() -> Self {
+        GameRules {
+            difficulty: Difficulty::Hard,
+            max_days: 20,
+            starting_cash: 1000,
+            starting_debt: 8000,
+            carrying_capacity: 75,
+            loan_shark_interest: 0.15,
+            stash_house_capacity: 150,
+            hospital_cost_per_point: 80,
+            fire_sale: true,
+            bank_interest: 0.005,
+            bank_withdrawal_limit: Some(2500),
+            laundering_fee: 0.25,
+            laundering_limit: 2500,
+            dirty_cash_penalty: 1.0,
+        }
+    }
+
+    /// The rules for a preset. `Custom` starts from `Normal`.
+    pub fn preset(difficulty: Difficulty) -> Self {
+        match difficulty {
+            Difficulty::Easy => Self::easy(),
+            Difficulty::Normal => Self::normal(),
+            Difficulty::Hard => Self::hard(),
+            Difficulty::Custom => GameRules {
+                difficulty: Difficulty::Custom,
+                ..Self::normal()
+            },
+        }
+    }
+
+    /// Checks every number, reporting every problem at once.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut problems = Vec::new();
+
+        if self.max_days < 1 {
+            problems.push("the game must last at least one day".to_string());
+        }
+        if self.starting_cash < 0 {
+            problems.push("starting cash can't be negative".to_string());
+        }
+        if self.starting_debt < 0 {
+            problems.push("starting debt can't be negative".to_string());
+        }
+        if self.carrying_capacity < 1 {
+            problems.push("carrying capacity must be at least 1".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.loan_shark_interest) {
+            problems.push("loan shark interest must be between 0% and 100%".to_string());
+        }
+        if self.stash_house_capacity < 1 {
+            problems.push("stash house capacity must be at least 1".to_string());
+        }
+        if self.hospital_cost_per_point < 0 {
+            problems.push("hospital costs can't be negative".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.bank_interest) {
+            problems.push("bank interest must be between 0% and 100%".to_string());
+        }
+        if self.bank_withdrawal_limit.is_some_and(|limit| limit < 1) {
+            problems.push("the bank withdrawal limit must be at least $1".to_string());
+        }
+        if !(0.0..1.0).contains(&self.laundering_fee) {
+            problems.push("the laundering fee must be at least 0% and below 100%".to_string());
+        }
+        if self.laundering_limit < 1 {
+            problems.push("fronts must be able to launder at least $1 a day".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.dirty_cash_penalty) {
+            problems.push("the dirty cash penalty must be between 0% and 100%".to_string());
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+
+    /// One line with every number, for showing next to a score.
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{}: {} days, ${} cash, ${} debt at {}% a day, carry {}, stash {}, hospital ${}/point",
+            self.difficulty,
+            self.max_days,
+            self.starting_cash,
+            self.starting_debt,
+            (self.loan_shark_interest * 100.0).round(),
+            self.carrying_capacity,
+            self.stash_house_capacity,
+            self.hospital_cost_per_point
+        );
+        summary.push_str(&format!(
+            ", bank {}% a day",
+            (self.bank_interest * 1000.0).round() / 10.0
+        ));
+        if let Some(limit) = self.bank_withdrawal_limit {
+            summary.push_str(&format!(", withdraw up to ${} a day", limit));
+        }
+        summary.push_str(&format!(
+            ", launder ${} a day for {}%, dirty cash counts {}%",
+            self.laundering_limit,
+            (self.laundering_fee * 100.0).round(),
+            ((1.0 - self.dirty_cash_penalty) * 100.0).round()
+        ));
+        if self.fire_sale {
+            summary.push_str(", fire sale at the end");
+        }
+        summary
+    }
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self::normal()
+    }
+}
@@ -0,0 +1,264 @@
+//! Versioned save files.
+//!
+//! A save is a JSON object `{ "version": N, "game": <GameEngine>, "frontend": <F> }`
+//! where `F` is whatever the frontend needs to put the player back where they
+//! were (the GUI stores its current screen). A frontend that can't make sense
+//! of another frontend's data starts from its own defaults instead, so every
+//! frontend can load every save.
+//!
+//! When the shape of the game state changes, bump `SAVE_VERSION` and teach
+//! `migrate` how to rewrite the previous version; new fields marked
+//! `#[serde(default)]` need no migration step.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::{ContentPack, GameEngine};
+
+pub const SAVE_VERSION: u32 = 3;
+
+pub const MANUAL_SAVE_FILE: &str = "savegame.json";
+pub const AUTOSAVE_FILE: &str = "autosave.json";
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Format(serde_json::Error),
+    MissingVersion,
+    UnsupportedVersion(u32),
+    NoDataDir,
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "could not access the save file: {}", e),
+            SaveError::Format(e) => write!(f, "the save file is corrupt: {}", e),
+            SaveError::MissingVersion => write!(f, "the save file has no version number"),
+            SaveError::UnsupportedVersion(v) => write!(
+                f,
+                "the save file is version {}, but this build only understands up to version {}",
+                v, SAVE_VERSION
+            ),
+            SaveError::NoDataDir => write!(f, "could not find a data directory for save files"),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<io::Error> for SaveError {
+    fn from(e: io::Error) -> Self {
+        SaveError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(e: serde_json::Error) -> Self {
+        SaveError::Format(e)
+    }
+}
+
+#[derive(Serialize)]
+struct SaveFileRef<'a, F> {
+    version: u32,
+    game: &'a GameEngine,
+    frontend: &'a F,
+}
+
+#[derive(Deserialize)]
+struct SaveFile {
+    game: GameEngine,
+    #[serde(default)]
+    frontend: Value,
+}
+
+/// Directory that holds this game's files in the user's data dir.
+pub fn data_dir() -> Result<PathBuf, SaveError> {
+    dirs::data_dir()
+        .map(|dir| dir.join("dopewars-gui"))
+        .ok_or(SaveError::NoDataDir)
+}
+
+/// Full path of a save slot such as `MANUAL_SAVE_FILE`.
+pub fn save_path(file_name: &str) -> Result<PathBuf, SaveError> {
+    Ok(data_dir()?.join(file_name))
+}
+
+pub fn save_game<F: Serialize>(
+    path: &Path,
+    game: &GameEngine,
+    frontend: &F,
+) -> Result<(), SaveError> {
+    let file = SaveFileRef {
+        version: SAVE_VERSION,
+        game,
+        frontend,
+    };
+    let json = serde_json::to_string_pretty(&file)?;
+    write_atomic(path, json.as_bytes())
+}
+
+/// Writes `bytes` to `path`, creating its directory if needed. The data goes
+/// next to the target first and is renamed over it, so a crash never leaves
+/// half a file behind.
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), SaveError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, bytes)?;
+    fs::rename(&tmp_path, path)?;
+
+    Ok(())
+}
+
+pub fn load_game<F: DeserializeOwned + Default>(path: &Path) -> Result<(GameEngine, F), SaveError> {
+    let (value, version) = read_versioned(path, SAVE_VERSION)?;
+    let file: SaveFile = serde_json::from_value(migrate(value, version))?;
+    let frontend = F::deserialize(file.frontend).unwrap_or_default();
+    Ok((file.game, frontend))
+}
+
+/// Reads a JSON file with a top-level `version` and returns it with that
+/// version, refusing files from builds newer than `newest`.
+pub(crate) fn read_versioned(path: &Path, newest: u32) -> Result<(Value, u32), SaveError> {
+    let text = fs::read_to_string(path)?;
+    let value: Value = serde_json::from_str(&text)?;
+
+    let version = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or(SaveError::MissingVersion)? as u32;
+    if version > newest {
+        return Err(SaveError::UnsupportedVersion(version));
+    }
+
+    Ok((value, version))
+}
+
+/// Upgrades a save written by an older build to the current layout, one
+/// version at a time.
+fn migrate(mut value: Value, version: u32) -> Value {
+    if version < 2 {
+        value = v1_to_v2(value);
+    }
+    if version < 3 {
+        value = v2_to_v3(value);
+    }
+    value
+}
+
+/// Version 2 replaced the city-wide price table with one per borough. Old
+/// saves get their prices and history copied into every borough.
+fn v1_to_v2(mut value: Value) -> Value {
+    if let Some(market) = value
+        .pointer_mut("/game/market")
+        .and_then(Value::as_object_mut)
+    {
+        let prices = market.remove("prices").unwrap_or_else(|| json!({}));
+        let price_history = market.remove("price_history").unwrap_or_else(|| json!({}));
+
+        // Version 1 predates content packs, so it was always the built-in city
+        let locations: Map<String, Value> = ContentPack::builtin()
+            .location_names()
+            .map(|location| {
+                (
+                    location.to_string(),
+                    json!({ "prices": prices, "price_history": price_history }),
+                )
+            })
+            .collect();
+        market.insert("locations".to_string(), Value::Object(locations));
+    }
+    value
+}
+
+/// Version 3 turned the day's news from plain strings into typed events that
+/// last several days. Old headlines can't be turned back into price effects,
+/// so they are dropped.
+fn v2_to_v3(mut value: Value) -> Value {
+    if let Some(market) = value
+        .pointer_mut("/game/market")
+        .and_then(Value::as_object_mut)
+    {
+        market.insert("events".to_string(), json!([]));
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Loads `json` through a real file, the way a frontend would.
+    fn load_fixture(name: &str, json: &str) -> (GameEngine, String) {
+        let path = std::env::temp_dir().join(format!(
+            "dopewars-gui-test-{}-{}.json",
+            std::process::id(),
+            name
+        ));
+        fs::write(&path, json).unwrap();
+        let loaded = load_game(&path);
+        let _ = fs::remove_file(&path);
+        loaded.unwrap()
+    }
+
+    #[test]
+    fn version_1_saves_get_a_price_table_per_borough() {
+        let (mut game, frontend) = load_fixture("v1", include_str!("testdata/save_v1.json"));
+
+        assert_eq!(frontend, "Main");
+        assert_eq!(game.player.cash, 1985);
+        assert_eq!(game.player.inventory["Weed"], 3);
+        assert_eq!(game.player.current_location, "Brooklyn");
+        assert_eq!(game.player.day, 2);
+
+        // The one city-wide table is copied into every borough
+        for location in ContentPack::builtin().location_names() {
+            assert_eq!(game.market.price(location, "Weed"), 33);
+            assert_eq!(game.market.price(location, "Heroin"), 1061);
+            assert_eq!(game.market.history(location, "Heroin").last(), Some(&1061));
+        }
+        // Old headlines can't be turned into events
+        assert!(game.market.events.is_empty());
+
+        assert!(game.buy("Weed", 1).is_ok());
+    }
+
+    #[test]
+    fn version_2_saves_keep_their_borough_prices() {
+        let (mut game, frontend) = load_fixture("v2", include_str!("testdata/save_v2.json"));
+
+        assert_eq!(frontend, "Main");
+        assert_eq!(game.player.cash, 1889);
+        assert_eq!(game.player.inventory["Weed"], 3);
+        assert_eq!(game.market.price("Central Park", "Speed"), 617);
+        assert_eq!(game.market.history("Central Park", "Speed"), &[189, 617]);
+        assert!(game.market.events.is_empty());
+
+        assert!(game.buy("Weed", 1).is_ok());
+    }
+
+    #[test]
+    fn saves_from_newer_builds_are_refused() {
+        let path = std::env::temp_dir().join(format!(
+            "dopewars-gui-test-{}-future.json",
+            std::process::id()
+        ));
+        fs::write(&path, json!({ "version": SAVE_VERSION + 1 }).to_string()).unwrap();
+        let loaded = load_game::<String>(&path);
+        let _ = fs::remove_file(&path);
+
+        assert!(matches!(
+            loaded,
+            Err(SaveError::UnsupportedVersion(v)) if v == SAVE_VERSION + 1
+        ));
+    }
+}
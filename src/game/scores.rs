@@ -0,0 +1,150 @@
+//! The local high-score table.
+//!
+//! Scores live in `HIGH_SCORES_FILE` next to the save files. Each entry keeps
+//! the rules it was played under, since a score only means something next to
+//! others from the same rules.
+
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::save::{self, SaveError};
+use super::{GameEngine, GameRules};
+
+pub const HIGH_SCORES_FILE: &str = "highscores.json";
+
+/// How many entries the table keeps; anything below drops off.
+pub const MAX_HIGH_SCORES: usize = 50;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HighScore {
+    pub name: String,
+    pub date: NaiveDate,
+    pub net_worth: i32,
+    pub days_survived: i32,
+    pub seed: u64,
+    pub rules: GameRules,
+}
+
+impl HighScore {
+    /// The score for a finished game, dated today.
+    pub fn from_game(name: &str, game: &GameEngine) -> Self {
+        let name = match name.trim() {
+            "" => "Anonymous",
+            name => name,
+        };
+        HighScore {
+            name: name.to_string(),
+            date: Local::now().date_naive(),
+            net_worth: game.net_worth(),
+            days_survived: game.days_survived(),
+            seed: game.seed(),
+            rules: game.rules().clone(),
+        }
+    }
+}
+
+/// Every recorded score, best first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HighScores {
+    entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    /// Reads the table, or starts an empty one if there is no file yet.
+    pub fn load(path: &Path) -> Result<Self, SaveError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(serde_json::from_str(&text)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SaveError> {
+        let json = serde_json::to_string_pretty(self)?;
+        save::write_atomic(path, json.as_bytes())
+    }
+
+    pub fn entries(&self) -> &[HighScore] {
+        &self.entries
+    }
+
+    /// Adds a score in rank order and returns where it landed, or `None` if
+    /// it didn't make the table. Ties go to the score recorded first.
+    pub fn add(&mut self, score: HighScore) -> Option<usize> {
+        let rank = self
+            .entries
+            .iter()
+            .position(|entry| entry.net_worth < score.net_worth)
+            .unwrap_or(self.entries.len());
+        if rank >= MAX_HIGH_SCORES {
+            return None;
+        }
+
+        self.entries.insert(rank, score);
+        self.entries.truncate(MAX_HIGH_SCORES);
+        Some(rank)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(name: &str, net_worth: i32) -> HighScore {
+        HighScore {
+            name: name.to_string(),
+            date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            net_worth,
+            days_survived: 30,
+            seed: 7,
+            rules: GameRules::normal(),
+        }
+    }
+
+    fn names(scores: &HighScores) -> Vec<&str> {
+        scores.entries().iter().map(|s| s.name.as_str()).collect()
+    }
+
+    #[test]
+    fn scores_are_kept_best_first() {
+        let mut scores = HighScores::default();
+
+        assert_eq!(scores.add(score("middle", 500)), Some(0));
+        assert_eq!(scores.add(score("best", 900)), Some(0));
+        assert_eq!(scores.add(score("worst", -100)), Some(2));
+        assert_eq!(scores.add(score("second", 700)), Some(1));
+
+        assert_eq!(names(&scores), ["best", "second", "middle", "worst"]);
+    }
+
+    #[test]
+    fn ties_go_to_the_earlier_score() {
+        let mut scores = HighScores::default();
+        scores.add(score("first", 500));
+        scores.add(score("lower", 100));
+
+        assert_eq!(scores.add(score("second", 500)), Some(1));
+        assert_eq!(names(&scores), ["first", "second", "lower"]);
+    }
+
+    #[test]
+    fn the_table_keeps_only_the_best() {
+        let mut scores = HighScores::default();
+        for i in 0..MAX_HIGH_SCORES as i32 {
+            assert!(scores.add(score("filler", 1000 - i)).is_some());
+        }
+        let last = 1000 - MAX_HIGH_SCORES as i32 + 1;
+
+        // Equal to the last place is not enough to push it out
+        assert_eq!(scores.add(score("too low", last)), None);
+        assert_eq!(scores.entries().len(), MAX_HIGH_SCORES);
+
+        assert_eq!(scores.add(score("new best", 2000)), Some(0));
+        assert_eq!(scores.entries().len(), MAX_HIGH_SCORES);
+        assert_eq!(scores.entries()[0].name, "new best");
+        assert_eq!(scores.entries().last().unwrap().net_worth, last + 1);
+    }
+}
@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Most extra space tiers one stash house can take.
+pub const MAX_EXTRA_SPACE_TIERS: i32 = 3;
+/// Room each extra space tier adds, as a percentage of a new house's capacity.
+pub const EXTRA_SPACE_PERCENT: i32 = 50;
+/// What guards are paid every day, as a percentage of the house's price.
+pub const GUARD_WAGES_PERCENT: i32 = 2;
+/// The broker's cut when the player sells a stash house, in percent.
+pub const BROKER_CUT_PERCENT: i32 = 10;
+
+/// Something that can be added to a stash house.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum StashUpgrade {
+    /// More room; can be bought up to `MAX_EXTRA_SPACE_TIERS` times.
+    ExtraSpace,
+    /// Holds the police up long enough to flush some of the stock, and makes
+    /// the house harder to seize.
+    ReinforcedDoor,
+    /// Warns the house before a raid, often in time to clear out.
+    Alarm,
+    /// Scare off some raids and hold on to more of the stock. They want
+    /// paying every day and leave when they aren't.
+    Guards,
+}
+
+impl StashUpgrade {
+    pub const ALL: [StashUpgrade; 4] = [
+        StashUpgrade::ExtraSpace,
+        StashUpgrade::ReinforcedDoor,
+        StashUpgrade::Alarm,
+        StashUpgrade::Guards,
+    ];
+
+    /// Price as a percentage of the stash house's own price. Each extra
+    /// space tier costs this much more than the last.
+    pub fn price_percent(self) -> i32 {
+        match self {
+            StashUpgrade::ExtraSpace => 50,
+            StashUpgrade::ReinforcedDoor => 25,
+            StashUpgrade::Alarm => 40,
+            StashUpgrade::Guards => 10,
+        }
+    }
+
+    /// Chance in percent that a raid on the house comes to nothing.
+    pub fn raid_protection(self) -> i32 {
+        match self {
+            StashUpgrade::Alarm => 50,
+            StashUpgrade::Guards => 25,
+            _ => 0,
+        }
+    }
+
+    /// Percentage points knocked off how much of the stock a raid takes.
+    pub fn confiscation_reduction(self) -> i32 {
+        match self {
+            StashUpgrade::ReinforcedDoor => 20,
+            StashUpgrade::Guards => 10,
+            _ => 0,
+        }
+    }
+
+    /// Percentage by which the chance of the house being seized is cut.
+    pub fn seizure_reduction(self) -> i32 {
+        match self {
+            StashUpgrade::ReinforcedDoor => 50,
+            _ => 0,
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            StashUpgrade::ExtraSpace => "Room for half as much again.",
+            StashUpgrade::ReinforcedDoor => {
+                "Raids take less of the stock and rarely end with the house seized."
+            }
+            StashUpgrade::Alarm => "Half of all raids find the place cleared out.",
+            StashUpgrade::Guards => {
+                "Scare off a quarter of raids and save some stock, for a daily wage."
+            }
+        }
+    }
+}
+
+impl fmt::Display for StashUpgrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            StashUpgrade::ExtraSpace => "Extra space",
+            StashUpgrade::ReinforcedDoor => "Reinforced door",
+            StashUpgrade::Alarm => "Alarm system",
+            StashUpgrade::Guards => "Guards",
+        };
+        f.write_str(name)
+    }
+}
@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+
+/// What the player is worth, broken down by where the money is.
+///
+/// Stock is valued at what selling all of it in the borough it's in would
+/// fetch today, weapons at what a dealer would pay for them used and stash
+/// houses and fronts at their asking price. Dirty cash is marked down by the
+/// rules' penalty.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetWorth {
+    /// Clean cash.
+    pub cash: i32,
+    /// Dirty cash at face value.
+    #[serde(default)]
+    pub dirty_cash: i32,
+    /// Knocked off the dirty cash for not being laundered.
+    #[serde(default)]
+    pub dirty_cash_penalty: i32,
+    /// Savings in the bank.
+    #[serde(default)]
+    pub bank: i32,
+    pub debt: i32,
+    /// Drugs in the player's pockets.
+    pub carried_stock: i32,
+    /// Drugs in every stash house.
+    pub stashed_stock: i32,
+    /// The stash houses and fronts themselves.
+    pub property: i32,
+    pub weapons: i32,
+}
+
+impl NetWorth {
+    /// The total, held at the ends of the `i32` range for fortunes too big
+    /// to count.
+    pub fn total(&self) -> i32 {
+        self.total_wide().clamp(i32::MIN as i64, i32::MAX as i64) as i32
+    }
+
+    /// The total, added up without overflowing however large the parts are.
+    pub fn total_wide(&self) -> i64 {
+        [
+            self.cash,
+            self.dirty_cash,
+            -self.dirty_cash_penalty,
+            self.bank,
+            -self.debt,
+            self.carried_stock,
+            self.stashed_stock,
+            self.property,
+            self.weapons,
+        ]
+        .iter()
+        .map(|&part| part as i64)
+        .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_total_subtracts_debt_and_the_dirty_cash_penalty() {
+        let worth = NetWorth {
+            cash: 1000,
+            dirty_cash: 400,
+            dirty_cash_penalty: 100,
+            bank: 2000,
+            debt: 5000,
+            carried_stock: 300,
+            stashed_stock: 200,
+            property: 10_000,
+            weapons: 50,
+        };
+
+        assert_eq!(worth.total(), 8850);
+        assert_eq!(worth.total_wide(), 8850);
+    }
+
+    #[test]
+    fn totals_past_the_range_of_i32_are_held_at_its_ends() {
+        let rich = NetWorth {
+            cash: i32::MAX,
+            bank: i32::MAX,
+            ..NetWorth::default()
+        };
+        assert_eq!(rich.total_wide(), 2 * i32::MAX as i64);
+        assert_eq!(rich.total(), i32::MAX);
+
+        let broke = NetWorth {
+            cash: i32::MIN + 1,
+            debt: i32::MAX,
+            ..NetWorth::default()
+        };
+        assert_eq!(broke.total(), i32::MIN);
+    }
+}
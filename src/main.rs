@@ -1,340 +1,175 @@
+use dopewars_gui::game::replay::{self, Replay};
+use dopewars_gui::game::save::{self, AUTOSAVE_FILE, MANUAL_SAVE_FILE};
+use dopewars_gui::game::scores::{HighScore, HighScores, HIGH_SCORES_FILE};
+use dopewars_gui::game::{
+    describe_problems, Action, ContentPack, Difficulty, GameEngine, GameError, GameRules,
+    GameState, PoliceState, Service, StashUpgrade, TravelOutcome, BARRED_AFTER_DAYS,
+    BROKER_CUT_PERCENT, ENFORCERS_AFTER_DAYS, EXTRA_SPACE_PERCENT, MAX_EXTRA_SPACE_TIERS,
+    MINIMUM_PAYMENT_PERCENT, TERM_DAYS,
+};
 use eframe::egui;
-use egui::{Color32, Response, RichText, Ui};
-use rand::Rng;
-use std::collections::HashMap;
-
-const LOCATIONS: [&str; 6] = [
-    "Bronx",
-    "Brooklyn",
-    "Manhattan",
-    "Queens",
-    "Staten Island",
-    "Central Park",
-];
-
-const SUBSTANCES: [&str; 6] = ["Weed", "Cocaine", "Ludes", "Acid", "Heroin", "Speed"];
-
-const WEAPONS: [&str; 5] = ["Knife", "Pistol", "Shotgun", "Assault Rifle", "Grenade"];
-
-const WEAPON_PRICES: [i32; 5] = [500, 1500, 3000, 6000, 4000];
-
-const WEAPON_POWER: [i32; 5] = [20, 40, 65, 85, 95];
-
-const MAX_DAYS: i32 = 30;
-const STARTING_CASH: i32 = 2000;
-const MAX_CARRYING_CAPACITY: i32 = 100;
-const LOAN_SHARK_INTEREST: f32 = 0.1; // 10% interest per day
-const STASH_HOUSE_CAPACITY: i32 = 200;
-const STASH_HOUSE_BASE_PRICE: i32 = 10000;
-
-// Define a struct for stash houses
-struct StashHouse {
-    location: String,
-    inventory: HashMap<String, i32>,
-    capacity: i32,
-}
-
-impl StashHouse {
-    fn new(location: String) -> Self {
-        let mut inventory = HashMap::new();
-        for substance in SUBSTANCES.iter() {
-            inventory.insert(substance.to_string(), 0);
-        }
-
-        StashHouse {
-            location,
-            inventory,
-            capacity: STASH_HOUSE_CAPACITY,
-        }
-    }
-
-    fn total_items(&self) -> i32 {
-        self.inventory.values().sum()
-    }
-
-    fn space_available(&self) -> i32 {
-        self.capacity - self.total_items()
-    }
-}
-
-struct Player {
-    cash: i32,
-    debt: i32,
-    inventory: HashMap<String, i32>,
-    current_location: String,
-    day: i32,
-    health: i32,
-    weapons: HashMap<String, i32>,
-    active_weapon: Option<String>,
-    stash_houses: HashMap<String, StashHouse>,
-}
-
-impl Player {
-    fn new() -> Self {
-        let mut inventory = HashMap::new();
-        for substance in SUBSTANCES.iter() {
-            inventory.insert(substance.to_string(), 0);
-        }
-
-        let mut weapons = HashMap::new();
-        for weapon in WEAPONS.iter() {
-            weapons.insert(weapon.to_string(), 0);
-        }
-
-        Player {
-            cash: STARTING_CASH,
-            debt: 5000,
-            inventory,
-            current_location: LOCATIONS[0].to_string(),
-            day: 1,
-            health: 100,
-            weapons,
-            active_weapon: None,
-            stash_houses: HashMap::new(),
-        }
-    }
-
-    fn total_items(&self) -> i32 {
-        self.inventory.values().sum()
-    }
-
-    fn space_available(&self) -> i32 {
-        MAX_CARRYING_CAPACITY - self.total_items()
-    }
-
-    fn has_weapon(&self) -> bool {
-        self.weapons.values().sum::<i32>() > 0
-    }
-
-    fn get_active_weapon_power(&self) -> i32 {
-        if let Some(weapon) = &self.active_weapon {
-            // Find the weapon index
-            for (i, w) in WEAPONS.iter().enumerate() {
-                if *w == *weapon {
-                    return WEAPON_POWER[i];
-                }
-            }
-        }
-        0 // No weapon or weapon not found
-    }
-
-    fn owns_stash_house_in_current_location(&self) -> bool {
-        self.stash_houses.contains_key(&self.current_location)
-    }
-
-    fn get_stash_house_price(&self, location: &str) -> i32 {
-        // Prices vary by location
-        match location {
-            "Manhattan" => STASH_HOUSE_BASE_PRICE * 2, // Most expensive
-            "Bronx" | "Brooklyn" => STASH_HOUSE_BASE_PRICE,
-            "Queens" => STASH_HOUSE_BASE_PRICE * 3 / 4, // 75% of base price
-            "Staten Island" => STASH_HOUSE_BASE_PRICE / 2, // Half price
-            "Central Park" => STASH_HOUSE_BASE_PRICE * 4, // Very expensive, high risk
-            _ => STASH_HOUSE_BASE_PRICE,
-        }
-    }
-}
-
-struct Market {
-    prices: HashMap<String, i32>,
-    events: Vec<String>,
-    price_history: HashMap<String, Vec<i32>>,
-    max_history: usize,
-}
-
-impl Market {
-    fn new() -> Self {
-        let mut price_history = HashMap::new();
-        // Initialize price history for each substance
-        for substance in SUBSTANCES.iter() {
-            price_history.insert(substance.to_string(), Vec::new());
-        }
-
-        Market {
-            prices: HashMap::new(),
-            events: Vec::new(),
-            price_history,
-            max_history: 10, // Keep track of the last 10 prices
-        }
-    }
-
-    fn generate_prices(&mut self) {
-        self.events.clear();
-        let mut rng = rand::thread_rng();
-
-        // Chance for global market event
-        let global_event = rng.gen_range(0..20);
-        let global_market_modifier = match global_event {
-            0 => {
-                let message = "📰 GLOBAL EVENT: International drug enforcement operation! All prices increased!".to_string();
-                self.events.push(message);
-                1.5 // Prices up 50%
-            }
-            1 => {
-                let message = "📰 GLOBAL EVENT: Major drug cartel busted! Supply chains disrupted!"
-                    .to_string();
-                self.events.push(message);
-                2.0 // Prices doubled
-            }
-            2 => {
-                let message = "📰 GLOBAL EVENT: New synthetic alternatives flooding market! All prices decreased!".to_string();
-                self.events.push(message);
-                0.5 // Prices halved
-            }
-            3 => {
-                let message = "📰 GLOBAL EVENT: Economic recession! Drug demand down!".to_string();
-                self.events.push(message);
-                0.7 // Prices 30% down
-            }
-            _ => 1.0, // No global effect
-        };
-
-        // Generate prices for each substance
-        for substance in SUBSTANCES.iter() {
-            let base_price = match *substance {
-                "Weed" => rng.gen_range(10..100),
-                "Cocaine" => rng.gen_range(100..1000),
-                "Ludes" => rng.gen_range(20..200),
-                "Acid" => rng.gen_range(50..400),
-                "Heroin" => rng.gen_range(150..1500),
-                "Speed" => rng.gen_range(50..700),
-                _ => 0,
-            };
-
-            // Random event: sometimes a substance's price spikes or crashes
-            let special_event = rng.gen_range(0..20);
-            let price = match special_event {
-                0 => {
-                    let message = format!(
-                        "📰 Breaking news! Police busted a {} shipment! Prices skyrocketing!",
-                        substance
-                    );
-                    self.events.push(message);
-                    base_price * 5
-                }
-                1 => {
-                    let message =
-                        format!("📰 Market flooded with {}! Prices have crashed!", substance);
-                    self.events.push(message);
-                    base_price / 5
-                }
-                2 => {
-                    let message = format!(
-                        "📰 New high-quality {} on the market! Premium prices!",
-                        substance
-                    );
-                    self.events.push(message);
-                    base_price * 3
-                }
-                3 => {
-                    let message = format!(
-                        "📰 Contaminated {} discovered! Demand plummeting!",
-                        substance
-                    );
-                    self.events.push(message);
-                    base_price / 3
-                }
-                _ => base_price,
-            };
-
-            // Apply global modifier
-            let final_price = (price as f32 * global_market_modifier) as i32;
-
-            // Ensure price is always at least 1
-            let final_price = std::cmp::max(final_price, 1);
-
-            self.prices.insert(substance.to_string(), final_price);
-
-            // Update price history
-            if let Some(history) = self.price_history.get_mut(&substance.to_string()) {
-                history.push(final_price);
-                // Keep only the most recent prices based on max_history
-                if history.len() > self.max_history {
-                    history.remove(0);
-                }
-            }
-        }
-    }
-}
-
-enum GameState {
-    Running,
-    GameOver(String),
-}
+use egui::{Color32, RichText, Ui};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
-#[derive(Clone)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 enum Screen {
+    #[default]
     Main,
     Buy,
     Sell,
     Travel,
     LoanShark,
+    Bank,
     Hospital,
     Settings,
     PriceChart,
+    News,
     Weapons,
     PoliceEncounter,
+    BribeNegotiation,
+    Jail,
     StashHouses,
     StashDeposit,
     StashWithdraw,
+    Fronts,
+    NewGame,
+    HighScores,
+    NetWorth,
+    Replay,
+}
+
+/// A replay being watched: the game after every recorded action.
+struct ReplayViewer {
+    title: String,
+    frames: Vec<GameEngine>,
+    actions: Vec<Action>,
+    step: usize,
+}
+
+impl ReplayViewer {
+    fn day(&self, step: usize) -> i32 {
+        self.frames[step].player.day
+    }
+
+    /// The first step shown on the same day as `step`.
+    fn start_of_day(&self, step: usize) -> usize {
+        let day = self.day(step);
+        (0..=step)
+            .rev()
+            .take_while(|&i| self.day(i) == day)
+            .last()
+            .unwrap_or(step)
+    }
+
+    fn previous_day(&mut self) {
+        let start = self.start_of_day(self.step);
+        self.step = if self.step > start || start == 0 {
+            start
+        } else {
+            self.start_of_day(start - 1)
+        };
+    }
+
+    fn next_day(&mut self) {
+        let day = self.day(self.step);
+        self.step = (self.step..self.frames.len())
+            .find(|&i| self.day(i) != day)
+            .unwrap_or(self.frames.len() - 1);
+    }
 }
 
 struct DopeWarsApp {
-    player: Player,
-    market: Market,
-    game_state: GameState,
+    engine: GameEngine,
     current_screen: Screen,
-    message_log: Vec<String>,
     selected_substance: Option<String>,
     amount_to_trade: String,
     amount_to_borrow_repay: String,
+    amount_to_bank: String,
+    amount_to_launder: String,
     ui_scale: f32,
     font_size: f32,
     selected_chart_substance: Option<String>,
+    selected_chart_location: Option<String>,
     restart_requested: bool,
+    restart_seed: Option<u64>,
+    seed_input: String,
     selected_weapon: Option<String>,
     bribe_amount: String,
     stash_selected_substance: Option<String>,
     stash_amount_to_transfer: String,
+    autosave_enabled: bool,
+    last_autosave_day: i32,
+    /// Pack that the next new game is played with.
+    content: ContentPack,
+    content_path_input: String,
+    content_status: Option<Result<String, String>>,
+    /// Rules that the next new game is played by.
+    rules: GameRules,
+    /// Name the next score is recorded under.
+    player_name: String,
+    high_scores: HighScores,
+    /// Whether this game's score has gone into the table yet.
+    score_recorded: bool,
+    /// Where this game's score landed in the table, if it made it.
+    new_high_score: Option<usize>,
+    high_score_error: Option<String>,
+    /// Which rules the High Scores screen shows; `None` shows all of them.
+    high_score_filter: Option<Difficulty>,
+    replay_viewer: Option<ReplayViewer>,
+    /// Recorded replays, newest first, as of the last time the list was read.
+    replay_files: Vec<PathBuf>,
+    /// Where this game's replay was recorded, or why it couldn't be.
+    replay_status: Option<Result<String, String>>,
 }
 
 impl Default for DopeWarsApp {
     fn default() -> Self {
-        let mut app = Self {
-            player: Player::new(),
-            market: Market::new(),
-            game_state: GameState::Running,
+        Self {
+            engine: GameEngine::new(),
             current_screen: Screen::Main,
-            message_log: Vec::new(),
             selected_substance: None,
             amount_to_trade: "0".to_string(),
             amount_to_borrow_repay: "0".to_string(),
+            amount_to_bank: "0".to_string(),
+            amount_to_launder: "0".to_string(),
             ui_scale: 1.0,
             font_size: 14.0,
-            selected_chart_substance: Some("Weed".to_string()), // Default to first substance
+            selected_chart_substance: None, // Set to the pack's first substance on reset
+            selected_chart_location: None,  // Follows the player until one is picked
             restart_requested: false,
+            restart_seed: None,
+            seed_input: String::new(),
+            autosave_enabled: true,
+            last_autosave_day: 1,
             selected_weapon: None,
             bribe_amount: "0".to_string(),
             stash_selected_substance: None,
             stash_amount_to_transfer: "0".to_string(),
-        };
-
-        app.message_log
-            .push("Welcome to DopeWars GUI Edition!".to_string());
-        app.message_log
-            .push("You have 30 days to make as much money as possible.".to_string());
-        app.message_log
-            .push("Buy low, sell high, and watch out for the cops!".to_string());
-
-        // Generate initial market prices
-        app.market.generate_prices();
-
-        // Add market events to message log
-        for event in &app.market.events {
-            app.message_log.push(event.clone());
+            content: ContentPack::default(),
+            content_path_input: String::new(),
+            content_status: None,
+            rules: GameRules::default(),
+            player_name: "Player".to_string(),
+            high_scores: HighScores::default(),
+            score_recorded: false,
+            new_high_score: None,
+            high_score_error: None,
+            high_score_filter: None,
+            replay_viewer: None,
+            replay_files: Vec::new(),
+            replay_status: None,
         }
+    }
+}
 
-        app
+/// Cash for a status line, with how much of it is dirty.
+fn cash_text(engine: &GameEngine) -> String {
+    let player = &engine.player;
+    if player.dirty_cash > 0 {
+        format!("Cash: ${} (${} dirty)", player.cash, player.dirty_cash)
+    } else {
+        format!("Cash: ${}", player.cash)
     }
 }
 
@@ -370,54 +205,52 @@ impl eframe::App for DopeWarsApp {
 
         // Check if restart was requested
         if self.restart_requested {
-            // Reset player
-            self.player = Player::new();
-
-            // Reset market
-            self.market = Market::new();
-            self.market.generate_prices();
-
-            // Reset game state
-            self.game_state = GameState::Running;
+            // Start a fresh game, from the chosen seed if there is one
+            let replay_note = self.record_unfinished_game();
+            let seed = self.restart_seed.take().unwrap_or_else(rand::random);
+            self.engine = GameEngine::with_rules(seed, self.content.clone(), self.rules.clone());
+            self.engine.message_log.extend(replay_note);
+            self.engine.message_log.push("Game restarted!".to_string());
             self.current_screen = Screen::Main;
-
-            // Reset interface state
-            self.selected_substance = None;
-            self.amount_to_trade = "0".to_string();
-            self.amount_to_borrow_repay = "0".to_string();
-            self.selected_weapon = None;
-            self.bribe_amount = "0".to_string();
-            self.stash_selected_substance = None;
-            self.stash_amount_to_transfer = "0".to_string();
-
-            // Reset message log but keep intro messages
-            self.message_log.clear();
-            self.message_log
-                .push("Welcome to DopeWars GUI Edition!".to_string());
-            self.message_log
-                .push("You have 30 days to make as much money as possible.".to_string());
-            self.message_log
-                .push("Buy low, sell high, and watch out for the cops!".to_string());
-            self.message_log.push("Game restarted!".to_string());
-
-            // Add market events to message log
-            for event in &self.market.events {
-                self.message_log.push(event.clone());
-            }
+            self.score_recorded = false;
+            self.new_high_score = None;
+            self.high_score_error = None;
+            self.replay_status = None;
+            self.last_autosave_day = self.engine.player.day;
+            self.reset_interface_state();
 
             // Reset restart flag
             self.restart_requested = false;
         }
 
-        match self.game_state {
+        match self.engine.state {
             GameState::Running => {
+                // Text boxes keep Ctrl+Z for themselves while they have focus
+                if ctx.memory(|memory| memory.focus().is_none())
+                    && ctx.input_mut(|input| {
+                        input.consume_key(egui::Modifiers::COMMAND, egui::Key::Z)
+                    })
+                {
+                    self.undo();
+                }
+
                 egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
                     self.render_top_panel(ui);
                 });
 
-                egui::SidePanel::left("side_panel").show(ctx, |ui| {
-                    self.render_side_panel(ui);
-                });
+                // No wandering off while the police are holding you
+                if self.engine.custody().is_some() {
+                    self.current_screen = Screen::Jail;
+                } else if let Some(encounter) = self.engine.police_encounter() {
+                    self.current_screen = match encounter.state {
+                        PoliceState::Negotiating => Screen::BribeNegotiation,
+                        _ => Screen::PoliceEncounter,
+                    };
+                } else {
+                    egui::SidePanel::left("side_panel").show(ctx, |ui| {
+                        self.render_side_panel(ui);
+                    });
+                }
 
                 egui::CentralPanel::default().show(ctx, |ui| match self.current_screen {
                     Screen::Main => self.render_main_screen(ui),
@@ -425,14 +258,23 @@ impl eframe::App for DopeWarsApp {
                     Screen::Sell => self.render_sell_screen(ui),
                     Screen::Travel => self.render_travel_screen(ui),
                     Screen::LoanShark => self.render_loan_shark_screen(ui),
+                    Screen::Bank => self.render_bank_screen(ui),
                     Screen::Hospital => self.render_hospital_screen(ui),
                     Screen::Settings => self.render_settings_screen(ui),
                     Screen::PriceChart => self.render_price_chart_screen(ui),
+                    Screen::News => self.render_news_screen(ui),
                     Screen::Weapons => self.render_weapons_screen(ui),
                     Screen::PoliceEncounter => self.render_police_encounter(ui),
+                    Screen::BribeNegotiation => self.render_bribe_negotiation_screen(ui),
+                    Screen::Jail => self.render_jail_screen(ui),
                     Screen::StashHouses => self.render_stash_houses_screen(ui),
+                    Screen::Fronts => self.render_fronts_screen(ui),
                     Screen::StashDeposit => self.render_stash_deposit_screen(ui),
                     Screen::StashWithdraw => self.render_stash_withdraw_screen(ui),
+                    Screen::NewGame => self.render_new_game_screen(ui),
+                    Screen::HighScores => self.render_high_scores_screen(ui),
+                    Screen::NetWorth => self.render_net_worth_screen(ui),
+                    Screen::Replay => self.render_replay_screen(ui),
                 });
 
                 egui::TopBottomPanel::bottom("bottom_panel").show(ctx, |ui| {
@@ -441,50 +283,290 @@ impl eframe::App for DopeWarsApp {
             }
             GameState::GameOver(ref reason) => {
                 let reason_string = reason.clone(); // Clone to avoid borrowing issues
+                if !self.score_recorded {
+                    self.record_score();
+                }
                 egui::CentralPanel::default().show(ctx, |ui| {
-                    self.render_game_over(ui, &reason_string);
+                    if matches!(self.current_screen, Screen::Replay) {
+                        self.render_replay_screen(ui);
+                    } else {
+                        self.render_game_over(ui, &reason_string);
+                    }
                 });
             }
         }
 
         // Check for game over conditions
-        if let GameState::Running = self.game_state {
-            if self.player.day > MAX_DAYS {
-                self.game_state =
-                    GameState::GameOver("Time's up! Your 30 days are over.".to_string());
-            } else if self.player.health <= 0 {
-                self.game_state = GameState::GameOver("You died from your injuries!".to_string());
+        self.engine.update_state();
+
+        // Autosave once at the start of every new day
+        if self.autosave_enabled
+            && self.engine.is_running()
+            && self.engine.player.day != self.last_autosave_day
+        {
+            self.last_autosave_day = self.engine.player.day;
+            if let Err(e) = self.write_save(AUTOSAVE_FILE) {
+                self.engine
+                    .message_log
+                    .push(format!("Autosave failed: {}", e));
             }
         }
     }
+
+    fn on_close_event(&mut self) -> bool {
+        // There's no log left to show the outcome in
+        let _ = self.record_unfinished_game();
+        true
+    }
 }
 
 impl DopeWarsApp {
+    fn new(content: ContentPack, notice: Option<String>) -> Self {
+        let mut app = Self {
+            engine: GameEngine::with_content(rand::random(), content.clone()),
+            content,
+            ..Self::default()
+        };
+        app.reset_interface_state();
+        if let Some(notice) = notice {
+            app.engine.message_log.push(notice);
+        }
+        match save::save_path(HIGH_SCORES_FILE).and_then(|path| HighScores::load(&path)) {
+            Ok(scores) => app.high_scores = scores,
+            Err(e) => app
+                .engine
+                .message_log
+                .push(format!("Could not read the high scores: {}", e)),
+        }
+        app
+    }
+
+    /// Puts the finished game's score into the table and writes it out.
+    fn record_score(&mut self) {
+        self.score_recorded = true;
+        let score = HighScore::from_game(&self.player_name, &self.engine);
+        self.new_high_score = self.high_scores.add(score);
+        if self.new_high_score.is_some() {
+            let written =
+                save::save_path(HIGH_SCORES_FILE).and_then(|path| self.high_scores.save(&path));
+            if let Err(e) = written {
+                self.high_score_error = Some(format!("Could not save the high scores: {}", e));
+            }
+        }
+
+        self.replay_status = replay::record(&self.engine).map(|saved| match saved {
+            Ok(path) => Ok(format!("Replay saved to {}", path.display())),
+            Err(e) => Err(format!("Could not save the replay: {}", e)),
+        });
+    }
+
+    /// Records the replay of a game the player is leaving unfinished, by
+    /// quitting or by starting or loading another. Returns what happened,
+    /// for the log. Finished games were recorded when they ended.
+    fn record_unfinished_game(&self) -> Option<String> {
+        if !self.engine.is_running() {
+            return None;
+        }
+        replay::record(&self.engine).map(|saved| match saved {
+            Ok(path) => format!("The last game's replay was saved to {}", path.display()),
+            Err(e) => format!("Could not save the last game's replay: {}", e),
+        })
+    }
+
+    /// Loads a replay into the viewer, or explains why it can't be watched.
+    fn watch_replay(&mut self, title: String, replay: &Replay) {
+        match replay.frames() {
+            Ok(frames) => {
+                self.replay_viewer = Some(ReplayViewer {
+                    title,
+                    frames,
+                    actions: replay.actions.clone(),
+                    step: 0,
+                });
+                self.current_screen = Screen::Replay;
+            }
+            Err(e) => self.replay_status = Some(Err(format!("Could not play the replay: {}", e))),
+        }
+    }
+
+    fn open_replays(&mut self) {
+        self.current_screen = Screen::Replay;
+        self.replay_viewer = None;
+        match replay::list_replays() {
+            Ok(files) => self.replay_files = files,
+            Err(e) => {
+                self.replay_files.clear();
+                self.replay_status = Some(Err(format!("Could not list the replays: {}", e)));
+            }
+        }
+    }
+
+    fn undo(&mut self) {
+        if let Err(e) = self.engine.undo() {
+            self.engine.message_log.push(e.to_string());
+        }
+    }
+
+    /// Substances in the current game's content pack, in pack order.
+    fn substance_names(&self) -> Vec<String> {
+        self.engine
+            .content()
+            .substance_names()
+            .map(String::from)
+            .collect()
+    }
+
+    fn location_names(&self) -> Vec<String> {
+        self.engine
+            .content()
+            .location_names()
+            .map(String::from)
+            .collect()
+    }
+
+    fn reset_interface_state(&mut self) {
+        self.selected_substance = None;
+        self.amount_to_trade = "0".to_string();
+        self.amount_to_borrow_repay = "0".to_string();
+        self.amount_to_bank = "0".to_string();
+        self.amount_to_launder = "0".to_string();
+        self.selected_weapon = None;
+        self.bribe_amount = "0".to_string();
+        self.stash_selected_substance = None;
+        self.stash_amount_to_transfer = "0".to_string();
+        self.selected_chart_substance = self.substance_names().into_iter().next();
+        self.selected_chart_location = None;
+    }
+
+    fn write_save(&self, file_name: &str) -> Result<(), save::SaveError> {
+        let path = save::save_path(file_name)?;
+        save::save_game(&path, &self.engine, &self.current_screen)
+    }
+
+    fn save_to(&mut self, file_name: &str) {
+        match self.write_save(file_name) {
+            Ok(()) => self.engine.message_log.push("Game saved.".to_string()),
+            Err(e) => self
+                .engine
+                .message_log
+                .push(format!("Could not save the game: {}", e)),
+        }
+    }
+
+    fn load_from(&mut self, file_name: &str) {
+        let loaded = save::save_path(file_name).and_then(|path| save::load_game(&path));
+        match loaded {
+            Ok((engine, screen)) => {
+                let replay_note = self.record_unfinished_game();
+                self.engine = engine;
+                self.engine.message_log.extend(replay_note);
+                self.current_screen = screen;
+                // Keep playing in the saved game's city and rules after a restart
+                self.content = self.engine.content().clone();
+                self.rules = self.engine.rules().clone();
+                // A finished game was scored when it ended
+                self.score_recorded = !self.engine.is_running();
+                self.new_high_score = None;
+                self.high_score_error = None;
+                self.last_autosave_day = self.engine.player.day;
+                self.reset_interface_state();
+                self.engine.message_log.push("Game loaded.".to_string());
+            }
+            Err(e) => self
+                .engine
+                .message_log
+                .push(format!("Could not load the game: {}", e)),
+        }
+    }
+
     fn render_top_panel(&self, ui: &mut Ui) {
         ui.horizontal(|ui| {
             ui.heading("DopeWars GUI");
             ui.add_space(20.0);
-            ui.label(format!("Day: {}/{}", self.player.day, MAX_DAYS));
+            ui.label(format!(
+                "Day: {}/{}",
+                self.engine.player.day,
+                self.engine.rules().max_days
+            ));
             ui.add_space(10.0);
-            ui.label(format!("Cash: ${}", self.player.cash));
+            ui.label(cash_text(&self.engine));
             ui.add_space(10.0);
-            ui.label(format!("Debt: ${}", self.player.debt));
+            ui.label(format!("Debt: ${}", self.engine.player.debt));
             ui.add_space(10.0);
-            ui.label(format!("Location: {}", self.player.current_location));
+            if self.engine.player.bank_balance > 0 {
+                ui.label(format!("Bank: ${}", self.engine.player.bank_balance));
+                ui.add_space(10.0);
+            }
+            ui.label(format!("Location: {}", self.engine.player.current_location));
             ui.add_space(10.0);
 
-            let health_text = if self.player.health < 30 {
-                RichText::new(format!("Health: {}", self.player.health)).color(Color32::RED)
-            } else if self.player.health < 70 {
-                RichText::new(format!("Health: {}", self.player.health)).color(Color32::YELLOW)
+            let health_text = if self.engine.player.health < 30 {
+                RichText::new(format!("Health: {}", self.engine.player.health)).color(Color32::RED)
+            } else if self.engine.player.health < 70 {
+                RichText::new(format!("Health: {}", self.engine.player.health))
+                    .color(Color32::YELLOW)
             } else {
-                RichText::new(format!("Health: {}", self.player.health)).color(Color32::GREEN)
+                RichText::new(format!("Health: {}", self.engine.player.health))
+                    .color(Color32::GREEN)
             };
 
             ui.label(health_text);
+            ui.add_space(10.0);
+
+            let heat = self.engine.player.heat;
+            let heat_label = format!("Heat: {} ({})", heat, self.engine.player.heat_description());
+            let heat_text = if heat >= 45 {
+                RichText::new(heat_label).color(Color32::RED)
+            } else if heat >= 20 {
+                RichText::new(heat_label).color(Color32::YELLOW)
+            } else {
+                RichText::new(heat_label).color(Color32::GREEN)
+            };
+
+            ui.label(heat_text).on_hover_text(format!(
+                "{}% chance of a police stop on your next trip",
+                self.engine.police_stop_chance()
+            ));
         });
     }
 
+    /// A button for a service, greyed out with where to find it when it
+    /// isn't offered here.
+    fn service_button(&self, ui: &mut Ui, text: &str, service: Service) -> bool {
+        self.service_button_unless(ui, text, service, false)
+    }
+
+    /// Like `service_button`, but stays enabled when `still_useful`, for
+    /// screens that also manage what the player already owns.
+    fn service_button_unless(
+        &self,
+        ui: &mut Ui,
+        text: &str,
+        service: Service,
+        still_useful: bool,
+    ) -> bool {
+        ui.add_enabled(
+            still_useful || self.engine.service_here(service),
+            egui::Button::new(text),
+        )
+        .on_disabled_hover_text(self.engine.where_to_find(service))
+        .clicked()
+    }
+
+    /// Says where to go instead when `service` isn't offered here. Returns
+    /// whether it is.
+    fn service_available(&self, ui: &mut Ui, service: Service) -> bool {
+        if self.engine.service_here(service) {
+            return true;
+        }
+        ui.label(format!(
+            "There's no {} in {}.",
+            service, self.engine.player.current_location
+        ));
+        ui.label(self.engine.where_to_find(service));
+        false
+    }
+
     fn render_side_panel(&mut self, ui: &mut Ui) {
         ui.heading("Navigation");
 
@@ -508,23 +590,36 @@ impl DopeWarsApp {
             self.current_screen = Screen::Travel;
         }
 
-        if ui.button("Weapons Shop").clicked() {
+        // Owned weapons can be equipped and owned stash houses used anywhere
+        let armed = self.engine.player.has_weapon();
+        if self.service_button_unless(ui, "Weapons Shop", Service::GunShop, armed) {
             self.current_screen = Screen::Weapons;
             self.selected_weapon = None;
         }
 
-        if ui.button("Stash Houses").clicked() {
+        let stash_here = self.engine.player.owns_stash_house_in_current_location();
+        if self.service_button_unless(ui, "Stash Houses", Service::StashBroker, stash_here) {
             self.current_screen = Screen::StashHouses;
             self.stash_selected_substance = None;
             self.stash_amount_to_transfer = "0".to_string();
         }
 
-        if ui.button("Loan Shark").clicked() {
+        if ui.button("Fronts").clicked() {
+            self.current_screen = Screen::Fronts;
+            self.amount_to_launder = "0".to_string();
+        }
+
+        if self.service_button(ui, "Loan Shark", Service::LoanShark) {
             self.current_screen = Screen::LoanShark;
             self.amount_to_borrow_repay = "0".to_string();
         }
 
-        if ui.button("Hospital").clicked() {
+        if self.service_button(ui, "Bank", Service::Bank) {
+            self.current_screen = Screen::Bank;
+            self.amount_to_bank = "0".to_string();
+        }
+
+        if self.service_button(ui, "Hospital", Service::Hospital) {
             self.current_screen = Screen::Hospital;
         }
 
@@ -532,20 +627,74 @@ impl DopeWarsApp {
             self.current_screen = Screen::PriceChart;
         }
 
+        if ui.button("News").clicked() {
+            self.current_screen = Screen::News;
+        }
+
+        if ui.button("Net Worth").clicked() {
+            self.current_screen = Screen::NetWorth;
+        }
+
+        if ui.button("New Game").clicked() {
+            self.current_screen = Screen::NewGame;
+        }
+
+        if ui.button("High Scores").clicked() {
+            self.current_screen = Screen::HighScores;
+        }
+
+        if ui.button("Replays").clicked() {
+            self.open_replays();
+        }
+
         if ui.button("Settings").clicked() {
             self.current_screen = Screen::Settings;
         }
 
         ui.separator();
 
+        ui.heading("Game");
+
+        let undo_hint = match self.engine.last_undoable() {
+            Some(action) => format!("{} (Ctrl+Z)", action),
+            None => "Nothing to undo since your last trip.".to_string(),
+        };
+        if ui
+            .add_enabled(
+                self.engine.last_undoable().is_some(),
+                egui::Button::new("Undo"),
+            )
+            .on_hover_text(&undo_hint)
+            .on_disabled_hover_text(&undo_hint)
+            .clicked()
+        {
+            self.undo();
+        }
+
+        if ui.button("Save Game").clicked() {
+            self.save_to(MANUAL_SAVE_FILE);
+        }
+
+        if ui.button("Load Game").clicked() {
+            self.load_from(MANUAL_SAVE_FILE);
+        }
+
+        if ui.button("Load Autosave").clicked() {
+            self.load_from(AUTOSAVE_FILE);
+        }
+
+        ui.checkbox(&mut self.autosave_enabled, "Autosave every day");
+
+        ui.separator();
+
         ui.heading("Inventory");
         ui.label(format!(
             "Space: {}/{}",
-            self.player.total_items(),
-            MAX_CARRYING_CAPACITY
+            self.engine.player.total_items(),
+            self.engine.player.carrying_capacity
         ));
 
-        for (substance, amount) in &self.player.inventory {
+        for (substance, amount) in &self.engine.player.inventory {
             if *amount > 0 {
                 ui.label(format!("{}: {}", substance, amount));
             }
@@ -554,12 +703,12 @@ impl DopeWarsApp {
         ui.separator();
 
         ui.heading("Weapons");
-        let has_weapons = self.player.weapons.values().sum::<i32>() > 0;
+        let has_weapons = self.engine.player.weapons.values().sum::<i32>() > 0;
 
         if has_weapons {
-            for (weapon, amount) in &self.player.weapons {
+            for (weapon, amount) in &self.engine.player.weapons {
                 if *amount > 0 {
-                    let is_active = match &self.player.active_weapon {
+                    let is_active = match &self.engine.player.active_weapon {
                         Some(active) => active == weapon,
                         None => false,
                     };
@@ -581,18 +730,18 @@ impl DopeWarsApp {
         ui.separator();
 
         ui.heading("Market Prices");
-        for substance in SUBSTANCES.iter() {
-            let price = self.market.prices.get(&substance.to_string()).unwrap_or(&0);
+        for substance in self.substance_names().iter() {
+            let price = self.engine.local_price(substance);
             ui.label(format!("{}: ${}", substance, price));
         }
 
         // Add stash houses section to sidebar if player owns any
-        if !self.player.stash_houses.is_empty() {
+        if !self.engine.player.stash_houses.is_empty() {
             ui.separator();
             ui.heading("Your Stash Houses");
 
-            for (location, stash) in &self.player.stash_houses {
-                let is_current = *location == self.player.current_location;
+            for (location, stash) in &self.engine.player.stash_houses {
+                let is_current = *location == self.engine.player.current_location;
                 let text = if is_current {
                     RichText::new(format!(
                         "{}: {}/{}",
@@ -619,6 +768,17 @@ impl DopeWarsApp {
         ui.heading("DopeWars - Main Menu");
         ui.label("What do you want to do?");
 
+        let record = &self.engine.player.record;
+        if !record.is_clean() {
+            ui.label(
+                RichText::new(format!(
+                    "Criminal record: {} arrests, {} convictions",
+                    record.arrests, record.convictions
+                ))
+                .color(Color32::YELLOW),
+            );
+        }
+
         ui.separator();
 
         if ui.button("Buy substances").clicked() {
@@ -648,12 +808,22 @@ impl DopeWarsApp {
             self.stash_amount_to_transfer = "0".to_string();
         }
 
-        if ui.button("Visit loan shark").clicked() {
+        if ui.button("Launder money").clicked() {
+            self.current_screen = Screen::Fronts;
+            self.amount_to_launder = "0".to_string();
+        }
+
+        if self.service_button(ui, "Visit loan shark", Service::LoanShark) {
             self.current_screen = Screen::LoanShark;
             self.amount_to_borrow_repay = "0".to_string();
         }
 
-        if ui.button("Go to hospital").clicked() {
+        if self.service_button(ui, "Go to the bank", Service::Bank) {
+            self.current_screen = Screen::Bank;
+            self.amount_to_bank = "0".to_string();
+        }
+
+        if self.service_button(ui, "Go to hospital", Service::Hospital) {
             self.current_screen = Screen::Hospital;
         }
 
@@ -661,6 +831,26 @@ impl DopeWarsApp {
             self.current_screen = Screen::PriceChart;
         }
 
+        if ui.button("Read the news").clicked() {
+            self.current_screen = Screen::News;
+        }
+
+        if ui.button("Count your net worth").clicked() {
+            self.current_screen = Screen::NetWorth;
+        }
+
+        if ui.button("Start a new game").clicked() {
+            self.current_screen = Screen::NewGame;
+        }
+
+        if ui.button("View high scores").clicked() {
+            self.current_screen = Screen::HighScores;
+        }
+
+        if ui.button("Watch a replay").clicked() {
+            self.open_replays();
+        }
+
         if ui.button("Settings").clicked() {
             self.current_screen = Screen::Settings;
         }
@@ -668,17 +858,17 @@ impl DopeWarsApp {
 
     fn render_buy_screen(&mut self, ui: &mut Ui) {
         ui.heading("Buy Substances");
-        ui.label(format!("Cash: ${}", self.player.cash));
+        ui.label(format!("Cash: ${}", self.engine.player.cash));
         ui.label(format!(
             "Space available: {}",
-            self.player.space_available()
+            self.engine.player.space_available()
         ));
 
         ui.separator();
 
-        for substance in SUBSTANCES.iter() {
+        for substance in self.substance_names().iter() {
             let substance_str = substance.to_string();
-            let price = self.market.prices.get(&substance_str).unwrap_or(&0);
+            let price = self.engine.local_price(substance);
 
             let is_selected = match &self.selected_substance {
                 Some(selected) => selected == &substance_str,
@@ -696,8 +886,8 @@ impl DopeWarsApp {
 
         ui.separator();
 
-        if let Some(ref substance) = self.selected_substance {
-            let price = self.market.prices.get(substance).unwrap_or(&0);
+        if let Some(substance) = self.selected_substance.clone() {
+            let price = self.engine.local_price(&substance);
 
             ui.label(format!("Selected: {} - ${} per unit", substance, price));
 
@@ -706,49 +896,37 @@ impl DopeWarsApp {
                 ui.text_edit_singleline(&mut self.amount_to_trade);
 
                 if ui.button("Max").clicked() {
-                    let max_affordable = self.player.cash / price;
-                    let max_space = self.player.space_available();
-                    let max_amount = std::cmp::min(max_affordable, max_space);
-                    self.amount_to_trade = max_amount.to_string();
+                    self.amount_to_trade = self.engine.max_buy(&substance).to_string();
                 }
             });
 
-            let amount: i32 = match self.amount_to_trade.parse() {
-                Ok(num) => num,
-                Err(_) => 0,
-            };
+            let amount: i32 = self.amount_to_trade.parse().unwrap_or(0);
 
-            let total_cost = amount * price;
+            let total_cost = if amount > 0 {
+                self.engine.quote_buy(&substance, amount)
+            } else {
+                0
+            };
             ui.label(format!("Total cost: ${}", total_cost));
-
-            let can_buy = amount > 0
-                && amount <= self.player.space_available()
-                && total_cost <= self.player.cash;
-
-            if !can_buy {
-                let mut reason = "Cannot buy: ".to_string();
-                if amount <= 0 {
-                    reason.push_str("Amount must be positive. ");
-                }
-                if amount > self.player.space_available() {
-                    reason.push_str("Not enough space. ");
-                }
-                if total_cost > self.player.cash {
-                    reason.push_str("Not enough cash. ");
-                }
-                ui.label(RichText::new(reason).color(Color32::RED));
+            if amount > 0 {
+                ui.label(format!(
+                    "Buying this many will push the price up to ${}",
+                    self.engine.price_after_trade(&substance, amount)
+                ));
             }
 
-            if ui.add_enabled(can_buy, egui::Button::new("Buy")).clicked() {
-                self.player.cash -= total_cost;
-                *self.player.inventory.entry(substance.clone()).or_insert(0) += amount;
+            let problems = self.engine.check_buy(&substance, amount);
+            let can_buy = problems.is_empty();
 
-                let message = format!(
-                    "Bought {} units of {} for ${}",
-                    amount, substance, total_cost
+            if !can_buy {
+                ui.label(
+                    RichText::new(describe_problems("Cannot buy: ", &problems)).color(Color32::RED),
                 );
-                self.message_log.push(message);
+            }
 
+            if ui.add_enabled(can_buy, egui::Button::new("Buy")).clicked()
+                && self.engine.buy(&substance, amount).is_ok()
+            {
                 self.selected_substance = None;
                 self.amount_to_trade = "0".to_string();
             }
@@ -759,16 +937,22 @@ impl DopeWarsApp {
 
     fn render_sell_screen(&mut self, ui: &mut Ui) {
         ui.heading("Sell Substances");
-        ui.label(format!("Cash: ${}", self.player.cash));
+        ui.label(cash_text(&self.engine));
+        ui.label("What you make selling is dirty money until it's laundered through a front.");
 
         ui.separator();
 
         let mut has_inventory = false;
 
-        for substance in SUBSTANCES.iter() {
+        for substance in self.substance_names().iter() {
             let substance_str = substance.to_string();
-            let price = self.market.prices.get(&substance_str).unwrap_or(&0);
-            let amount = self.player.inventory.get(&substance_str).unwrap_or(&0);
+            let price = self.engine.local_price(substance);
+            let amount = self
+                .engine
+                .player
+                .inventory
+                .get(&substance_str)
+                .unwrap_or(&0);
 
             if *amount > 0 {
                 has_inventory = true;
@@ -797,9 +981,9 @@ impl DopeWarsApp {
 
         ui.separator();
 
-        if let Some(ref substance) = self.selected_substance {
-            let price = self.market.prices.get(substance).unwrap_or(&0);
-            let current_amount = self.player.inventory.get(substance).unwrap_or(&0);
+        if let Some(substance) = self.selected_substance.clone() {
+            let price = self.engine.local_price(&substance);
+            let current_amount = *self.engine.player.inventory.get(&substance).unwrap_or(&0);
 
             ui.label(format!("Selected: {} - ${} per unit", substance, price));
 
@@ -812,40 +996,36 @@ impl DopeWarsApp {
                 }
             });
 
-            let amount: i32 = match self.amount_to_trade.parse() {
-                Ok(num) => num,
-                Err(_) => 0,
-            };
+            let amount: i32 = self.amount_to_trade.parse().unwrap_or(0);
 
-            let total_earned = amount * price;
+            let total_earned = if amount > 0 {
+                self.engine.quote_sell(&substance, amount)
+            } else {
+                0
+            };
             ui.label(format!("Total earned: ${}", total_earned));
+            if amount > 0 {
+                ui.label(format!(
+                    "Selling this many will push the price down to ${}",
+                    self.engine.price_after_trade(&substance, -amount)
+                ));
+            }
 
-            let can_sell = amount > 0 && amount <= *current_amount;
+            let problems = self.engine.check_sell(&substance, amount);
+            let can_sell = problems.is_empty();
 
             if !can_sell {
-                let mut reason = "Cannot sell: ".to_string();
-                if amount <= 0 {
-                    reason.push_str("Amount must be positive. ");
-                }
-                if amount > *current_amount {
-                    reason.push_str("Not enough inventory. ");
-                }
-                ui.label(RichText::new(reason).color(Color32::RED));
+                ui.label(
+                    RichText::new(describe_problems("Cannot sell: ", &problems))
+                        .color(Color32::RED),
+                );
             }
 
             if ui
                 .add_enabled(can_sell, egui::Button::new("Sell"))
                 .clicked()
+                && self.engine.sell(&substance, amount).is_ok()
             {
-                self.player.cash += total_earned;
-                *self.player.inventory.get_mut(substance).unwrap() -= amount;
-
-                let message = format!(
-                    "Sold {} units of {} for ${}",
-                    amount, substance, total_earned
-                );
-                self.message_log.push(message);
-
                 self.selected_substance = None;
                 self.amount_to_trade = "0".to_string();
             }
@@ -860,791 +1040,521 @@ impl DopeWarsApp {
 
         ui.separator();
 
-        for location in LOCATIONS.iter() {
-            if *location != self.player.current_location {
-                if ui.button(*location).clicked() {
-                    self.travel_to(location);
-                }
+        for location in self.location_names().iter() {
+            if *location != self.engine.player.current_location
+                && ui.button(location.as_str()).clicked()
+            {
+                self.current_screen = match self.engine.travel(location) {
+                    Ok(TravelOutcome::PoliceStop) => Screen::PoliceEncounter,
+                    Ok(TravelOutcome::Arrived) | Err(_) => Screen::Main,
+                };
             }
         }
     }
 
-    fn travel_to(&mut self, new_location: &str) {
-        let mut rng = rand::thread_rng();
-        let random_event = rng.gen_range(0..12); // Increased range for more event types
-
-        self.message_log
-            .push(format!("Traveling to {}...", new_location));
-
-        match random_event {
-            0 => {
-                // Trigger the police encounter screen
-                self.message_log
-                    .push("🚨 You've been stopped by the police!".to_string());
-                self.current_screen = Screen::PoliceEncounter;
-                return; // Exit early - don't continue with the travel until the encounter is resolved
-            }
-            1 => {
-                let message = "🔫 You were mugged! You lost some cash.".to_string();
-                self.message_log.push(message);
-
-                // Check if player has a weapon for defense
-                if self.player.has_weapon() && self.player.active_weapon.is_some() {
-                    let weapon_power = self.player.get_active_weapon_power();
-                    let defend_chance = 30 + (weapon_power / 2);
-
-                    if rng.gen_range(0..100) < defend_chance {
-                        self.message_log.push(format!(
-                            "You used your {} to fight off the muggers!",
-                            self.player.active_weapon.as_ref().unwrap()
-                        ));
-
-                        // Still might get hurt
-                        let damage = rng.gen_range(0..10);
-                        if damage > 0 {
-                            self.player.health -= damage;
-                            self.message_log
-                                .push(format!("You got hurt in the fight (-{} health)", damage));
-                        }
-
-                        return;
-                    }
-                }
-
-                let lost = rng.gen_range(100..500).min(self.player.cash);
-                self.player.cash -= lost;
-
-                let message = format!("You lost ${}", lost);
-                self.message_log.push(message);
-            }
-            2 => {
-                let message = "🏥 You got injured during travel!".to_string();
-                self.message_log.push(message);
+    fn render_police_encounter(&mut self, ui: &mut Ui) {
+        ui.add_space(20.0);
 
-                let damage = rng.gen_range(5..20);
-                self.player.health -= damage;
+        if self.engine.police_encounter().is_none() {
+            ui.vertical_centered(|ui| {
+                ui.heading("🚨 POLICE ENCOUNTER 🚨");
+                ui.label("The police are gone.");
+                if ui.button("Continue").clicked() {
+                    self.current_screen = Screen::Main;
+                }
+            });
+            return;
+        }
 
-                let message = format!("You lost {} health points", damage);
-                self.message_log.push(message);
+        ui.vertical_centered(|ui| {
+            ui.heading("🚨 POLICE ENCOUNTER 🚨");
 
-                if self.player.health <= 0 {
-                    self.player.health = 0;
-                    self.message_log
-                        .push("You're severely injured and need medical attention!".to_string());
+            match self.render_police_stop(ui) {
+                // Once the stop is settled the trip goes ahead; the log says how it went
+                Some(Ok(state)) if state.is_over() => self.current_screen = Screen::Main,
+                Some(Ok(PoliceState::Negotiating)) => {
+                    // Start haggling from what the officer asked for
+                    if let Some(negotiation) = self.engine.bribe_negotiation() {
+                        self.bribe_amount = negotiation.asking_price.to_string();
+                    }
+                    self.current_screen = Screen::BribeNegotiation;
                 }
+                _ => {}
             }
-            3 => {
-                // Substance price crash
-                let substance = SUBSTANCES[rng.gen_range(0..SUBSTANCES.len())].to_string();
-                let message = format!("📰 Market flooded with {}! Prices have crashed!", substance);
-                self.message_log.push(message);
+        });
+    }
 
-                if let Some(price) = self.market.prices.get_mut(&substance) {
-                    *price = *price / 3;
-                }
-            }
-            4 => {
-                // Substance price spike
-                let substance = SUBSTANCES[rng.gen_range(0..SUBSTANCES.len())].to_string();
-                let message = format!(
-                    "📰 Police busted a {} shipment! Prices skyrocketing!",
-                    substance
-                );
-                self.message_log.push(message);
+    fn render_police_stop(&mut self, ui: &mut Ui) -> Option<Result<PoliceState, GameError>> {
+        ui.label("You've been stopped by the police!");
 
-                if let Some(price) = self.market.prices.get_mut(&substance) {
-                    *price = *price * 3;
-                }
-            }
-            5 => {
-                // Find some drugs
-                let substance = SUBSTANCES[rng.gen_range(0..SUBSTANCES.len())].to_string();
-                let amount = rng.gen_range(1..5);
+        ui.add_space(10.0);
+        if self.engine.player.total_items() == 0 {
+            ui.label("You aren't carrying any illegal substances.");
+        } else {
+            ui.label("The officer wants to search you. You're carrying:");
 
-                if self.player.space_available() >= amount {
-                    *self.player.inventory.entry(substance.clone()).or_insert(0) += amount;
-                    let message = format!(
-                        "Lucky! You found {} units of {} on the ground!",
-                        amount, substance
-                    );
-                    self.message_log.push(message);
-                } else {
-                    self.message_log
-                        .push("You found some drugs but had no space to carry them.".to_string());
+            for substance in self.substance_names().iter() {
+                let amount = self
+                    .engine
+                    .player
+                    .inventory
+                    .get(substance)
+                    .copied()
+                    .unwrap_or(0);
+                if amount > 0 {
+                    ui.label(format!("- {} units of {}", amount, substance));
                 }
             }
-            _ => {
-                self.message_log.push("Journey was uneventful.".to_string());
-            }
         }
 
-        self.player.current_location = new_location.to_string();
-        self.player.day += 1;
-        self.player.debt = (self.player.debt as f32 * (1.0 + LOAN_SHARK_INTEREST)) as i32;
-
-        let message = format!("You've arrived at {}.", new_location);
-        self.message_log.push(message);
-
-        let message = format!(
-            "Your debt has increased to ${} due to interest.",
-            self.player.debt
-        );
-        self.message_log.push(message);
+        ui.add_space(20.0);
 
-        // Check for possible stash house raids
-        self.check_stash_house_raid();
+        let mut result = None;
+        ui.horizontal(|ui| {
+            if ui.button("Fight").clicked() {
+                result = Some(self.engine.police_fight());
+            }
 
-        // Generate new market prices for the new location
-        self.market.generate_prices();
+            if ui.button("Run").clicked() {
+                result = Some(self.engine.police_run());
+            }
 
-        // Add market events to message log
-        for event in &self.market.events {
-            self.message_log.push(event.clone());
-        }
+            if ui.button("Offer Bribe").clicked() {
+                result = Some(self.engine.police_negotiate());
+            }
 
-        // Return to main screen
-        self.current_screen = Screen::Main;
+            if ui.button("Let Them Search You").clicked() {
+                result = Some(self.engine.police_surrender());
+            }
+        });
+        result
     }
 
-    fn check_stash_house_raid(&mut self) {
-        let mut rng = rand::thread_rng();
+    fn render_bribe_negotiation_screen(&mut self, ui: &mut Ui) {
+        ui.add_space(20.0);
 
-        // Only check if player has at least one stash house
-        if self.player.stash_houses.is_empty() {
+        let Some(negotiation) = self.engine.bribe_negotiation().cloned() else {
+            self.current_screen = Screen::PoliceEncounter;
             return;
-        }
-
-        // 5% chance of a raid per travel if player has stash houses
-        if rng.gen_range(0..100) < 5 {
-            // Determine which stash house gets raided (if player has multiple)
-            let stash_locations: Vec<String> = self.player.stash_houses.keys().cloned().collect();
-            let raided_location = &stash_locations[rng.gen_range(0..stash_locations.len())];
+        };
 
-            self.message_log.push(format!(
-                "🚨 ALERT! Your stash house in {} was raided by police!",
-                raided_location
+        let mut result = None;
+        ui.vertical_centered(|ui| {
+            ui.heading("💰 BRIBE NEGOTIATION 💰");
+            ui.label(format!(
+                "You're carrying inventory worth approximately ${}",
+                self.engine.calculate_inventory_value()
             ));
 
-            // Check if the stash has anything in it
-            if let Some(stash) = self.player.stash_houses.get_mut(raided_location) {
-                let has_substances = stash.inventory.values().sum::<i32>() > 0;
-
-                if has_substances {
-                    // Determine how much is confiscated (50-100%)
-                    let confiscation_percentage = rng.gen_range(50..=100);
-
-                    self.message_log.push(format!(
-                        "Police confiscated {}% of your stash!",
-                        confiscation_percentage
-                    ));
-
-                    // Apply confiscation to each substance
-                    for (_, amount) in stash.inventory.iter_mut() {
-                        if *amount > 0 {
-                            let confiscated = (*amount * confiscation_percentage) / 100;
-                            *amount -= confiscated;
-                        }
-                    }
-
-                    // Potential fine
-                    let fine = rng.gen_range(1000..5000).min(self.player.cash);
-                    if fine > 0 {
-                        self.player.cash -= fine;
-                        self.message_log
-                            .push(format!("You were fined ${} for the illegal stash!", fine));
-                    }
-                } else {
-                    self.message_log.push(
-                        "Luckily, your stash was empty so nothing was confiscated!".to_string(),
-                    );
-                }
-
-                // Small chance (10%) the stash house is seized entirely
-                if rng.gen_range(0..100) < 10 {
-                    self.message_log.push(format!(
-                        "Your stash house in {} was seized by authorities!",
-                        raided_location
-                    ));
-                    self.player.stash_houses.remove(raided_location);
-                }
-            }
-        }
-    }
-
-    fn handle_police_encounter(&mut self) -> bool {
-        let mut rng = rand::thread_rng();
-
-        // Check if the player has any drugs
-        let has_drugs = self.player.inventory.values().sum::<i32>() > 0;
-        if !has_drugs {
-            self.message_log.push(
-                "The cops let you go since you aren't carrying anything illegal.".to_string(),
+            ui.add_space(10.0);
+            ui.label(
+                RichText::new(format!("The officer wants ${}", negotiation.asking_price))
+                    .strong()
+                    .color(Color32::YELLOW),
             );
-            return false; // No drugs, no problem
-        }
-
-        // Police encounter dialog
-        self.message_log
-            .push("🚨 POLICE! You've been stopped for a search!".to_string());
-
-        // Let's avoid using clone() for Screen enum
-        // Create a custom dialog for police encounter
-        let mut encounter_resolved = false;
-
-        // Fight outcome
-        let weapon_power = self.player.get_active_weapon_power();
-        let fight_chance = if weapon_power > 0 {
-            // Base chance plus weapon bonus
-            20 + (weapon_power as f32 * 0.7) as i32
-        } else {
-            // Base chance with no weapon
-            10
-        };
-
-        // Use a Grenade if equipped (one-time use)
-        let using_grenade = if let Some(active_weapon) = &self.player.active_weapon {
-            active_weapon == "Grenade"
-        } else {
-            false
-        };
-
-        // Get the substance with the most quantity
-        let mut max_substance = String::new();
-        let mut max_amount = 0;
-
-        for (substance, amount) in &self.player.inventory {
-            if *amount > max_amount {
-                max_amount = *amount;
-                max_substance = substance.clone();
-            }
-        }
-
-        if using_grenade {
-            // Grenade is extremely effective but one-time use
-            let success = rng.gen_range(0..100) < 95; // 95% chance to escape
 
-            if success {
-                self.message_log
-                    .push("💥 You threw a grenade and escaped in the chaos!".to_string());
-
-                // Remove the grenade
-                if let Some(amount) = self.player.weapons.get_mut("Grenade") {
-                    *amount -= 1;
-                }
-
-                // Unequip the grenade
-                if self.player.active_weapon == Some("Grenade".to_string()) {
-                    self.player.active_weapon = None;
-                }
-
-                return false; // Escaped successfully
+            let greed_text = if negotiation.greed > 0.6 {
+                "This one looks greedy. Don't expect them to come down much."
             } else {
-                self.message_log.push(
-                    "💥 Your grenade didn't go off properly! The police arrested you!".to_string(),
-                );
-                self.confiscate_substances();
-
-                // Remove the grenade
-                if let Some(amount) = self.player.weapons.get_mut("Grenade") {
-                    *amount -= 1;
-                }
+                "This one seems willing to meet you partway."
+            };
+            ui.label(greed_text);
 
-                // Unequip the grenade
-                if self.player.active_weapon == Some("Grenade".to_string()) {
-                    self.player.active_weapon = None;
-                }
+            let patience_text = match negotiation.patience {
+                1 => RichText::new("The officer's patience is wearing thin!").color(Color32::RED),
+                2 => RichText::new("The officer is getting impatient.").color(Color32::YELLOW),
+                _ => RichText::new("The officer seems in no hurry.").color(Color32::GREEN),
+            };
+            ui.label(patience_text);
 
-                return true; // Caught
+            if !negotiation.offers.is_empty() {
+                let offers: Vec<String> = negotiation
+                    .offers
+                    .iter()
+                    .map(|offer| format!("${}", offer))
+                    .collect();
+                ui.label(format!("Your offers so far: {}", offers.join(", ")));
             }
-        }
 
-        // Determine if the player fights, runs, or bribes
-        let encounter_type = rng.gen_range(0..3);
+            ui.add_space(10.0);
+            ui.label(format!("Cash: ${}", self.engine.player.cash));
 
-        match encounter_type {
-            0 => {
-                // Fight
-                let success = rng.gen_range(0..100) < fight_chance;
+            ui.horizontal(|ui| {
+                ui.label("Your offer: $");
+                ui.text_edit_singleline(&mut self.bribe_amount);
+            });
 
-                if success {
-                    if weapon_power > 0 {
-                        self.message_log.push(format!(
-                            "You used your {} to fight off the cops and escaped!",
-                            self.player
-                                .active_weapon
-                                .as_ref()
-                                .unwrap_or(&"weapon".to_string())
-                        ));
-                    } else {
-                        self.message_log.push("You somehow managed to fight off the cops with your bare hands and escaped!".to_string());
-                    }
+            let bribe: i32 = self.bribe_amount.parse().unwrap_or(0);
 
-                    // Take some damage
-                    let damage = rng.gen_range(5..20);
-                    self.player.health -= damage;
-                    self.message_log
-                        .push(format!("You got hurt in the fight (-{} health)", damage));
-
-                    if self.player.health <= 0 {
-                        self.player.health = 1; // Don't let player die from police fight
-                        self.message_log.push(
-                            "You're critically injured and need medical attention immediately!"
-                                .to_string(),
-                        );
-                    }
+            let can_bribe = bribe > 0 && bribe <= self.engine.player.cash;
 
-                    return false; // Escaped
-                } else {
-                    if weapon_power > 0 {
-                        self.message_log.push(format!("Even with your {}, you couldn't fight off the cops. You've been arrested!",
-                            self.player.active_weapon.as_ref().unwrap_or(&"weapon".to_string())));
-                    } else {
-                        self.message_log.push("You tried to fight the cops with your bare hands and failed miserably! You've been arrested!".to_string());
-                    }
-
-                    self.confiscate_substances();
-
-                    // Take more damage for failing
-                    let damage = rng.gen_range(15..40);
-                    self.player.health -= damage;
-                    self.message_log
-                        .push(format!("The cops roughed you up (-{} health)", damage));
+            if bribe <= 0 {
+                ui.label(RichText::new("Enter a positive amount").color(Color32::RED));
+            } else if bribe > self.engine.player.cash {
+                ui.label(RichText::new("You don't have that much cash").color(Color32::RED));
+            }
 
-                    if self.player.health <= 0 {
-                        self.player.health = 1; // Don't let player die from police fight
-                        self.message_log.push(
-                            "You're critically injured and need medical attention immediately!"
-                                .to_string(),
-                        );
-                    }
+            ui.add_space(10.0);
 
-                    return true; // Caught
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(can_bribe, egui::Button::new("Make Offer"))
+                    .clicked()
+                {
+                    result = Some(self.engine.offer_bribe(bribe));
                 }
-            }
-            1 => {
-                // Run
-                let run_chance = 30 + (self.player.health / 4); // Health affects running ability
-                let success = rng.gen_range(0..100) < run_chance;
 
-                if success {
-                    self.message_log
-                        .push("You outran the cops and got away!".to_string());
-                    return false; // Escaped
-                } else {
-                    self.message_log.push(
-                        "You tried to run but the cops caught you! You've been arrested!"
-                            .to_string(),
-                    );
-                    self.confiscate_substances();
-                    return true; // Caught
+                let can_pay = negotiation.asking_price <= self.engine.player.cash;
+                if ui
+                    .add_enabled(
+                        can_pay,
+                        egui::Button::new(format!("Pay ${}", negotiation.asking_price)),
+                    )
+                    .clicked()
+                {
+                    result = Some(self.engine.offer_bribe(negotiation.asking_price));
                 }
-            }
-            _ => {
-                // Bribe opportunity
-                self.message_log.push(
-                    "The cop hints that this could all go away for the right price...".to_string(),
-                );
-
-                // Let's determine a reasonable bribe amount based on what the player is carrying
-                let total_value = self.calculate_inventory_value();
-                let min_bribe = total_value / 4;
-                let ideal_bribe = total_value / 2;
-
-                // Bribe success depends on the ratio of offered bribe to ideal bribe
-                let bribe_amount = rng.gen_range(min_bribe..=ideal_bribe);
-                let bribe_success = rng.gen_range(0..100) < 70; // 70% chance of success
-
-                if self.player.cash >= bribe_amount && bribe_success {
-                    self.player.cash -= bribe_amount;
-                    self.message_log.push(format!(
-                        "You slipped the cop ${} and they let you go.",
-                        bribe_amount
-                    ));
-                    return false; // Bribe worked
-                } else {
-                    if self.player.cash < bribe_amount {
-                        self.message_log.push(format!(
-                            "The cop wanted ${} but you couldn't afford it!",
-                            bribe_amount
-                        ));
-                    } else {
-                        self.message_log.push(format!(
-                            "You offered ${} but the cop refused the bribe!",
-                            bribe_amount
-                        ));
-                    }
 
-                    self.message_log
-                        .push("You've been arrested and your substances confiscated!".to_string());
-                    self.confiscate_substances();
-                    return true; // Caught
+                if ui.button("Refuse to Bribe").clicked() {
+                    result = Some(self.engine.refuse_bribe());
                 }
-            }
-        }
-    }
-
-    fn confiscate_substances(&mut self) {
-        // Confiscate all substances
-        for (_, amount) in self.player.inventory.iter_mut() {
-            *amount = 0;
-        }
-
-        self.message_log
-            .push("All your substances have been confiscated!".to_string());
-    }
-
-    fn calculate_inventory_value(&self) -> i32 {
-        let mut total = 0;
+            });
+        });
 
-        for (substance, amount) in &self.player.inventory {
-            if let Some(price) = self.market.prices.get(substance) {
-                total += price * amount;
+        // Once the stop is settled the trip goes ahead; the log says how it went
+        if let Some(Ok(state)) = result {
+            if state.is_over() {
+                self.bribe_amount = "0".to_string();
+                self.current_screen = Screen::Main;
             }
         }
-
-        total
     }
 
-    fn render_police_encounter(&mut self, ui: &mut Ui) {
+    fn render_jail_screen(&mut self, ui: &mut Ui) {
         ui.add_space(20.0);
 
-        let mut rng = rand::thread_rng();
+        let Some(custody) = self.engine.custody().cloned() else {
+            self.current_screen = Screen::Main;
+            return;
+        };
 
+        let mut result = None;
         ui.vertical_centered(|ui| {
-            ui.heading("🚨 POLICE ENCOUNTER 🚨");
-            ui.label("You've been stopped by the police!");
-
-            let total_drugs = self.player.inventory.values().sum::<i32>();
-
-            if total_drugs == 0 {
-                ui.label("You aren't carrying any illegal substances.");
-                ui.label("The officer lets you go with a warning.");
-
-                if ui.button("Continue").clicked() {
-                    self.current_screen = Screen::Main;
-                }
-                return;
-            }
+            ui.heading("🔒 IN CUSTODY 🔒");
+            ui.label("You've been arrested and booked at the precinct.");
 
             ui.add_space(10.0);
-            ui.label("The officer wants to search you. You're carrying:");
+            let record = &self.engine.player.record;
+            ui.label(format!(
+                "Criminal record: {} arrests, {} convictions, {} days served",
+                record.arrests, record.convictions, record.days_in_jail
+            ));
+            ui.label(format!(
+                "Heat: {} ({})",
+                self.engine.player.heat,
+                self.engine.player.heat_description()
+            ));
+            ui.label("The judge will weigh both when your case comes to court.");
 
-            for (substance, amount) in &self.player.inventory {
-                if *amount > 0 {
-                    ui.label(format!("- {} units of {}", amount, substance));
-                }
-            }
+            ui.add_space(10.0);
+            ui.label(
+                RichText::new(format!("Bail: ${}", custody.bail))
+                    .strong()
+                    .color(Color32::YELLOW),
+            );
+            ui.label(format!(
+                "Without bail you'll wait {} days in a cell for your hearing.",
+                custody.days_until_trial
+            ));
+            ui.label(format!(
+                "Cash: ${}   Debt: ${}",
+                self.engine.player.cash, self.engine.player.debt
+            ));
 
             ui.add_space(20.0);
 
             ui.horizontal(|ui| {
-                let fight_outcome = if ui.button("Fight").clicked() {
-                    let weapon_power = self.player.get_active_weapon_power();
-                    let fight_chance = if weapon_power > 0 {
-                        20 + (weapon_power as f32 * 0.7) as i32
-                    } else {
-                        10
-                    };
-
-                    let success = rng.gen_range(0..100) < fight_chance;
-
-                    if success {
-                        if weapon_power > 0 {
-                            self.message_log.push(format!("You used your {} to fight off the cops and escaped!",
-                                self.player.active_weapon.as_ref().unwrap_or(&"weapon".to_string())));
-                        } else {
-                            self.message_log.push("You somehow managed to fight off the cops with your bare hands and escaped!".to_string());
-                        }
-
-                        // Take some damage
-                        let damage = rng.gen_range(5..20);
-                        self.player.health -= damage;
-                        self.message_log.push(format!("You got hurt in the fight (-{} health)", damage));
-
-                        if self.player.health <= 0 {
-                            self.player.health = 1; // Don't let player die from police fight
-                            self.message_log.push("You're critically injured and need medical attention immediately!".to_string());
-                        }
-
-                        self.current_screen = Screen::Main;
-                        true
-                    } else {
-                        if weapon_power > 0 {
-                            self.message_log.push(format!("Even with your {}, you couldn't fight off the cops. You've been arrested!",
-                                self.player.active_weapon.as_ref().unwrap_or(&"weapon".to_string())));
-                        } else {
-                            self.message_log.push("You tried to fight the cops with your bare hands and failed miserably! You've been arrested!".to_string());
-                        }
-
-                        self.confiscate_substances();
-
-                        // Take more damage for failing
-                        let damage = rng.gen_range(15..40);
-                        self.player.health -= damage;
-                        self.message_log.push(format!("The cops roughed you up (-{} health)", damage));
-
-                        if self.player.health <= 0 {
-                            self.player.health = 1; // Don't let player die from police fight
-                            self.message_log.push("You're critically injured and need medical attention immediately!".to_string());
-                        }
-
-                        self.current_screen = Screen::Main;
-                        false
-                    }
-                } else {
-                    false
-                };
-
-                let run_outcome = if ui.button("Run").clicked() {
-                    let run_chance = 30 + (self.player.health / 4); // Health affects running ability
-                    let success = rng.gen_range(0..100) < run_chance;
+                let can_post = custody.bail <= self.engine.player.cash;
+                if ui
+                    .add_enabled(
+                        can_post,
+                        egui::Button::new(format!("Post Bail (${})", custody.bail)),
+                    )
+                    .clicked()
+                {
+                    result = Some(self.engine.post_bail());
+                }
 
-                    if success {
-                        self.message_log.push("You outran the cops and got away!".to_string());
-                        self.current_screen = Screen::Main;
-                        true
-                    } else {
-                        self.message_log.push("You tried to run but the cops caught you! You've been arrested!".to_string());
-                        self.confiscate_substances();
-                        self.current_screen = Screen::Main;
-                        false
-                    }
+                let refusal = if self.engine.player.loan.barred {
+                    "The loan shark won't lend you anything until you pay up".to_string()
                 } else {
-                    false
+                    format!(
+                        "The loan shark will only lend you ${} more",
+                        self.engine.credit_available()
+                    )
                 };
-
-                let bribe_clicked = ui.button("Offer Bribe").clicked();
-                if bribe_clicked {
-                    // Handle the bribe logic
-                    let bribe_successful = self.offer_bribe(ui);
-                    if bribe_successful {
-                        self.current_screen = Screen::Main;
-                    } else {
-                        self.current_screen = Screen::Main;
-                    }
+                if ui
+                    .add_enabled(
+                        custody.bail <= self.engine.credit_available(),
+                        egui::Button::new("Call the Loan Shark"),
+                    )
+                    .on_hover_text("The loan shark pays your bail and adds it to your debt")
+                    .on_disabled_hover_text(refusal)
+                    .clicked()
+                {
+                    result = Some(self.engine.borrow_bail());
                 }
 
-                let surrender_outcome = if ui.button("Surrender").clicked() {
-                    self.message_log.push("You surrender to the police.".to_string());
-                    self.confiscate_substances();
-
-                    // Sometimes you get a fine
-                    let fine_chance = rng.gen_bool(0.7); // 70% chance of fine
-                    if fine_chance {
-                        let fine = rng.gen_range(500..2000).min(self.player.cash);
-                        if fine > 0 {
-                            self.player.cash -= fine;
-                            self.message_log.push(format!("You were fined ${} for possession!", fine));
-                        }
-                    }
-
-                    self.current_screen = Screen::Main;
-                    false
-                } else {
-                    false
-                };
+                if ui
+                    .button(format!("Wait {} Days for Trial", custody.days_until_trial))
+                    .clicked()
+                {
+                    result = Some(self.engine.await_trial());
+                }
             });
         });
+
+        // The verdict is in the log; the trip that was interrupted is over
+        if let Some(Ok(_)) = result {
+            self.current_screen = Screen::Main;
+        }
     }
 
-    fn offer_bribe(&mut self, ui: &mut Ui) -> bool {
-        let mut rng = rand::thread_rng();
+    fn render_loan_shark_screen(&mut self, ui: &mut Ui) {
+        ui.heading("Loan Shark");
+        ui.label(format!("Current debt: ${}", self.engine.player.debt));
+        ui.label(format!("Current cash: ${}", self.engine.player.cash));
 
-        ui.heading("🚨 POLICE ENCOUNTER");
-        ui.label("The officer seems open to a bribe...");
+        ui.separator();
 
-        let total_value = self.calculate_inventory_value();
-        let suggested_bribe = total_value / 3;
+        self.render_loan_terms(ui);
 
-        ui.label(format!(
-            "You're carrying inventory worth approximately ${}",
-            total_value
-        ));
-        ui.label(format!("Suggested bribe: ${}", suggested_bribe));
+        if !self.service_available(ui, Service::LoanShark) {
+            return;
+        }
+
+        ui.separator();
 
         ui.horizontal(|ui| {
-            ui.label("Bribe amount: $");
-            ui.text_edit_singleline(&mut self.bribe_amount);
+            ui.label("Amount:");
+            ui.text_edit_singleline(&mut self.amount_to_borrow_repay);
+            let due = self.engine.player.loan.payment_due();
+            if due > 0 && ui.button("Payment Due").clicked() {
+                self.amount_to_borrow_repay = due.min(self.engine.player.cash).to_string();
+            }
         });
 
-        let bribe: i32 = match self.bribe_amount.parse() {
-            Ok(num) => num,
-            Err(_) => 0,
-        };
+        let amount: i32 = self.amount_to_borrow_repay.parse().unwrap_or(0);
 
-        let can_bribe = bribe > 0 && bribe <= self.player.cash;
+        let borrow_problems = self.engine.check_borrow(amount);
+        let repay_problems = self.engine.check_repay(amount);
+        let can_borrow = borrow_problems.is_empty();
+        let can_repay = repay_problems.is_empty();
 
-        if !can_bribe {
-            if bribe <= 0 {
-                ui.label(RichText::new("Enter a positive amount").color(Color32::RED));
-            } else if bribe > self.player.cash {
-                ui.label(RichText::new("You don't have that much cash").color(Color32::RED));
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(can_borrow, egui::Button::new("Borrow"))
+                .clicked()
+                && self.engine.borrow(amount).is_ok()
+            {
+                self.amount_to_borrow_repay = "0".to_string();
             }
-        }
 
-        let bribe_offered = ui
-            .add_enabled(can_bribe, egui::Button::new("Offer Bribe"))
-            .clicked();
-        let refuse = ui.button("Refuse to Bribe").clicked();
+            if ui
+                .add_enabled(can_repay, egui::Button::new("Repay"))
+                .clicked()
+                && self.engine.repay(amount).is_ok()
+            {
+                self.amount_to_borrow_repay = "0".to_string();
+            }
+        });
 
-        if bribe_offered {
-            // Calculate success chance based on bribe amount relative to inventory value
-            let ratio = bribe as f32 / total_value as f32;
-            let success_chance = (ratio * 100.0).min(95.0).max(10.0) as i32;
+        if !can_borrow {
+            ui.label(
+                RichText::new(describe_problems("Cannot borrow: ", &borrow_problems))
+                    .color(Color32::RED),
+            );
+        }
 
-            let success = rng.gen_range(0..100) < success_chance;
+        if !can_repay {
+            ui.label(
+                RichText::new(describe_problems("Cannot repay: ", &repay_problems))
+                    .color(Color32::RED),
+            );
+        }
 
-            self.player.cash -= bribe;
+        ui.separator();
+        ui.label(format!(
+            "Daily interest rate: {}%",
+            (self.engine.rules().loan_shark_interest * 100.0).round()
+        ));
+        ui.label("Your debt increases every time you travel to a new location.");
+        ui.label(format!(
+            "Every {} days he wants {}% of what you owed at the start of them back. Fall {} days \
+             behind and his enforcers come looking; {} days and he stops lending until you're \
+             paid up.",
+            TERM_DAYS, MINIMUM_PAYMENT_PERCENT, ENFORCERS_AFTER_DAYS, BARRED_AFTER_DAYS
+        ));
+    }
+
+    fn render_loan_terms(&self, ui: &mut Ui) {
+        let loan = &self.engine.player.loan;
+        let overdue = loan.days_overdue(self.engine.player.day);
 
-            if success {
-                self.message_log.push(format!(
-                    "You offered ${} as a bribe and the officer accepted!",
-                    bribe
+        match loan.due_day {
+            Some(due) if overdue > 0 => {
+                ui.label(
+                    RichText::new(format!(
+                        "OVERDUE: ${} was due on day {}. You're {} days late.",
+                        loan.payment_due(),
+                        due,
+                        overdue
+                    ))
+                    .color(Color32::RED),
+                );
+            }
+            Some(due) if loan.payment_due() > 0 => {
+                ui.label(format!(
+                    "Next payment: ${} by the end of day {}",
+                    loan.payment_due(),
+                    due
                 ));
-                self.message_log
-                    .push("The officer lets you go with a warning.".to_string());
-                return true; // Successful bribe
-            } else {
-                self.message_log.push(format!(
-                    "You offered ${} as a bribe but the officer refused!",
-                    bribe
+            }
+            Some(due) => {
+                ui.label(format!(
+                    "This payment is made. The next one is set after day {}.",
+                    due
                 ));
-                self.message_log
-                    .push("The officer seems offended and decides to arrest you.".to_string());
-                self.confiscate_substances();
-
-                // Additional penalty for failed bribe
-                let fine = rng.gen_range(500..2000).min(self.player.cash);
-                if fine > 0 {
-                    self.player.cash -= fine;
-                    self.message_log.push(format!(
-                        "You were fined an additional ${} for attempted bribery!",
-                        fine
-                    ));
-                }
-
-                return false; // Failed bribe
             }
-        }
-
-        if refuse {
-            self.message_log
-                .push("You refused to offer a bribe.".to_string());
-
-            // 50% chance of getting arrested anyway
-            if rng.gen_bool(0.5) {
-                self.message_log.push(
-                    "The officer decides to search you anyway and finds your stash!".to_string(),
-                );
-                self.confiscate_substances();
-                return false;
-            } else {
-                self.message_log
-                    .push("The officer lets you go with a warning.".to_string());
-                return true;
+            None => {
+                ui.label("You don't owe the loan shark anything.");
             }
         }
 
-        // If we get here, the player hasn't made a decision yet
-        false
+        if loan.barred {
+            ui.label(
+                RichText::new("He won't lend you another cent until your debt is paid off.")
+                    .color(Color32::RED),
+            );
+        } else {
+            ui.label(format!(
+                "Credit limit: ${} (${} available)",
+                self.engine.credit_limit(),
+                self.engine.credit_available()
+            ));
+        }
+        ui.label(format!(
+            "Payments made on time: {}   Late: {}",
+            loan.terms_met, loan.terms_missed
+        ));
     }
 
-    fn render_loan_shark_screen(&mut self, ui: &mut Ui) {
-        ui.heading("Loan Shark");
-        ui.label(format!("Current debt: ${}", self.player.debt));
-        ui.label(format!("Current cash: ${}", self.player.cash));
+    fn render_bank_screen(&mut self, ui: &mut Ui) {
+        ui.heading("Bank");
+        ui.label(format!("Savings: ${}", self.engine.player.bank_balance));
+        ui.label(format!(
+            "Current cash: ${} (${} clean)",
+            self.engine.player.cash,
+            self.engine.player.clean_cash()
+        ));
 
         ui.separator();
 
+        if !self.service_available(ui, Service::Bank) {
+            return;
+        }
+
         ui.horizontal(|ui| {
             ui.label("Amount:");
-            ui.text_edit_singleline(&mut self.amount_to_borrow_repay);
+            ui.text_edit_singleline(&mut self.amount_to_bank);
+            if ui.button("All clean cash").clicked() {
+                self.amount_to_bank = self.engine.player.clean_cash().to_string();
+            }
+            if ui.button("All savings").clicked() {
+                let savings = self.engine.player.bank_balance;
+                let most = self
+                    .engine
+                    .bank_withdrawal_left()
+                    .map_or(savings, |left| left.min(savings));
+                self.amount_to_bank = most.to_string();
+            }
         });
 
-        let amount: i32 = match self.amount_to_borrow_repay.parse() {
-            Ok(num) => num,
-            Err(_) => 0,
-        };
+        let amount: i32 = self.amount_to_bank.parse().unwrap_or(0);
 
-        let can_borrow = amount > 0;
-        let can_repay = amount > 0 && amount <= self.player.cash && amount <= self.player.debt;
+        let deposit_problems = self.engine.check_bank_deposit(amount);
+        let withdraw_problems = self.engine.check_bank_withdraw(amount);
+        let can_deposit = deposit_problems.is_empty();
+        let can_withdraw = withdraw_problems.is_empty();
 
         ui.horizontal(|ui| {
             if ui
-                .add_enabled(can_borrow, egui::Button::new("Borrow"))
+                .add_enabled(can_deposit, egui::Button::new("Deposit"))
                 .clicked()
+                && self.engine.bank_deposit(amount).is_ok()
             {
-                self.player.cash += amount;
-                self.player.debt += amount;
-
-                let message = format!(
-                    "You borrowed ${}, your debt is now ${}",
-                    amount, self.player.debt
-                );
-                self.message_log.push(message);
-
-                self.amount_to_borrow_repay = "0".to_string();
+                self.amount_to_bank = "0".to_string();
             }
 
             if ui
-                .add_enabled(can_repay, egui::Button::new("Repay"))
+                .add_enabled(can_withdraw, egui::Button::new("Withdraw"))
                 .clicked()
+                && self.engine.bank_withdraw(amount).is_ok()
             {
-                self.player.cash -= amount;
-                self.player.debt -= amount;
-
-                let message = format!(
-                    "You repaid ${}, your debt is now ${}",
-                    amount, self.player.debt
-                );
-                self.message_log.push(message);
-
-                self.amount_to_borrow_repay = "0".to_string();
+                self.amount_to_bank = "0".to_string();
             }
         });
 
-        if !can_borrow {
-            ui.label(RichText::new("To borrow, enter a positive amount.").color(Color32::RED));
+        if !can_deposit {
+            ui.label(
+                RichText::new(describe_problems("Cannot deposit: ", &deposit_problems))
+                    .color(Color32::RED),
+            );
         }
 
-        if !can_repay {
-            let mut reason = "Cannot repay: ".to_string();
-            if amount <= 0 {
-                reason.push_str("Amount must be positive. ");
-            }
-            if amount > self.player.cash {
-                reason.push_str("Not enough cash. ");
-            }
-            if amount > self.player.debt {
-                reason.push_str("Amount exceeds debt. ");
-            }
-            ui.label(RichText::new(reason).color(Color32::RED));
+        if !can_withdraw {
+            ui.label(
+                RichText::new(describe_problems("Cannot withdraw: ", &withdraw_problems))
+                    .color(Color32::RED),
+            );
         }
 
         ui.separator();
-        ui.label("Daily interest rate: 10%");
-        ui.label("Your debt increases every time you travel to a new location.");
+        ui.label(format!(
+            "Daily interest on savings: {}%",
+            (self.engine.rules().bank_interest * 1000.0).round() / 10.0
+        ));
+        if let Some(left) = self.engine.bank_withdrawal_left() {
+            ui.label(format!("You can withdraw ${} more today.", left));
+        }
+        ui.label("Savings are safe from muggers and the police.");
+        ui.label("The bank only takes clean money.");
     }
 
     fn render_hospital_screen(&mut self, ui: &mut Ui) {
         ui.heading("Hospital");
-        ui.label(format!("Your health: {}/100", self.player.health));
+        ui.label(format!("Your health: {}/100", self.engine.player.health));
+
+        if !self.service_available(ui, Service::Hospital) {
+            return;
+        }
 
-        if self.player.health >= 100 {
+        if self.engine.player.health >= 100 {
             ui.label("You're in perfect health!");
             return;
         }
 
-        let healing_needed = 100 - self.player.health;
-        let cost_per_point = 50;
-        let total_cost = healing_needed * cost_per_point;
+        let healing_needed = 100 - self.engine.player.health;
+        let total_cost = self.engine.heal_cost();
 
         ui.label(format!(
             "It will cost ${} to fully heal ({} health points at ${} each).",
-            total_cost, healing_needed, cost_per_point
+            total_cost,
+            healing_needed,
+            self.engine.rules().hospital_cost_per_point
         ));
 
-        let can_heal = self.player.cash >= total_cost;
+        let can_heal = self.engine.player.cash >= total_cost;
 
         if !can_heal {
             ui.label(
@@ -1656,11 +1566,7 @@ impl DopeWarsApp {
             .add_enabled(can_heal, egui::Button::new("Get treatment"))
             .clicked()
         {
-            self.player.cash -= total_cost;
-            self.player.health = 100;
-
-            let message = "You've been treated and are now at full health!".to_string();
-            self.message_log.push(message);
+            let _ = self.engine.heal();
         }
     }
 
@@ -1671,7 +1577,7 @@ impl DopeWarsApp {
             .max_height(150.0)
             .stick_to_bottom(true)
             .show(ui, |ui| {
-                for message in &self.message_log {
+                for message in &self.engine.message_log {
                     ui.label(message);
                 }
             });
@@ -1733,33 +1639,489 @@ impl DopeWarsApp {
         });
     }
 
+    fn render_new_game_screen(&mut self, ui: &mut Ui) {
+        ui.heading("New Game");
+        ui.label(format!("Current game seed: {}", self.engine.seed()));
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.label("Your name:");
+            ui.text_edit_singleline(&mut self.player_name);
+        });
+        ui.label("Scores go into the high score table under this name.");
+
+        ui.add_space(10.0);
+
+        ui.label("Enter a seed to replay a shared game, or leave it empty for a random one.");
+        ui.horizontal(|ui| {
+            ui.label("Seed:");
+            ui.text_edit_singleline(&mut self.seed_input);
+        });
+
+        let seed_text = self.seed_input.trim();
+        let seed = if seed_text.is_empty() {
+            Ok(None)
+        } else {
+            seed_text.parse::<u64>().map(Some)
+        };
+
+        if seed.is_err() {
+            ui.label(
+                RichText::new(
+                    "The seed must be a whole number between 0 and 18446744073709551615.",
+                )
+                .color(Color32::RED),
+            );
+        }
+
+        let rule_problems = self.rules.validate().err().unwrap_or_default();
+        for problem in &rule_problems {
+            ui.label(RichText::new(format!("Rules: {}.", problem)).color(Color32::RED));
+        }
+
+        if ui
+            .add_enabled(
+                seed.is_ok() && rule_problems.is_empty(),
+                egui::Button::new("Start New Game"),
+            )
+            .clicked()
+        {
+            self.restart_seed = seed.unwrap_or(None);
+            self.restart_requested = true;
+        }
+
+        ui.separator();
+
+        self.render_rules_editor(ui);
+
+        ui.separator();
+
+        ui.heading("Content Pack");
+        ui.label(format!("New games use: {}", self.content.name));
+        if !self.content.description.is_empty() {
+            ui.label(self.content.description.as_str());
+        }
+        ui.label(format!(
+            "{} locations, {} substances, {} weapons",
+            self.content.locations.len(),
+            self.content.substances.len(),
+            self.content.weapons.len()
+        ));
+
+        ui.horizontal(|ui| {
+            ui.label("Pack file (.toml or .json):");
+            ui.text_edit_singleline(&mut self.content_path_input);
+        });
+
+        ui.horizontal(|ui| {
+            let has_path = !self.content_path_input.trim().is_empty();
+            if ui
+                .add_enabled(has_path, egui::Button::new("Load Content Pack"))
+                .clicked()
+            {
+                let path = PathBuf::from(self.content_path_input.trim());
+                self.content_status = Some(match ContentPack::load(&path) {
+                    Ok(content) => {
+                        let status =
+                            format!("Loaded \"{}\". Start a new game to play it.", content.name);
+                        self.content = content;
+                        Ok(status)
+                    }
+                    Err(e) => Err(format!("Could not load {}: {}", path.display(), e)),
+                });
+            }
+
+            if ui.button("Use Built-in Pack").clicked() {
+                self.content = ContentPack::default();
+                self.content_status = Some(Ok("Using the built-in pack.".to_string()));
+            }
+        });
+
+        match &self.content_status {
+            Some(Ok(status)) => {
+                ui.label(RichText::new(status.as_str()).color(Color32::GREEN));
+            }
+            Some(Err(error)) => {
+                ui.label(RichText::new(error.as_str()).color(Color32::RED));
+            }
+            None => {}
+        }
+
+        ui.with_layout(egui::Layout::bottom_up(egui::Align::Center), |ui| {
+            if ui.button("Return to Main Menu").clicked() {
+                self.current_screen = Screen::Main;
+            }
+        });
+    }
+
+    fn render_rules_editor(&mut self, ui: &mut Ui) {
+        ui.heading("Rules");
+        ui.label("Scores are only comparable between games played under the same rules.");
+
+        ui.horizontal(|ui| {
+            ui.label("Difficulty:");
+            for difficulty in Difficulty::PRESETS {
+                if ui
+                    .selectable_label(self.rules.difficulty == difficulty, difficulty.to_string())
+                    .clicked()
+                {
+                    self.rules = GameRules::preset(difficulty);
+                }
+            }
+            if ui
+                .selectable_label(self.rules.difficulty == Difficulty::Custom, "Custom")
+                .clicked()
+            {
+                // Start from whatever is set now
+                self.rules.difficulty = Difficulty::Custom;
+            }
+        });
+
+        let mut edited = self.rules.clone();
+        egui::Grid::new("rules_grid").num_columns(2).show(ui, |ui| {
+            ui.label("Days:");
+            ui.add(egui::DragValue::new(&mut edited.max_days).clamp_range(1..=365));
+            ui.end_row();
+
+            ui.label("Starting cash:");
+            ui.add(
+                egui::DragValue::new(&mut edited.starting_cash)
+                    .clamp_range(0..=1_000_000)
+                    .prefix("$"),
+            );
+            ui.end_row();
+
+            ui.label("Starting debt:");
+            ui.add(
+                egui::DragValue::new(&mut edited.starting_debt)
+                    .clamp_range(0..=1_000_000)
+                    .prefix("$"),
+            );
+            ui.end_row();
+
+            ui.label("Carrying capacity:");
+            ui.add(egui::DragValue::new(&mut edited.carrying_capacity).clamp_range(1..=10_000));
+            ui.end_row();
+
+            ui.label("Loan shark interest per day:");
+            let mut percent = edited.loan_shark_interest * 100.0;
+            if ui
+                .add(
+                    egui::DragValue::new(&mut percent)
+                        .clamp_range(0.0..=100.0)
+                        .speed(0.5)
+                        .suffix("%"),
+                )
+                .changed()
+            {
+                edited.loan_shark_interest = percent / 100.0;
+            }
+            ui.end_row();
+
+            ui.label("Stash house capacity:");
+            ui.add(egui::DragValue::new(&mut edited.stash_house_capacity).clamp_range(1..=100_000));
+            ui.end_row();
+
+            ui.label("Hospital cost per health point:");
+            ui.add(
+                egui::DragValue::new(&mut edited.hospital_cost_per_point)
+                    .clamp_range(0..=10_000)
+                    .prefix("$"),
+            );
+            ui.end_row();
+
+            ui.label("Fire sale at the end:");
+            ui.checkbox(&mut edited.fire_sale, "Sell everything at a discount");
+            ui.end_row();
+
+            ui.label("Bank interest per day:");
+            let mut percent = edited.bank_interest * 100.0;
+            if ui
+                .add(
+                    egui::DragValue::new(&mut percent)
+                        .clamp_range(0.0..=100.0)
+                        .speed(0.1)
+                        .suffix("%"),
+                )
+                .changed()
+            {
+                edited.bank_interest = percent / 100.0;
+            }
+            ui.end_row();
+
+            ui.label("Bank withdrawals per day:");
+            ui.horizontal(|ui| {
+                let mut limited = edited.bank_withdrawal_limit.is_some();
+                if ui.checkbox(&mut limited, "Limited").changed() {
+                    edited.bank_withdrawal_limit = limited.then_some(5000);
+                }
+                if let Some(limit) = &mut edited.bank_withdrawal_limit {
+                    ui.add(
+                        egui::DragValue::new(limit)
+                            .clamp_range(1..=1_000_000)
+                            .prefix("$"),
+                    );
+                }
+            });
+            ui.end_row();
+
+            ui.label("Laundering fee:");
+            let mut percent = edited.laundering_fee * 100.0;
+            if ui
+                .add(
+                    egui::DragValue::new(&mut percent)
+                        .clamp_range(0.0..=99.0)
+                        .speed(0.5)
+                        .suffix("%"),
+                )
+                .changed()
+            {
+                edited.laundering_fee = percent / 100.0;
+            }
+            ui.end_row();
+
+            ui.label("Laundering per front per day:");
+            ui.add(
+                egui::DragValue::new(&mut edited.laundering_limit)
+                    .clamp_range(1..=1_000_000)
+                    .prefix("$"),
+            );
+            ui.end_row();
+
+            ui.label("Dirty cash penalty:");
+            let mut percent = edited.dirty_cash_penalty * 100.0;
+            if ui
+                .add(
+                    egui::DragValue::new(&mut percent)
+                        .clamp_range(0.0..=100.0)
+                        .speed(0.5)
+                        .suffix("%"),
+                )
+                .on_hover_text("How much of its value unlaundered money loses in the score")
+                .changed()
+            {
+                edited.dirty_cash_penalty = percent / 100.0;
+            }
+            ui.end_row();
+        });
+
+        // Any change to a preset's numbers makes it a custom game
+        if edited != self.rules {
+            edited.difficulty = Difficulty::Custom;
+            self.rules = edited;
+        }
+    }
+
+    fn render_net_worth_screen(&mut self, ui: &mut Ui) {
+        ui.heading("Net Worth");
+        ui.label("What you'd have if you sold up today.");
+
+        ui.add_space(10.0);
+
+        self.render_net_worth_breakdown(ui);
+
+        ui.add_space(10.0);
+
+        ui.label("Stock counts at what selling all of it where it is would fetch today.");
+        ui.label("Weapons count at what a dealer pays for them used.");
+        ui.label(format!(
+            "Dirty cash counts for {}% of what it says until it's laundered.",
+            ((1.0 - self.engine.rules().dirty_cash_penalty) * 100.0).round()
+        ));
+        if self.engine.rules().fire_sale {
+            ui.label(
+                RichText::new(
+                    "These rules end with a fire sale: everything but cash goes at a discount.",
+                )
+                .color(Color32::YELLOW),
+            );
+        }
+
+        ui.with_layout(egui::Layout::bottom_up(egui::Align::Center), |ui| {
+            if ui.button("Return to Main Menu").clicked() {
+                self.current_screen = Screen::Main;
+            }
+        });
+    }
+
+    fn render_net_worth_breakdown(&self, ui: &mut Ui) {
+        let worth = self.engine.net_worth_breakdown();
+        egui::Grid::new("net_worth_grid")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                let rows = [
+                    ("Clean cash", worth.cash),
+                    ("Dirty cash", worth.dirty_cash),
+                    ("Dirty cash penalty", -worth.dirty_cash_penalty),
+                    ("Bank", worth.bank),
+                    ("Debt", -worth.debt),
+                    ("Carried stock", worth.carried_stock),
+                    ("Stashed stock", worth.stashed_stock),
+                    ("Stash houses and fronts", worth.property),
+                    ("Weapons", worth.weapons),
+                ];
+                for (label, amount) in rows {
+                    ui.label(label);
+                    ui.label(format!("${}", amount));
+                    ui.end_row();
+                }
+
+                ui.label(RichText::new("Net worth").strong());
+                ui.label(RichText::new(format!("${}", worth.total())).strong());
+                ui.end_row();
+            });
+    }
+
+    fn render_high_scores_screen(&mut self, ui: &mut Ui) {
+        ui.heading("High Scores");
+        ui.label("Scores are only comparable between games played under the same rules.");
+
+        ui.horizontal(|ui| {
+            ui.label("Show:");
+            if ui
+                .selectable_label(self.high_score_filter.is_none(), "All")
+                .clicked()
+            {
+                self.high_score_filter = None;
+            }
+            for difficulty in Difficulty::PRESETS
+                .into_iter()
+                .chain(std::iter::once(Difficulty::Custom))
+            {
+                if ui
+                    .selectable_label(
+                        self.high_score_filter == Some(difficulty),
+                        difficulty.to_string(),
+                    )
+                    .clicked()
+                {
+                    self.high_score_filter = Some(difficulty);
+                }
+            }
+        });
+
+        ui.separator();
+
+        egui::ScrollArea::vertical()
+            .max_height(ui.available_height() - 40.0)
+            .show(ui, |ui| {
+                self.render_high_score_table(ui, self.high_score_filter, usize::MAX);
+            });
+
+        ui.with_layout(egui::Layout::bottom_up(egui::Align::Center), |ui| {
+            if ui.button("Return to Main Menu").clicked() {
+                self.current_screen = Screen::Main;
+            }
+        });
+    }
+
+    /// The best `limit` scores played under `filter`, with this game's new
+    /// entry picked out.
+    fn render_high_score_table(&self, ui: &mut Ui, filter: Option<Difficulty>, limit: usize) {
+        let shown: Vec<(usize, &HighScore)> = self
+            .high_scores
+            .entries()
+            .iter()
+            .enumerate()
+            .filter(|(_, score)| filter.is_none_or(|d| score.rules.difficulty == d))
+            .take(limit)
+            .collect();
+
+        if shown.is_empty() {
+            ui.label("No scores yet.");
+            return;
+        }
+
+        egui::Grid::new("high_scores_grid")
+            .num_columns(7)
+            .striped(true)
+            .show(ui, |ui| {
+                for title in ["#", "Name", "Date", "Net Worth", "Days", "Seed", "Rules"] {
+                    ui.label(RichText::new(title).strong());
+                }
+                ui.end_row();
+
+                for (rank, (index, score)) in shown.into_iter().enumerate() {
+                    let color = if self.new_high_score == Some(index) {
+                        Color32::YELLOW
+                    } else {
+                        ui.visuals().text_color()
+                    };
+                    let cell = |text: String| RichText::new(text).color(color);
+
+                    ui.label(cell(format!("{}", rank + 1)));
+                    ui.label(cell(score.name.clone()));
+                    ui.label(cell(score.date.format("%Y-%m-%d").to_string()));
+                    ui.label(cell(format!("${}", score.net_worth)));
+                    ui.label(cell(format!("{}", score.days_survived)));
+                    ui.label(cell(format!("{}", score.seed)));
+                    ui.label(cell(score.rules.difficulty.to_string()))
+                        .on_hover_text(score.rules.summary());
+                    ui.end_row();
+                }
+            });
+    }
+
     fn render_price_chart_screen(&mut self, ui: &mut Ui) {
         ui.heading("Price Charts");
 
         ui.horizontal(|ui| {
             ui.label("Select substance:");
 
-            for substance in SUBSTANCES.iter() {
+            for substance in self.substance_names().iter() {
                 let substance_str = substance.to_string();
                 let is_selected = match &self.selected_chart_substance {
                     Some(selected) => selected == &substance_str,
                     None => false,
                 };
 
-                if ui.selectable_label(is_selected, *substance).clicked() {
+                if ui
+                    .selectable_label(is_selected, substance.as_str())
+                    .clicked()
+                {
                     self.selected_chart_substance = Some(substance_str.clone());
                 }
             }
         });
 
+        ui.horizontal(|ui| {
+            ui.label("Select borough:");
+
+            for location in self.location_names().iter() {
+                let is_selected = match &self.selected_chart_location {
+                    Some(selected) => selected == location,
+                    None => *location == self.engine.player.current_location,
+                };
+
+                if ui
+                    .selectable_label(is_selected, location.as_str())
+                    .clicked()
+                {
+                    self.selected_chart_location = Some(location.to_string());
+                }
+            }
+        });
+
+        let location = self
+            .selected_chart_location
+            .clone()
+            .unwrap_or_else(|| self.engine.player.current_location.clone());
+        let local_market = self.engine.market.locations.get(&location);
+
         if let Some(ref substance) = self.selected_chart_substance {
-            let current_price = self.market.prices.get(substance).unwrap_or(&0);
+            let current_price = local_market
+                .and_then(|local| local.prices.get(substance))
+                .unwrap_or(&0);
             ui.label(format!(
-                "Current price of {}: ${}",
-                substance, current_price
+                "Current price of {} in {}: ${}",
+                substance, location, current_price
             ));
 
-            if let Some(price_history) = self.market.price_history.get(substance) {
+            if let Some(price_history) =
+                local_market.and_then(|local| local.price_history.get(substance))
+            {
                 if price_history.is_empty() {
                     ui.label("No price history available yet. Travel to generate price data.");
                 } else {
@@ -1817,7 +2179,7 @@ impl DopeWarsApp {
                         );
 
                         // Draw price labels
-                        let price = max_price - (i as i32) * price_range / 4;
+                        let price = max_price - i * price_range / 4;
                         painter.text(
                             egui::pos2(inner_rect.min.x + 5.0, y - 10.0),
                             egui::Align2::LEFT_CENTER,
@@ -1845,7 +2207,10 @@ impl DopeWarsApp {
                             let day_text = if i == points_count - 1 {
                                 "Now".to_string()
                             } else {
-                                format!("Day {}", self.player.day - (points_count - i - 1) as i32)
+                                format!(
+                                    "Day {}",
+                                    self.engine.player.day - (points_count - i - 1) as i32
+                                )
                             };
 
                             // Draw vertical gridlines
@@ -1911,7 +2276,9 @@ impl DopeWarsApp {
             }
 
             // Trend analysis if we have history
-            if let Some(price_history) = self.market.price_history.get(substance) {
+            if let Some(price_history) =
+                local_market.and_then(|local| local.price_history.get(substance))
+            {
                 if price_history.len() >= 2 {
                     ui.add_space(10.0);
 
@@ -1967,6 +2334,94 @@ impl DopeWarsApp {
                     ui.label(advice);
                 }
             }
+
+            // Compare today's prices across the city
+            ui.add_space(10.0);
+            ui.label(format!("{} across the city today:", substance));
+
+            let city_prices: Vec<(&str, i32)> = self
+                .engine
+                .content()
+                .location_names()
+                .map(|location| (location, self.engine.market.price(location, substance)))
+                .collect();
+            let cheapest = city_prices
+                .iter()
+                .map(|(_, price)| *price)
+                .min()
+                .unwrap_or(0);
+            let dearest = city_prices
+                .iter()
+                .map(|(_, price)| *price)
+                .max()
+                .unwrap_or(0);
+
+            egui::Grid::new("city_prices").striped(true).show(ui, |ui| {
+                for (location, price) in &city_prices {
+                    let here = *location == self.engine.player.current_location;
+                    let name = if here {
+                        format!("{} (you are here)", location)
+                    } else {
+                        location.to_string()
+                    };
+                    let price_text = if *price == cheapest {
+                        RichText::new(format!("${} (cheapest)", price)).color(Color32::GREEN)
+                    } else if *price == dearest {
+                        RichText::new(format!("${} (priciest)", price)).color(Color32::RED)
+                    } else {
+                        RichText::new(format!("${}", price))
+                    };
+
+                    ui.label(name);
+                    ui.label(price_text);
+                    ui.end_row();
+                }
+            });
+        }
+
+        ui.with_layout(egui::Layout::bottom_up(egui::Align::Center), |ui| {
+            if ui.button("Return to Main Menu").clicked() {
+                self.current_screen = Screen::Main;
+            }
+        });
+    }
+
+    fn render_news_screen(&mut self, ui: &mut Ui) {
+        ui.heading("News");
+        ui.label("What's moving prices on the street right now.");
+
+        ui.separator();
+
+        if self.engine.market.events.is_empty() {
+            ui.label("Nothing in the news. Prices are following supply and demand.");
+        } else {
+            egui::Grid::new("news_grid")
+                .striped(true)
+                .spacing([20.0, 6.0])
+                .show(ui, |ui| {
+                    ui.label(RichText::new("Headline").strong());
+                    ui.label(RichText::new("Effect").strong());
+                    ui.label(RichText::new("Lasts").strong());
+                    ui.end_row();
+
+                    for event in &self.engine.market.events {
+                        let effect = if event.magnitude >= 1.0 {
+                            RichText::new(event.effect()).color(Color32::GREEN)
+                        } else {
+                            RichText::new(event.effect()).color(Color32::RED)
+                        };
+                        let lasts = if event.days_remaining == 1 {
+                            "last day".to_string()
+                        } else {
+                            format!("{} more days", event.days_remaining)
+                        };
+
+                        ui.label(event.headline());
+                        ui.label(effect);
+                        ui.label(lasts);
+                        ui.end_row();
+                    }
+                });
         }
 
         ui.with_layout(egui::Layout::bottom_up(egui::Align::Center), |ui| {
@@ -1978,7 +2433,7 @@ impl DopeWarsApp {
 
     fn render_weapons_screen(&mut self, ui: &mut Ui) {
         ui.heading("Weapons Shop");
-        ui.label(format!("Cash: ${}", self.player.cash));
+        ui.label(format!("Cash: ${}", self.engine.player.cash));
 
         ui.separator();
 
@@ -1987,17 +2442,16 @@ impl DopeWarsApp {
 
         ui.separator();
 
-        let mut equip_clicked = false;
         let mut equipped_weapon = None;
 
         // First, show the player's current weapons
         ui.heading("Your Weapons");
-        let has_weapons = self.player.weapons.values().sum::<i32>() > 0;
+        let has_weapons = self.engine.player.weapons.values().sum::<i32>() > 0;
 
         if has_weapons {
-            for (weapon, amount) in &self.player.weapons {
+            for (weapon, amount) in &self.engine.player.weapons {
                 if *amount > 0 {
-                    let is_active = match &self.player.active_weapon {
+                    let is_active = match &self.engine.player.active_weapon {
                         Some(active) => active == weapon,
                         None => false,
                     };
@@ -2005,13 +2459,10 @@ impl DopeWarsApp {
                     ui.horizontal(|ui| {
                         ui.label(format!("{}: {}", weapon, amount));
 
-                        if !is_active {
-                            if ui.button("Equip").clicked() {
-                                equip_clicked = true;
-                                equipped_weapon = Some(weapon.clone());
-                            }
-                        } else {
+                        if is_active {
                             ui.label(RichText::new(" [EQUIPPED]").color(Color32::GREEN));
+                        } else if ui.button("Equip").clicked() {
+                            equipped_weapon = Some(weapon.clone());
                         }
                     });
                 }
@@ -2020,12 +2471,8 @@ impl DopeWarsApp {
             ui.label("You don't have any weapons yet.");
         }
 
-        if equip_clicked && equipped_weapon.is_some() {
-            self.player.active_weapon = equipped_weapon;
-            self.message_log.push(format!(
-                "You equipped a {}.",
-                self.player.active_weapon.as_ref().unwrap()
-            ));
+        if let Some(weapon) = equipped_weapon {
+            let _ = self.engine.equip_weapon(&weapon);
         }
 
         ui.separator();
@@ -2033,91 +2480,153 @@ impl DopeWarsApp {
         // Show weapons for sale
         ui.heading("Weapons For Sale");
 
-        for (i, weapon) in WEAPONS.iter().enumerate() {
-            let price = WEAPON_PRICES[i];
-            let power = WEAPON_POWER[i];
+        for weapon in self.engine.content().weapons.clone() {
+            let price = weapon.price;
+            let power = weapon.power;
+            let weapon = weapon.name;
 
             let is_selected = match &self.selected_weapon {
-                Some(selected) => selected == weapon,
+                Some(selected) => *selected == weapon,
                 None => false,
             };
 
             if ui
-                .selectable_label(
-                    is_selected,
-                    format!("{} - ${} (Power: {})", weapon, price, power),
-                )
+                .selectable_label(
+                    is_selected,
+                    format!("{} - ${} (Power: {})", weapon, price, power),
+                )
+                .clicked()
+            {
+                self.selected_weapon = Some(weapon.to_string());
+            }
+        }
+
+        ui.separator();
+
+        if let Some(weapon) = self.selected_weapon.clone() {
+            let price = self.engine.weapon_price(&weapon).unwrap_or(0);
+            let power = self.engine.weapon_power(&weapon).unwrap_or(0);
+
+            ui.label(format!("Selected: {} - ${}", weapon, price));
+            ui.label(format!("Power Rating: {}/100", power));
+
+            if let Some(def) = self.engine.content().weapon(&weapon) {
+                if !def.description.is_empty() {
+                    ui.label(def.description.as_str());
+                }
+            }
+
+            let gun_shop_here = self.engine.service_here(Service::GunShop);
+            let can_buy = gun_shop_here && self.engine.player.cash >= price;
+
+            if !gun_shop_here {
+                ui.label(
+                    RichText::new(self.engine.where_to_find(Service::GunShop)).color(Color32::RED),
+                );
+            } else if !can_buy {
+                ui.label(RichText::new("You can't afford this weapon.").color(Color32::RED));
+            }
+
+            if ui
+                .add_enabled(can_buy, egui::Button::new("Buy Weapon"))
                 .clicked()
+                && self.engine.buy_weapon(&weapon).is_ok()
             {
-                self.selected_weapon = Some(weapon.to_string());
+                // Clear selection
+                self.selected_weapon = None;
             }
+        } else {
+            ui.label("Select a weapon to purchase");
         }
+    }
+
+    fn render_fronts_screen(&mut self, ui: &mut Ui) {
+        ui.heading("Fronts");
+        ui.label(cash_text(&self.engine));
+
+        ui.separator();
+
+        ui.label("Money from dealing is dirty. The police take it when they arrest you, the bank");
+        ui.label("won't touch it and it counts for less in your net worth.");
+        ui.label("A front business passes it off as takings, for a cut.");
 
         ui.separator();
 
-        if let Some(weapon) = &self.selected_weapon {
-            // Find the weapon index
-            let mut weapon_index = 0;
-            for (i, w) in WEAPONS.iter().enumerate() {
-                if *w == *weapon {
-                    weapon_index = i;
-                    break;
+        ui.heading("Your Fronts");
+        if self.engine.player.fronts.is_empty() {
+            ui.label("You don't own any fronts yet.");
+        } else {
+            for location in self.location_names() {
+                if let Some(front) = self.engine.player.fronts.get(&location) {
+                    ui.label(format!(
+                        "{}: ${} laundered today, ${} in all",
+                        location, front.laundered_today, front.total_laundered
+                    ));
                 }
             }
+        }
 
-            let price = WEAPON_PRICES[weapon_index];
-            let power = WEAPON_POWER[weapon_index];
+        ui.separator();
 
-            ui.label(format!("Selected: {} - ${}", weapon, price));
-            ui.label(format!("Power Rating: {}/100", power));
+        let location = self.engine.player.current_location.clone();
+        ui.heading(format!("Current Location: {}", location));
 
-            match weapon.as_str() {
-                "Knife" => {
-                    ui.label("A basic knife. Better than nothing in a fight.");
-                }
-                "Pistol" => {
-                    ui.label("Standard handgun. Decent stopping power.");
-                }
-                "Shotgun" => {
-                    ui.label("Good for close encounters. Very intimidating.");
-                }
-                "Assault Rifle" => {
-                    ui.label("Military grade. Highly effective but expensive.");
-                }
-                "Grenade" => {
-                    ui.label("Dangerous but extremely effective. One-time use only.");
-                }
-                _ => {}
-            }
+        if self.engine.player.owns_front_in_current_location() {
+            let left = self.engine.laundering_left();
+            ui.label(format!(
+                "Your front here can launder ${} more today, for a {}% cut.",
+                left,
+                (self.engine.rules().laundering_fee * 100.0).round()
+            ));
 
-            let can_buy = self.player.cash >= price;
+            ui.horizontal(|ui| {
+                ui.label("Amount:");
+                ui.text_edit_singleline(&mut self.amount_to_launder);
+                if ui.button("As much as possible").clicked() {
+                    self.amount_to_launder = self.engine.player.dirty_cash.min(left).to_string();
+                }
+            });
 
-            if !can_buy {
-                ui.label(RichText::new("You can't afford this weapon.").color(Color32::RED));
+            let amount: i32 = self.amount_to_launder.parse().unwrap_or(0);
+            let problems = self.engine.check_launder(amount);
+            if problems.is_empty() {
+                let fee = self.engine.laundering_fee(amount);
+                ui.label(format!(
+                    "The front keeps ${} and you get ${} back clean.",
+                    fee,
+                    amount - fee
+                ));
             }
 
             if ui
-                .add_enabled(can_buy, egui::Button::new("Buy Weapon"))
+                .add_enabled(problems.is_empty(), egui::Button::new("Launder"))
                 .clicked()
+                && self.engine.launder(amount).is_ok()
             {
-                self.player.cash -= price;
-                *self.player.weapons.entry(weapon.clone()).or_insert(0) += 1;
+                self.amount_to_launder = "0".to_string();
+            }
 
-                let message = format!("Bought a {} for ${}", weapon, price);
-                self.message_log.push(message);
+            if !problems.is_empty() {
+                ui.label(
+                    RichText::new(describe_problems("Cannot launder: ", &problems))
+                        .color(Color32::RED),
+                );
+            }
+        } else {
+            let price = self.engine.front_price(&location);
+            ui.label(format!("Cost to buy a front in {}: ${}", location, price));
 
-                // If this is the player's first weapon, automatically equip it
-                if self.player.active_weapon.is_none() {
-                    self.player.active_weapon = Some(weapon.clone());
-                    self.message_log
-                        .push(format!("You equipped your new {}.", weapon));
-                }
+            let can_afford = self.engine.player.cash >= price;
+            if !can_afford {
+                ui.label(RichText::new("You can't afford a front here.").color(Color32::RED));
+            }
 
-                // Clear selection
-                self.selected_weapon = None;
+            if ui
+                .add_enabled(can_afford, egui::Button::new("Buy Front"))
+                .clicked()
+            {
+                let _ = self.engine.buy_front();
             }
-        } else {
-            ui.label("Select a weapon to purchase");
         }
     }
 
@@ -2132,43 +2641,52 @@ impl DopeWarsApp {
         ui.separator();
 
         // First check if player has a stash house in the current location
-        let has_stash_in_current_location = self.player.owns_stash_house_in_current_location();
+        let has_stash_in_current_location =
+            self.engine.player.owns_stash_house_in_current_location();
 
         // Show list of owned stash houses
         ui.heading("Your Stash Houses");
 
-        if self.player.stash_houses.is_empty() {
+        if self.engine.player.stash_houses.is_empty() {
             ui.label("You don't own any stash houses yet.");
         } else {
-            for (location, stash) in &self.player.stash_houses {
-                let is_current = *location == self.player.current_location;
+            for (location, stash) in &self.engine.player.stash_houses {
+                let is_current = *location == self.engine.player.current_location;
+                let worth = self.engine.stash_house_sale_price(location).unwrap_or(0);
                 let text = if is_current {
                     RichText::new(format!(
-                        "{}: {}/{} capacity (CURRENT LOCATION)",
+                        "{}: {}/{} capacity, sells for ${} (CURRENT LOCATION)",
                         location,
                         stash.total_items(),
-                        stash.capacity
+                        stash.capacity,
+                        worth
                     ))
                     .color(Color32::GREEN)
                 } else {
                     RichText::new(format!(
-                        "{}: {}/{} capacity",
+                        "{}: {}/{} capacity, sells for ${}",
                         location,
                         stash.total_items(),
-                        stash.capacity
+                        stash.capacity,
+                        worth
                     ))
                 };
 
                 ui.label(text);
+                if !stash.upgrades.is_empty() {
+                    ui.label(format!("    {}", stash.describe_upgrades()));
+                }
             }
         }
 
+        ui.separator();
+        self.render_property_market(ui);
         ui.separator();
 
         // Options for the current location
         ui.heading(format!(
             "Current Location: {}",
-            self.player.current_location
+            self.engine.player.current_location
         ));
 
         if has_stash_in_current_location {
@@ -2185,39 +2703,42 @@ impl DopeWarsApp {
                     self.stash_amount_to_transfer = "0".to_string();
                 }
             });
+
+            ui.separator();
+            self.render_stash_upgrades(ui);
+
+            ui.separator();
+            self.render_stash_house_sale(ui);
         } else {
             // Show purchase option
             let stash_price = self
-                .player
-                .get_stash_house_price(&self.player.current_location);
+                .engine
+                .stash_house_price(&self.engine.player.current_location);
             ui.label(format!(
                 "Cost to buy a stash house in {}: ${}",
-                self.player.current_location, stash_price
+                self.engine.player.current_location, stash_price
             ));
 
-            let can_afford = self.player.cash >= stash_price;
+            let broker_here = self.engine.service_here(Service::StashBroker);
+            let can_afford = self.engine.player.cash >= stash_price;
 
-            if !can_afford {
+            if !broker_here {
+                ui.label(
+                    RichText::new(self.engine.where_to_find(Service::StashBroker))
+                        .color(Color32::RED),
+                );
+            } else if !can_afford {
                 ui.label(RichText::new("You can't afford a stash house here.").color(Color32::RED));
             }
 
             if ui
-                .add_enabled(can_afford, egui::Button::new("Buy Stash House"))
+                .add_enabled(
+                    broker_here && can_afford,
+                    egui::Button::new("Buy Stash House"),
+                )
                 .clicked()
             {
-                self.player.cash -= stash_price;
-
-                // Create new stash house
-                let stash = StashHouse::new(self.player.current_location.clone());
-                self.player
-                    .stash_houses
-                    .insert(self.player.current_location.clone(), stash);
-
-                let message = format!(
-                    "Purchased a stash house in {} for ${}",
-                    self.player.current_location, stash_price
-                );
-                self.message_log.push(message);
+                let _ = self.engine.buy_stash_house();
             }
         }
 
@@ -2228,10 +2749,149 @@ impl DopeWarsApp {
         });
     }
 
+    /// Today's stash house prices in every borough and how they're moving.
+    fn render_property_market(&self, ui: &mut Ui) {
+        ui.heading("Property Market");
+        egui::Grid::new("property_market_grid")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label(RichText::new("Location").strong());
+                ui.label(RichText::new("Stash house").strong());
+                ui.label(RichText::new("Since yesterday").strong());
+                ui.end_row();
+
+                for location in self.location_names() {
+                    ui.label(&location);
+                    ui.label(format!("${}", self.engine.stash_house_price(&location)));
+                    let change = self.engine.property_price_change(&location);
+                    let text = match change {
+                        0 => RichText::new("steady"),
+                        1.. => RichText::new(format!("+{}%", change)).color(Color32::GREEN),
+                        _ => RichText::new(format!("{}%", change)).color(Color32::RED),
+                    };
+                    ui.label(text);
+                    ui.end_row();
+                }
+            });
+    }
+
+    /// Selling the stash house here, and what happens to what's in it.
+    fn render_stash_house_sale(&mut self, ui: &mut Ui) {
+        let location = self.engine.player.current_location.clone();
+        let Some(stash) = self.engine.player.stash_houses.get(&location) else {
+            return;
+        };
+        let price = self.engine.stash_house_sale_price(&location).unwrap_or(0);
+        let left_behind = (stash.total_items() - self.engine.player.space_available()).max(0);
+
+        ui.label(format!(
+            "The broker will pay ${} for this house after a {}% cut, in dirty cash.",
+            price, BROKER_CUT_PERCENT
+        ));
+        if left_behind > 0 {
+            ui.label(
+                RichText::new(format!(
+                    "You can't carry everything in the stash: {} units would go with the house.",
+                    left_behind
+                ))
+                .color(Color32::RED),
+            );
+        }
+
+        let broker_here = self.engine.service_here(Service::StashBroker);
+        if ui
+            .add_enabled(broker_here, egui::Button::new("Sell Stash House"))
+            .on_disabled_hover_text(self.engine.where_to_find(Service::StashBroker))
+            .clicked()
+        {
+            let _ = self.engine.sell_stash_house();
+        }
+    }
+
+    /// What the stash house here has, what it could have and what it costs.
+    fn render_stash_upgrades(&mut self, ui: &mut Ui) {
+        let location = self.engine.player.current_location.clone();
+        let Some(stash) = self.engine.player.stash_houses.get(&location) else {
+            return;
+        };
+
+        ui.heading("Upgrades");
+        ui.label(format!(
+            "Security: {}% of raids stopped, raids take {} points less of the stock, \
+             {}% less chance of the house being seized.",
+            stash.raid_protection(),
+            stash.confiscation_reduction(),
+            stash.seizure_reduction()
+        ));
+
+        let mut bought = None;
+        egui::Grid::new("stash_upgrades_grid")
+            .num_columns(4)
+            .striped(true)
+            .show(ui, |ui| {
+                for upgrade in StashUpgrade::ALL {
+                    ui.label(upgrade.to_string());
+                    ui.label(upgrade.description());
+
+                    let owned = match upgrade {
+                        StashUpgrade::ExtraSpace => format!(
+                            "{}/{} tiers",
+                            stash.upgrade_count(upgrade),
+                            MAX_EXTRA_SPACE_TIERS
+                        ),
+                        StashUpgrade::Guards if stash.has_upgrade(upgrade) => {
+                            format!("Hired, ${} a day", self.engine.guard_wages(&location))
+                        }
+                        _ if stash.has_upgrade(upgrade) => "Fitted".to_string(),
+                        _ => "-".to_string(),
+                    };
+                    ui.label(owned);
+
+                    match self.engine.stash_upgrade_price(&location, upgrade) {
+                        Some(price) => {
+                            let text = match upgrade {
+                                StashUpgrade::ExtraSpace => format!(
+                                    "Add {} units for ${}",
+                                    self.engine.rules().stash_house_capacity * EXTRA_SPACE_PERCENT
+                                        / 100,
+                                    price
+                                ),
+                                StashUpgrade::Guards => format!(
+                                    "Hire for ${} + ${} a day",
+                                    price,
+                                    self.engine.guard_wages(&location)
+                                ),
+                                _ => format!("Buy for ${}", price),
+                            };
+                            if ui
+                                .add_enabled(
+                                    self.engine.player.cash >= price,
+                                    egui::Button::new(text),
+                                )
+                                .on_disabled_hover_text("You can't afford it.")
+                                .clicked()
+                            {
+                                bought = Some(upgrade);
+                            }
+                        }
+                        None => {
+                            ui.label(GameError::UpgradeAlreadyOwned(upgrade).to_string());
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(upgrade) = bought {
+            let _ = self.engine.buy_stash_upgrade(upgrade);
+        }
+    }
+
     fn render_stash_deposit_screen(&mut self, ui: &mut Ui) {
         ui.heading("Deposit Items to Stash House");
 
-        if !self.player.owns_stash_house_in_current_location() {
+        if !self.engine.player.owns_stash_house_in_current_location() {
             ui.label("You don't own a stash house in this location.");
 
             if ui.button("Back").clicked() {
@@ -2242,19 +2902,18 @@ impl DopeWarsApp {
         }
 
         // Copy necessary data to avoid borrow conflicts
-        let current_location = self.player.current_location.clone();
-        let player_total = self.player.total_items();
-        let player_space = self.player.space_available();
+        let current_location = self.engine.player.current_location.clone();
+        let player_total = self.engine.player.total_items();
 
         // Get the stash house for the current location
-        if let Some(stash) = self.player.stash_houses.get_mut(&current_location) {
+        if let Some(stash) = self.engine.player.stash_houses.get(&current_location) {
             let stash_total = stash.total_items();
             let stash_capacity = stash.capacity;
             let stash_space = stash.space_available();
 
             ui.label(format!(
                 "Your personal inventory: {}/{} units",
-                player_total, MAX_CARRYING_CAPACITY
+                player_total, self.engine.player.carrying_capacity
             ));
             ui.label(format!(
                 "Stash house inventory: {}/{} units",
@@ -2264,14 +2923,19 @@ impl DopeWarsApp {
             ui.separator();
 
             // Check if player has anything to deposit
-            let has_inventory = self.player.inventory.values().sum::<i32>() > 0;
+            let has_inventory = player_total > 0;
 
             if !has_inventory {
                 ui.label("You don't have any substances to deposit.");
             } else {
-                for substance in SUBSTANCES.iter() {
+                for substance in self.substance_names().iter() {
                     let substance_str = substance.to_string();
-                    let amount = self.player.inventory.get(&substance_str).unwrap_or(&0);
+                    let amount = self
+                        .engine
+                        .player
+                        .inventory
+                        .get(&substance_str)
+                        .unwrap_or(&0);
 
                     if *amount > 0 {
                         let is_selected = match &self.stash_selected_substance {
@@ -2294,8 +2958,9 @@ impl DopeWarsApp {
 
                 ui.separator();
 
-                if let Some(ref substance) = self.stash_selected_substance {
-                    let current_amount = self.player.inventory.get(substance).unwrap_or(&0);
+                if let Some(substance) = self.stash_selected_substance.clone() {
+                    let current_amount =
+                        *self.engine.player.inventory.get(&substance).unwrap_or(&0);
 
                     ui.label(format!(
                         "Selected: {} - {} units",
@@ -2308,51 +2973,32 @@ impl DopeWarsApp {
                         ui.text_edit_singleline(&mut self.stash_amount_to_transfer);
 
                         if ui.button("Max").clicked() {
-                            let max_amount = std::cmp::min(*current_amount, stash_space);
+                            let max_amount = std::cmp::min(current_amount, stash_space);
                             self.stash_amount_to_transfer = max_amount.to_string();
                         }
                     });
 
-                    let amount: i32 = match self.stash_amount_to_transfer.parse() {
-                        Ok(num) => num,
-                        Err(_) => 0,
-                    };
+                    let amount: i32 = self.stash_amount_to_transfer.parse().unwrap_or(0);
 
-                    let can_deposit =
-                        amount > 0 && amount <= *current_amount && amount <= stash_space;
+                    let problems = self.engine.check_deposit(&substance, amount);
+                    let can_deposit = problems.is_empty();
 
                     if !can_deposit {
-                        let mut reason = "Cannot deposit: ".to_string();
-                        if amount <= 0 {
-                            reason.push_str("Amount must be positive. ");
-                        }
-                        if amount > *current_amount {
-                            reason.push_str("Not enough in your inventory. ");
-                        }
-                        if amount > stash_space {
-                            reason.push_str("Not enough space in stash house. ");
-                        }
-                        ui.label(RichText::new(reason).color(Color32::RED));
+                        ui.label(
+                            RichText::new(describe_problems("Cannot deposit: ", &problems))
+                                .color(Color32::RED),
+                        );
                     }
 
                     if ui
                         .add_enabled(can_deposit, egui::Button::new("Deposit"))
                         .clicked()
+                        && self.engine.deposit(&substance, amount).is_ok()
                     {
-                        // Remove from player inventory
-                        *self.player.inventory.get_mut(substance).unwrap() -= amount;
-
-                        // Add to stash inventory
-                        *stash.inventory.entry(substance.clone()).or_insert(0) += amount;
-
-                        let message =
-                            format!("Deposited {} units of {} to your stash", amount, substance);
-                        self.message_log.push(message);
-
                         self.stash_selected_substance = None;
                         self.stash_amount_to_transfer = "0".to_string();
                     }
-                } else if has_inventory {
+                } else {
                     ui.label("Select a substance to deposit");
                 }
             }
@@ -2368,7 +3014,7 @@ impl DopeWarsApp {
     fn render_stash_withdraw_screen(&mut self, ui: &mut Ui) {
         ui.heading("Withdraw Items from Stash House");
 
-        if !self.player.owns_stash_house_in_current_location() {
+        if !self.engine.player.owns_stash_house_in_current_location() {
             ui.label("You don't own a stash house in this location.");
 
             if ui.button("Back").clicked() {
@@ -2379,18 +3025,18 @@ impl DopeWarsApp {
         }
 
         // Copy necessary data to avoid borrow conflicts
-        let current_location = self.player.current_location.clone();
-        let player_total = self.player.total_items();
-        let player_space = self.player.space_available();
+        let current_location = self.engine.player.current_location.clone();
+        let player_total = self.engine.player.total_items();
+        let player_space = self.engine.player.space_available();
 
         // Get the stash house for the current location
-        if let Some(stash) = self.player.stash_houses.get_mut(&current_location) {
+        if let Some(stash) = self.engine.player.stash_houses.get(&current_location) {
             let stash_total = stash.total_items();
             let stash_capacity = stash.capacity;
 
             ui.label(format!(
                 "Your personal inventory: {}/{} units",
-                player_total, MAX_CARRYING_CAPACITY
+                player_total, self.engine.player.carrying_capacity
             ));
             ui.label(format!(
                 "Stash house inventory: {}/{} units",
@@ -2400,12 +3046,12 @@ impl DopeWarsApp {
             ui.separator();
 
             // Check if stash has anything to withdraw
-            let has_stash_inventory = stash.inventory.values().sum::<i32>() > 0;
+            let has_stash_inventory = stash_total > 0;
 
             if !has_stash_inventory {
                 ui.label("Your stash house is empty.");
             } else {
-                for substance in SUBSTANCES.iter() {
+                for substance in self.substance_names().iter() {
                     let substance_str = substance.to_string();
                     let amount = stash.inventory.get(&substance_str).unwrap_or(&0);
 
@@ -2430,8 +3076,8 @@ impl DopeWarsApp {
 
                 ui.separator();
 
-                if let Some(ref substance) = self.stash_selected_substance {
-                    let stash_amount = stash.inventory.get(substance).unwrap_or(&0);
+                if let Some(substance) = self.stash_selected_substance.clone() {
+                    let stash_amount = *stash.inventory.get(&substance).unwrap_or(&0);
 
                     ui.label(format!("Selected: {} - {} units", substance, stash_amount));
                     ui.label(format!(
@@ -2444,51 +3090,32 @@ impl DopeWarsApp {
                         ui.text_edit_singleline(&mut self.stash_amount_to_transfer);
 
                         if ui.button("Max").clicked() {
-                            let max_amount = std::cmp::min(*stash_amount, player_space);
+                            let max_amount = std::cmp::min(stash_amount, player_space);
                             self.stash_amount_to_transfer = max_amount.to_string();
                         }
                     });
 
-                    let amount: i32 = match self.stash_amount_to_transfer.parse() {
-                        Ok(num) => num,
-                        Err(_) => 0,
-                    };
+                    let amount: i32 = self.stash_amount_to_transfer.parse().unwrap_or(0);
 
-                    let can_withdraw =
-                        amount > 0 && amount <= *stash_amount && amount <= player_space;
+                    let problems = self.engine.check_withdraw(&substance, amount);
+                    let can_withdraw = problems.is_empty();
 
                     if !can_withdraw {
-                        let mut reason = "Cannot withdraw: ".to_string();
-                        if amount <= 0 {
-                            reason.push_str("Amount must be positive. ");
-                        }
-                        if amount > *stash_amount {
-                            reason.push_str("Not enough in stash. ");
-                        }
-                        if amount > player_space {
-                            reason.push_str("Not enough space in your inventory. ");
-                        }
-                        ui.label(RichText::new(reason).color(Color32::RED));
+                        ui.label(
+                            RichText::new(describe_problems("Cannot withdraw: ", &problems))
+                                .color(Color32::RED),
+                        );
                     }
 
                     if ui
                         .add_enabled(can_withdraw, egui::Button::new("Withdraw"))
                         .clicked()
+                        && self.engine.withdraw(&substance, amount).is_ok()
                     {
-                        // Remove from stash inventory
-                        *stash.inventory.get_mut(substance).unwrap() -= amount;
-
-                        // Add to player inventory
-                        *self.player.inventory.entry(substance.clone()).or_insert(0) += amount;
-
-                        let message =
-                            format!("Withdrew {} units of {} from your stash", amount, substance);
-                        self.message_log.push(message);
-
                         self.stash_selected_substance = None;
                         self.stash_amount_to_transfer = "0".to_string();
                     }
-                } else if has_stash_inventory {
+                } else {
                     ui.label("Select a substance to withdraw");
                 }
             }
@@ -2501,62 +3128,304 @@ impl DopeWarsApp {
         }
     }
 
-    fn render_game_over(&mut self, ui: &mut Ui, reason: &str) {
-        ui.vertical_centered(|ui| {
-            ui.heading("GAME OVER");
-            ui.label(reason);
+    fn render_replay_screen(&mut self, ui: &mut Ui) {
+        ui.heading("Replays");
 
-            ui.add_space(20.0);
+        if let Some(Err(error)) = &self.replay_status {
+            ui.label(RichText::new(error.as_str()).color(Color32::RED));
+        }
 
-            ui.label("Final Stats:");
-            ui.label(format!("Days survived: {}", self.player.day));
-            ui.label(format!("Final cash: ${}", self.player.cash));
-            ui.label(format!("Final debt: ${}", self.player.debt));
-            ui.label(format!(
-                "Net worth: ${}",
-                self.player.cash - self.player.debt
-            ));
+        if let Some(viewer) = &mut self.replay_viewer {
+            if Self::render_replay_viewer(ui, viewer) {
+                self.open_replays();
+            }
+            return;
+        }
 
-            ui.add_space(10.0);
+        if let Some(replay) = Replay::from_game(&self.engine) {
+            if ui.button("Watch this game so far").clicked() {
+                self.watch_replay("This game".to_string(), &replay);
+            }
+        }
+
+        ui.separator();
+
+        if self.replay_files.is_empty() {
+            ui.label("No replays yet. Every game is recorded once it ends or is left.");
+        }
+
+        let mut chosen = None;
+        egui::ScrollArea::vertical()
+            .max_height(300.0)
+            .show(ui, |ui| {
+                for path in &self.replay_files {
+                    let name = path
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    ui.horizontal(|ui| {
+                        ui.label(name.as_str());
+                        if ui.button("Watch").clicked() {
+                            chosen = Some((name.clone(), path.clone()));
+                        }
+                    });
+                }
+            });
+
+        if let Some((name, path)) = chosen {
+            match Replay::load(&path) {
+                Ok(replay) => self.watch_replay(name, &replay),
+                Err(e) => {
+                    self.replay_status = Some(Err(format!("Could not load the replay: {}", e)))
+                }
+            }
+        }
+
+        ui.separator();
 
-            ui.label("Final inventory:");
-            for (substance, amount) in &self.player.inventory {
+        ui.horizontal(|ui| {
+            if ui.button("Refresh").clicked() {
+                self.open_replays();
+            }
+            if ui.button("Back").clicked() {
+                self.current_screen = Screen::Main;
+            }
+        });
+    }
+
+    /// Shows one step of a replay. Returns true once the viewer is closed.
+    fn render_replay_viewer(ui: &mut Ui, viewer: &mut ReplayViewer) -> bool {
+        let mut close = false;
+        let last = viewer.frames.len() - 1;
+        ui.label(format!(
+            "{}: step {} of {}",
+            viewer.title, viewer.step, last
+        ));
+
+        ui.horizontal(|ui| {
+            if ui.button("<< Day").clicked() {
+                viewer.previous_day();
+            }
+            if ui.button("< Step").clicked() {
+                viewer.step = viewer.step.saturating_sub(1);
+            }
+            if ui.button("Step >").clicked() {
+                viewer.step = (viewer.step + 1).min(last);
+            }
+            if ui.button("Day >>").clicked() {
+                viewer.next_day();
+            }
+            if ui.button("Close").clicked() {
+                close = true;
+            }
+        });
+        ui.add(egui::Slider::new(&mut viewer.step, 0..=last).text("Step"));
+
+        let game = &viewer.frames[viewer.step];
+        let player = &game.player;
+
+        match viewer.step.checked_sub(1).map(|i| &viewer.actions[i]) {
+            Some(action) => ui.label(RichText::new(action.to_string()).strong()),
+            None => ui.label(RichText::new("Start of the game").strong()),
+        };
+
+        ui.separator();
+
+        ui.columns(2, |columns| {
+            let ui = &mut columns[0];
+            ui.heading("Player");
+            ui.label(format!("Day: {}/{}", player.day, game.rules().max_days));
+            ui.label(format!("Location: {}", player.current_location));
+            ui.label(cash_text(game));
+            ui.label(format!("Debt: ${}", player.debt));
+            ui.label(format!("Bank: ${}", player.bank_balance));
+            ui.label(format!("Health: {}", player.health));
+            ui.label(format!(
+                "Heat: {} ({})",
+                player.heat,
+                player.heat_description()
+            ));
+            ui.label(format!("Net worth: ${}", game.net_worth()));
+            ui.label(format!(
+                "Space: {}/{}",
+                player.total_items(),
+                player.carrying_capacity
+            ));
+            for (substance, amount) in &player.inventory {
                 if *amount > 0 {
-                    ui.label(format!("{} - {} units", substance, amount));
+                    ui.label(format!("{}: {}", substance, amount));
                 }
             }
+            if let Some(weapon) = &player.active_weapon {
+                ui.label(format!("Equipped: {}", weapon));
+            }
+            for (location, stash) in &player.stash_houses {
+                ui.label(format!(
+                    "Stash in {}: {}/{}",
+                    location,
+                    stash.total_items(),
+                    stash.capacity
+                ));
+            }
 
-            ui.add_space(10.0);
+            let ui = &mut columns[1];
+            ui.heading(format!("Market in {}", player.current_location));
+            for substance in game.content().substance_names() {
+                ui.label(format!("{}: ${}", substance, game.local_price(substance)));
+            }
+        });
+
+        ui.separator();
+
+        ui.heading("Message Log");
+        egui::ScrollArea::vertical()
+            .max_height(200.0)
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                for message in &game.message_log {
+                    ui.label(message);
+                }
+            });
+
+        close
+    }
+
+    fn render_game_over(&mut self, ui: &mut Ui, reason: &str) {
+        // The score table can run past the bottom of the window
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.heading("GAME OVER");
+                ui.label(reason);
 
-            if !self.player.stash_houses.is_empty() {
-                ui.label("Stash houses:");
-                for (location, stash) in &self.player.stash_houses {
-                    let total_items = stash.total_items();
-                    if total_items > 0 {
-                        ui.label(format!("{}: {} units", location, total_items));
+                ui.add_space(20.0);
 
-                        for (substance, amount) in &stash.inventory {
-                            if *amount > 0 {
-                                ui.label(format!("  {}: {}", substance, amount));
+                ui.label("Final Stats:");
+                ui.label(format!("Days survived: {}", self.engine.days_survived()));
+                ui.label(format!("Seed: {}", self.engine.seed()));
+                ui.label(format!("Rules: {}", self.engine.rules().summary()))
+                    .on_hover_text("Only compare scores from games played under the same rules.");
+
+                ui.add_space(10.0);
+
+                self.render_net_worth_breakdown(ui);
+                if let Some(proceeds) = self.engine.fire_sale_proceeds() {
+                    ui.label(format!(
+                        "The fire sale turned everything else into ${} of that cash.",
+                        proceeds
+                    ));
+                }
+
+                ui.add_space(10.0);
+
+                ui.label("Final inventory:");
+                for (substance, amount) in &self.engine.player.inventory {
+                    if *amount > 0 {
+                        ui.label(format!("{} - {} units", substance, amount));
+                    }
+                }
+
+                ui.add_space(10.0);
+
+                if !self.engine.player.stash_houses.is_empty() {
+                    ui.label("Stash houses:");
+                    for (location, stash) in &self.engine.player.stash_houses {
+                        let total_items = stash.total_items();
+                        if total_items > 0 {
+                            ui.label(format!("{}: {} units", location, total_items));
+
+                            for (substance, amount) in &stash.inventory {
+                                if *amount > 0 {
+                                    ui.label(format!("  {}: {}", substance, amount));
+                                }
                             }
+                        } else {
+                            ui.label(format!("{}: empty", location));
                         }
-                    } else {
-                        ui.label(format!("{}: empty", location));
+                    }
+
+                    ui.add_space(10.0);
+                }
+
+                let difficulty = self.engine.rules().difficulty;
+                match self.new_high_score {
+                    Some(index) => {
+                        let rank = self.high_scores.entries()[..=index]
+                            .iter()
+                            .filter(|score| score.rules.difficulty == difficulty)
+                            .count();
+                        ui.label(
+                            RichText::new(format!(
+                                "New high score! #{} of {} games.",
+                                rank, difficulty
+                            ))
+                            .color(Color32::YELLOW),
+                        );
+                    }
+                    None => {
+                        ui.label("Not enough for the high score table this time.");
                     }
                 }
+                if let Some(error) = &self.high_score_error {
+                    ui.label(RichText::new(error.as_str()).color(Color32::RED));
+                }
 
                 ui.add_space(10.0);
-            }
 
-            ui.add_space(20.0);
+                ui.label(format!("Top {} scores:", difficulty));
+                self.render_high_score_table(ui, Some(difficulty), 10);
 
-            if ui.button("Play Again").clicked() {
-                self.restart_requested = true;
-            }
+                ui.add_space(10.0);
+
+                match &self.replay_status {
+                    Some(Ok(status)) => {
+                        ui.label(status.as_str());
+                    }
+                    Some(Err(error)) => {
+                        ui.label(RichText::new(error.as_str()).color(Color32::RED));
+                    }
+                    None => {}
+                }
+
+                ui.add_space(20.0);
+
+                if ui.button("Play Again").clicked() {
+                    self.restart_requested = true;
+                }
+
+                if ui.button("Replay This Seed").clicked() {
+                    // Same seed, same city, same rules
+                    self.restart_seed = Some(self.engine.seed());
+                    self.content = self.engine.content().clone();
+                    self.rules = self.engine.rules().clone();
+                    self.restart_requested = true;
+                }
+
+                if let Some(replay) = Replay::from_game(&self.engine) {
+                    if ui.button("Watch Replay").clicked() {
+                        self.watch_replay("This game".to_string(), &replay);
+                    }
+                }
+
+                if ui.button("Other Replays").clicked() {
+                    self.open_replays();
+                }
+            });
         });
     }
 }
 
+/// The content pack given with `--content <file>`, if any.
+fn content_arg() -> Option<PathBuf> {
+    let mut args = std::env::args().skip(1);
+    let mut path = None;
+    while let Some(arg) = args.next() {
+        if arg == "--content" {
+            path = args.next().map(PathBuf::from);
+        }
+    }
+    path
+}
+
 fn main() -> eframe::Result<()> {
     let options = eframe::NativeOptions {
         initial_window_size: Some(egui::vec2(1024.0, 768.0)),
@@ -2568,6 +3437,9 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         "DopeWars GUI",
         options,
-        Box::new(|_cc| Box::new(DopeWarsApp::default())),
+        Box::new(|_cc| {
+            let (content, notice) = ContentPack::startup(content_arg());
+            Box::new(DopeWarsApp::new(content, notice))
+        }),
     )
 }